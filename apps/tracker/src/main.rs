@@ -1,30 +1,254 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use anyhow::Result;
 use axum::serve;
+use clap::{Parser, Subcommand};
+use config::AppConfig;
 use tokio::{net::TcpListener, task};
 
-use config;
-use db::{init_pool, PostgresRepo, WriteData};
-use api::create_router;
-use service::fetchers::take_and_push_transactions;
+use db::feed::TransferFeed;
+use db::{init_pool, AddressLabel, ApiScope, PostgresRepo, ReadData, RiskModel, WriteData};
+use api::{create_router, Auth};
+use service::cctp::run_cctp_indexer;
+use service::fetchers::{backfill_events, take_and_push_transactions};
+use service::risk::{run_scheduled_scoring, score_addresses};
+use service::verify::{run_rolling_verification, verify_range};
+
+#[derive(Parser)]
+#[command(about = "USDC transfer tracker")]
+struct Cli {
+    /// Path to a TOML or YAML config file; defaults to $CONFIG_FILE or ./config.toml.
+    #[arg(long, global = true)]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the indexer and the HTTP API (default).
+    Serve,
+    /// Diff a block range in the database against the chain.
+    Verify {
+        #[arg(long)]
+        from_block: u64,
+        #[arg(long)]
+        to_block: u64,
+        /// Write missing and mismatched rows from chain data and delete extra rows.
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Index admin events (blacklisting, minters, pauses, ownership, upgrades) in a block range,
+    /// e.g. one indexed before they were tracked.
+    Backfill {
+        #[arg(long)]
+        from_block: u64,
+        #[arg(long)]
+        to_block: u64,
+    },
+    /// Score every address's exposure to blacklisted addresses and replace the stored scores.
+    Risk {
+        /// haircut or fifo; defaults to risk.model.
+        #[arg(long)]
+        model: Option<RiskModel>,
+    },
+    /// Manage API keys.
+    Keys {
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+    /// Manage address labels, which leaderboards leave out by default.
+    Labels {
+        #[command(subcommand)]
+        command: LabelsCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum KeysCommand {
+    /// Create a key and print it; it cannot be shown again.
+    Create {
+        #[arg(long)]
+        name: String,
+        /// Comma-separated: read, stream, export, admin.
+        #[arg(long, value_delimiter = ',', required = true)]
+        scopes: Vec<ApiScope>,
+        /// Defaults to auth.default_rate_limit_per_minute.
+        #[arg(long)]
+        rate_limit: Option<u32>,
+        /// Defaults to auth.default_daily_quota.
+        #[arg(long)]
+        daily_quota: Option<u64>,
+    },
+    /// List every key, revoked ones included.
+    List,
+    /// Revoke a key by id.
+    Revoke { id: i64 },
+}
+
+#[derive(Subcommand)]
+enum LabelsCommand {
+    /// Label an address, replacing its current label.
+    Add {
+        address: String,
+        #[arg(long)]
+        label: String,
+        /// e.g. exchange, treasury or bridge; leaderboards can exclude by category.
+        #[arg(long)]
+        category: String,
+    },
+    /// Remove the label of an address.
+    Remove { address: String },
+    /// List labeled addresses.
+    List {
+        #[arg(long)]
+        category: Option<String>,
+    },
+}
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let cfg = config::init().await?;
+    let cli = Cli::parse();
+    let cfg = Arc::new(AppConfig::load(cli.config.as_deref())?);
+    let _tracing = telemetry::logging::init(cfg.logging.format.parse()?, cfg.logging.otlp_endpoint.as_deref())?;
 
-    let pool = init_pool_with_retry(&cfg.db_url, cfg.start_block).await?;
+    let pool = init_pool_with_retry(&cfg.database.url, cfg.chain.start_block).await?;
     let pool = Arc::new(pool);
 
+    match cli.command.unwrap_or(Command::Serve) {
+        Command::Serve => serve_app(cfg, pool).await,
+        Command::Verify { from_block, to_block, repair } => {
+            let report = verify_range(pool, &cfg, from_block, to_block, repair).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            if !report.is_clean() && !report.repaired {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        Command::Backfill { from_block, to_block } => {
+            let stored = backfill_events(pool, &cfg, from_block, to_block).await?;
+            println!("stored {} new events", stored);
+            Ok(())
+        }
+        Command::Risk { model } => {
+            let model = match model {
+                Some(model) => model,
+                None => cfg.risk.model.parse().map_err(anyhow::Error::msg)?,
+            };
+            let report = score_addresses(pool, &cfg, model).await?;
+            println!("{}", serde_json::to_string_pretty(&report)?);
+            Ok(())
+        }
+        Command::Keys { command } => manage_keys(&cfg, pool, command).await,
+        Command::Labels { command } => manage_labels(pool, command).await,
+    }
+}
+
+async fn manage_keys(cfg: &AppConfig, pool: Arc<sqlx::PgPool>, command: KeysCommand) -> Result<()> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    match command {
+        KeysCommand::Create { name, scopes, rate_limit, daily_quota } => {
+            let (new_key, key) = api::auth::new_key(
+                &name,
+                &scopes,
+                rate_limit.unwrap_or(cfg.auth.default_rate_limit_per_minute),
+                daily_quota.unwrap_or(cfg.auth.default_daily_quota),
+            )?;
+            let api_key = repo.insert_api_key(&new_key).await?;
+            let mut created = serde_json::to_value(&api_key)?;
+            created["key"] = key.into();
+            println!("{}", serde_json::to_string_pretty(&created)?);
+        }
+        KeysCommand::List => {
+            println!("{}", serde_json::to_string_pretty(&repo.list_api_keys().await?)?);
+        }
+        KeysCommand::Revoke { id } => {
+            if !repo.revoke_api_key(id).await? {
+                anyhow::bail!("no active key with id {}", id);
+            }
+            println!("revoked key {}", id);
+        }
+    }
+    Ok(())
+}
+
+async fn manage_labels(pool: Arc<sqlx::PgPool>, command: LabelsCommand) -> Result<()> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    match command {
+        LabelsCommand::Add { address, label, category } => {
+            let label = AddressLabel { address: normalize_address(&address)?, label, category };
+            repo.upsert_address_label(&label).await?;
+            println!("{}", serde_json::to_string_pretty(&label)?);
+        }
+        LabelsCommand::Remove { address } => {
+            let address = normalize_address(&address)?;
+            if !repo.delete_address_label(&address).await? {
+                anyhow::bail!("{} has no label", address);
+            }
+            println!("removed the label of {}", address);
+        }
+        LabelsCommand::List { category } => {
+            println!("{}", serde_json::to_string_pretty(&repo.list_all_address_labels(category.as_deref()).await?)?);
+        }
+    }
+    Ok(())
+}
+
+/// Lowercases a 0x-prefixed 20-byte hex address, the form addresses are stored in.
+fn normalize_address(address: &str) -> Result<String> {
+    if address.len() != 42 || !address.starts_with("0x") || !address[2..].chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("'{}' is not a 0x-prefixed 20-byte hex address", address);
+    }
+    Ok(address.to_lowercase())
+}
+
+async fn serve_app(cfg: Arc<AppConfig>, pool: Arc<sqlx::PgPool>) -> Result<()> {
     let tracker_task = {
-        let pool = Arc::clone(&pool);
+        let (cfg, pool) = (Arc::clone(&cfg), Arc::clone(&pool));
         task::spawn(async move {
-            let _ = take_and_push_transactions(pool.clone()).await;
+            if let Err(e) = take_and_push_transactions(pool.clone(), &cfg).await {
+                tracing::error!(error = %e, "tracker stopped");
+            }
         })
     };
 
-    let app = create_router(pool.clone());
-    let addr = format!("0.0.0.0:{}", cfg.server_port);
+    if cfg.verify.last_blocks > 0 {
+        let (cfg, pool) = (Arc::clone(&cfg), Arc::clone(&pool));
+        task::spawn(async move {
+            if let Err(e) = run_rolling_verification(pool, &cfg).await {
+                tracing::error!(error = %e, "rolling verification stopped");
+            }
+        });
+    }
+
+    if cfg.risk.interval_secs > 0 {
+        let (cfg, pool) = (Arc::clone(&cfg), Arc::clone(&pool));
+        task::spawn(async move {
+            if let Err(e) = run_scheduled_scoring(pool, &cfg).await {
+                tracing::error!(error = %e, "scheduled risk scoring stopped");
+            }
+        });
+    }
+
+    for chain in cfg.cctp.chains.clone() {
+        let (cfg, pool) = (Arc::clone(&cfg), Arc::clone(&pool));
+        task::spawn(async move {
+            if let Err(e) = run_cctp_indexer(pool, &cfg, &chain).await {
+                tracing::error!(chain = %chain.name, error = %e, "cctp indexer stopped");
+            }
+        });
+    }
+
+    let feed = TransferFeed::start(&pool).await?;
+    let auth = Auth::start(pool.as_ref().clone(), &cfg.auth);
+    if !cfg.auth.required {
+        tracing::warn!("auth.required is off; requests without an API key are served unmetered, except admin routes");
+    }
+    let app = create_router(pool.clone(), cfg.clone(), feed, auth.clone());
+    let addr = format!("0.0.0.0:{}", cfg.server.port);
     let listener = TcpListener::bind(&addr).await?;
+    tracing::info!(%addr, "api listening");
 
     tokio::select! {
         _ = serve(listener, app.into_make_service()) => {},
@@ -32,6 +256,7 @@ async fn main() -> Result<()> {
         _ = tokio::signal::ctrl_c() => {},
     }
 
+    auth.flush().await;
     Ok(())
 }
 
@@ -42,10 +267,13 @@ async fn init_pool_with_retry(db_url: &str, start_block: u64) -> Result<sqlx::Pg
     let mut attempts = 0;
 
     while attempts < MAX_RETRIES {
-        if let Ok(pool) = init_pool(db_url).await {
-            let repo = PostgresRepo::new(pool.clone());
-            repo.update_sync_state_if_needs(start_block).await?;
-            return Ok(pool);
+        match init_pool(db_url).await {
+            Ok(pool) => {
+                let repo = PostgresRepo::new(pool.clone());
+                repo.update_sync_state_if_needs(start_block).await?;
+                return Ok(pool);
+            }
+            Err(e) => tracing::warn!(error = %e, attempt = attempts + 1, "database not ready"),
         }
         attempts += 1;
         sleep(Duration::from_secs(2)).await;
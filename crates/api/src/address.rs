@@ -0,0 +1,111 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{AddressFlows, PgPool, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::Counterparty;
+use crate::extract::{ApiPath, ApiQuery};
+use crate::openapi::{BadRequest, Internal, NotFound, Unavailable};
+use crate::validate::normalize_address;
+use crate::version::ApiVersion;
+
+pub(crate) const DEFAULT_COUNTERPARTIES: u32 = 10;
+pub(crate) const MAX_COUNTERPARTIES: u32 = 100;
+
+/// Optional window for flows and counterparties, at hourly resolution. Balance and first/last
+/// seen blocks are always all-time.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProfileQuery {
+    /// Start of the window, rounded down to the hour.
+    since: Option<DateTime<Utc>>,
+    /// Exclusive end of the window.
+    until: Option<DateTime<Utc>>,
+    /// Number of top counterparties, 1 to 100 (default 10).
+    counterparties: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressProfile {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    address: String,
+    #[schema(example = "52013.250000")]
+    balance: String,
+    first_seen_block: i64,
+    last_seen_block: i64,
+    since: Option<DateTime<Utc>>,
+    until: Option<DateTime<Utc>>,
+    inflow: String,
+    outflow: String,
+    transfers_in: i64,
+    transfers_out: i64,
+    top_counterparties: Vec<Counterparty>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/address/{address}",
+    tag = "analytics",
+    params(("address" = String, Path, description = "0x-prefixed address"), ProfileQuery),
+    responses(
+        (status = 200, description = "Balance, flows and top counterparties", body = AddressProfile),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_address_profile(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiPath(address): ApiPath<String>,
+    ApiQuery(query): ApiQuery<ProfileQuery>,
+) -> AppResult<Json<AddressProfile>> {
+    let address = normalize_address("address", &address)?;
+    if let (Some(since), Some(until)) = (query.since, query.until)
+        && since >= until {
+        return Err(AppError::BadRequest("since must be earlier than until".to_string()));
+    }
+    if query.counterparties == Some(0) {
+        return Err(AppError::BadRequest("counterparties must be at least 1".to_string()));
+    }
+    let limit = query.counterparties.unwrap_or(DEFAULT_COUNTERPARTIES).min(MAX_COUNTERPARTIES);
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let stats = repo
+        .get_address_stats(&address)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("no USDC transfers involve {}", address)))?;
+
+    let flows = if query.since.is_none() && query.until.is_none() {
+        AddressFlows {
+            inflow: stats.total_in,
+            outflow: stats.total_out,
+            transfers_in: stats.transfers_in,
+            transfers_out: stats.transfers_out,
+        }
+    } else {
+        repo.get_address_flows(&address, query.since, query.until).await?
+    };
+    let top_counterparties = repo
+        .list_top_counterparties(&address, query.since, query.until, limit)
+        .await?;
+
+    Ok(Json(AddressProfile {
+        address: version.address(&stats.address),
+        balance: version.amount(stats.balance),
+        first_seen_block: stats.first_block,
+        last_seen_block: stats.last_block,
+        since: query.since,
+        until: query.until,
+        inflow: version.amount(flows.inflow),
+        outflow: version.amount(flows.outflow),
+        transfers_in: flows.transfers_in,
+        transfers_out: flows.transfers_out,
+        top_counterparties: top_counterparties.iter().map(|c| Counterparty::new(c, version)).collect(),
+    }))
+}
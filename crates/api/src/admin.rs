@@ -0,0 +1,156 @@
+use axum::{extract::State, http::StatusCode, Json};
+use chrono::{Days, NaiveDate, Utc};
+use common::{AppError, AppResult};
+use db::{ApiScope, PostgresRepo, ReadData, WriteData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::auth;
+use crate::dto::{ApiKey, KeyUsage};
+use crate::extract::{ApiJson, ApiPath, ApiQuery};
+use crate::openapi::{BadRequest, Internal, NotFound, Unavailable};
+use crate::AppState;
+
+const MAX_USAGE_DAYS: u64 = 366;
+
+#[derive(Deserialize, ToSchema)]
+pub struct CreateKey {
+    #[schema(example = "acme-dashboard")]
+    name: String,
+    #[schema(example = json!(["read", "stream"]))]
+    scopes: Vec<ApiScope>,
+    /// Defaults to `auth.default_rate_limit_per_minute`.
+    rate_limit_per_minute: Option<u32>,
+    /// Requests per UTC day; defaults to `auth.default_daily_quota`.
+    daily_quota: Option<u64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct CreatedKey {
+    #[serde(flatten)]
+    api_key: ApiKey,
+    /// The key itself. It is not stored and cannot be shown again.
+    #[schema(example = "usdc_3f9a1c2e5b7d90a4c6e8f1b3d5a7c9e0f2b4d6a8c0e1f3a5")]
+    key: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UsageQuery {
+    /// First UTC day, inclusive (default: `to`).
+    from: Option<NaiveDate>,
+    /// Last UTC day, inclusive (default: today). At most 366 days after `from`.
+    to: Option<NaiveDate>,
+    /// Only this key.
+    key_id: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct UsageReport {
+    from: NaiveDate,
+    to: NaiveDate,
+    items: Vec<KeyUsage>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/admin/keys",
+    tag = "admin",
+    responses(
+        (status = 200, description = "Every key, revoked ones included", body = Vec<ApiKey>),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_keys(State(state): State<AppState>) -> AppResult<Json<Vec<ApiKey>>> {
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let keys = repo.list_api_keys().await?;
+    Ok(Json(keys.into_iter().map(ApiKey::from).collect()))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/admin/keys",
+    tag = "admin",
+    request_body = CreateKey,
+    responses(
+        (status = 201, description = "The new key; `key` is only returned here", body = CreatedKey),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn create_key(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<CreateKey>,
+) -> AppResult<(StatusCode, Json<CreatedKey>)> {
+    let defaults = &state.config.auth;
+    let (new_key, key) = auth::new_key(
+        &request.name,
+        &request.scopes,
+        request.rate_limit_per_minute.unwrap_or(defaults.default_rate_limit_per_minute),
+        request.daily_quota.unwrap_or(defaults.default_daily_quota),
+    )?;
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let api_key = repo.insert_api_key(&new_key).await?;
+    tracing::info!(key_id = api_key.id, name = %api_key.name, "api key created");
+    Ok((StatusCode::CREATED, Json(CreatedKey { api_key: api_key.into(), key })))
+}
+
+/// Takes effect immediately on this instance and within `auth.key_cache_secs` on others.
+#[utoipa::path(
+    delete,
+    path = "/v1/admin/keys/{id}",
+    tag = "admin",
+    params(("id" = i64, Path, description = "Key id")),
+    responses(
+        (status = 204, description = "Revoked"),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn revoke_key(State(state): State<AppState>, ApiPath(id): ApiPath<i64>) -> AppResult<StatusCode> {
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    if !repo.revoke_api_key(id).await? {
+        return Err(AppError::NotFound(format!("no active key with id {}", id)));
+    }
+    state.auth.forget(id);
+    tracing::info!(key_id = id, "api key revoked");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Daily request counts per key for billing. Counts from other instances lag by up to
+/// `auth.usage_flush_secs`.
+#[utoipa::path(
+    get,
+    path = "/v1/admin/usage",
+    tag = "admin",
+    params(UsageQuery),
+    responses(
+        (status = 200, description = "Usage rows by day, then key; days without requests are omitted", body = UsageReport),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn usage(
+    State(state): State<AppState>,
+    ApiQuery(query): ApiQuery<UsageQuery>,
+) -> AppResult<Json<UsageReport>> {
+    let to = query.to.unwrap_or_else(|| Utc::now().date_naive());
+    let from = query.from.unwrap_or(to);
+    if from > to {
+        return Err(AppError::BadRequest("from must not be after to".to_string()));
+    }
+    if from.checked_add_days(Days::new(MAX_USAGE_DAYS)).is_some_and(|limit| to > limit) {
+        return Err(AppError::BadRequest(format!("at most {} days can be requested at once", MAX_USAGE_DAYS)));
+    }
+
+    state.auth.flush().await;
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let usage = repo.list_api_key_usage(from, to, query.key_id).await?;
+    let items = usage.into_iter().map(KeyUsage::from).collect();
+    Ok(Json(UsageReport { from, to, items }))
+}
@@ -0,0 +1,602 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::{Query, Request, State},
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{AppError, AppResult};
+use config::AuthConfig;
+use db::{ApiKey, ApiScope, NewApiKey, PgPool, PostgresRepo, ReadData, UsageDelta, WriteData};
+use sha2::{Digest, Sha256};
+use tokio::time::MissedTickBehavior;
+use tracing::warn;
+
+/// Starts every key so leaked ones are easy to recognise and scan for.
+const KEY_TAG: &str = "usdc_";
+const SECRET_BYTES: usize = 24;
+/// Characters of a key kept in `api_keys.prefix`.
+const PREFIX_LEN: usize = KEY_TAG.len() + 8;
+pub const API_KEY_HEADER: &str = "x-api-key";
+/// Query parameter for clients that cannot set headers, i.e. browsers' EventSource and WebSocket.
+pub const API_KEY_PARAM: &str = "api_key";
+/// A full bucket holds this many seconds of a key's rate limit.
+const BURST_SECS: f64 = 10.0;
+/// Past this many cached lookups, expired ones are dropped before another is added.
+const MAX_CACHED_KEYS: usize = 10_000;
+/// Same for hashes that matched no key, kept apart so a client sending random keys only
+/// evicts its own misses.
+const MAX_CACHED_UNKNOWN: usize = 10_000;
+const MAX_NAME_LEN: usize = 100;
+const MAX_RATE_LIMIT: u32 = 100_000;
+const MAX_DAILY_QUOTA: u64 = 1_000_000_000;
+
+/// Validates the limits of a new key and generates its secret. Returns the row to insert and
+/// the key to hand out, which is not stored anywhere.
+pub fn new_key(
+    name: &str,
+    scopes: &[ApiScope],
+    rate_limit_per_minute: u32,
+    daily_quota: u64,
+) -> AppResult<(NewApiKey, String)> {
+    let name = name.trim();
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(AppError::BadRequest(format!("name must be 1 to {} characters", MAX_NAME_LEN)));
+    }
+    if scopes.is_empty() {
+        return Err(AppError::BadRequest("a key needs at least one scope".to_string()));
+    }
+    if !(1..=MAX_RATE_LIMIT).contains(&rate_limit_per_minute) {
+        return Err(AppError::BadRequest(format!("rate_limit_per_minute must be 1 to {}", MAX_RATE_LIMIT)));
+    }
+    if !(1..=MAX_DAILY_QUOTA).contains(&daily_quota) {
+        return Err(AppError::BadRequest(format!("daily_quota must be 1 to {}", MAX_DAILY_QUOTA)));
+    }
+
+    let mut bytes = [0u8; SECRET_BYTES];
+    getrandom::fill(&mut bytes).map_err(|e| AppError::Unknown(format!("no randomness for a key: {}", e)))?;
+    let secret = format!("{}{}", KEY_TAG, hex::encode(bytes));
+    let mut scopes = scopes.to_vec();
+    scopes.sort();
+    scopes.dedup();
+
+    let key = NewApiKey {
+        name: name.to_string(),
+        prefix: secret[..PREFIX_LEN].to_string(),
+        key_hash: hash(&secret),
+        scopes,
+        rate_limit_per_minute: rate_limit_per_minute as i32,
+        daily_quota: daily_quota as i64,
+    };
+    Ok((key, secret))
+}
+
+fn hash(secret: &str) -> String {
+    hex::encode(Sha256::digest(secret.as_bytes()))
+}
+
+/// Resolves API keys and meters their use. Lookups are cached for `key_cache_secs`, so a
+/// revocation made by another instance takes up to that long to apply. Rate limits are kept
+/// per instance; request counts are written to `api_key_usage` every `usage_flush_secs`, so
+/// quotas shared between instances can overshoot by what the others served in that window.
+#[derive(Clone)]
+pub struct Auth {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    pool: PgPool,
+    required: bool,
+    key_ttl: Duration,
+    keys: Mutex<KeyCache>,
+    meter: Mutex<Meter>,
+}
+
+/// Lookups by key hash with when they were made. Hashes that matched no active key are
+/// cached too, so unknown keys are not looked up on every request either.
+#[derive(Default)]
+struct KeyCache {
+    known: HashMap<String, (Arc<ApiKey>, Instant)>,
+    unknown: HashMap<String, Instant>,
+}
+
+impl KeyCache {
+    fn get(&self, key_hash: &str, ttl: Duration, now: Instant) -> Option<Option<Arc<ApiKey>>> {
+        let fresh = |fetched_at: &Instant| now.saturating_duration_since(*fetched_at) < ttl;
+        if let Some((key, fetched_at)) = self.known.get(key_hash) {
+            return fresh(fetched_at).then(|| Some(key.clone()));
+        }
+        self.unknown.get(key_hash).filter(|fetched_at| fresh(fetched_at)).map(|_| None)
+    }
+
+    fn insert(&mut self, key_hash: String, key: Option<Arc<ApiKey>>, ttl: Duration, now: Instant) {
+        match key {
+            Some(key) => {
+                make_room(&mut self.known, MAX_CACHED_KEYS, |(_, fetched_at)| *fetched_at, ttl, now);
+                self.unknown.remove(&key_hash);
+                self.known.insert(key_hash, (key, now));
+            }
+            None => {
+                make_room(&mut self.unknown, MAX_CACHED_UNKNOWN, |fetched_at| *fetched_at, ttl, now);
+                self.known.remove(&key_hash);
+                self.unknown.insert(key_hash, now);
+            }
+        }
+    }
+}
+
+/// Drops expired entries once `map` is full, and everything if none had expired.
+fn make_room<V>(
+    map: &mut HashMap<String, V>,
+    max: usize,
+    fetched_at: impl Fn(&V) -> Instant,
+    ttl: Duration,
+    now: Instant,
+) {
+    if map.len() < max {
+        return;
+    }
+    map.retain(|_, value| now.saturating_duration_since(fetched_at(value)) < ttl);
+    if map.len() >= max {
+        map.clear();
+    }
+}
+
+#[derive(Default)]
+struct Meter {
+    buckets: HashMap<i64, Bucket>,
+    /// Requests per key already recorded in Postgres for the day, as of the last read or flush.
+    recorded: HashMap<i64, (NaiveDate, i64)>,
+    /// Counts not yet written to Postgres.
+    pending: HashMap<(i64, NaiveDate), Pending>,
+}
+
+struct Bucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+#[derive(Clone, Copy, Default)]
+struct Pending {
+    requests: i64,
+    rejected: i64,
+}
+
+enum Refusal {
+    RateLimited { retry_after: u64 },
+    QuotaExhausted { retry_after: u64 },
+}
+
+impl Meter {
+    fn used(&self, key_id: i64, day: NaiveDate) -> i64 {
+        let recorded = match self.recorded.get(&key_id) {
+            Some((recorded_day, requests)) if *recorded_day == day => *requests,
+            _ => 0,
+        };
+        recorded + self.pending.get(&(key_id, day)).map_or(0, |p| p.requests)
+    }
+
+    fn record(&mut self, key_id: i64, day: NaiveDate, requests: i64) {
+        match self.recorded.get(&key_id) {
+            Some((recorded_day, _)) if *recorded_day > day => {}
+            _ => {
+                self.recorded.insert(key_id, (day, requests));
+            }
+        }
+    }
+
+    /// `now` drives the rate limit and `at` (its wall-clock time) the daily quota.
+    fn admit(&mut self, key: &ApiKey, now: Instant, at: DateTime<Utc>) -> Result<(), Refusal> {
+        let day = at.date_naive();
+        if self.used(key.id, day) >= key.daily_quota {
+            self.pending.entry((key.id, day)).or_default().rejected += 1;
+            return Err(Refusal::QuotaExhausted { retry_after: seconds_until_next_day(at) });
+        }
+
+        let per_sec = key.rate_limit_per_minute as f64 / 60.0;
+        let capacity = (per_sec * BURST_SECS).max(1.0);
+        let bucket = self
+            .buckets
+            .entry(key.id)
+            .or_insert(Bucket { tokens: capacity, updated_at: now });
+        let elapsed = now.saturating_duration_since(bucket.updated_at).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_sec).min(capacity);
+        bucket.updated_at = now;
+
+        let pending = self.pending.entry((key.id, day)).or_default();
+        if bucket.tokens < 1.0 {
+            pending.rejected += 1;
+            let retry_after = ((1.0 - bucket.tokens) / per_sec).ceil() as u64;
+            return Err(Refusal::RateLimited { retry_after: retry_after.max(1) });
+        }
+        bucket.tokens -= 1.0;
+        pending.requests += 1;
+        Ok(())
+    }
+}
+
+impl Auth {
+    /// Also starts the task that writes usage counters to Postgres.
+    pub fn start(pool: PgPool, config: &AuthConfig) -> Self {
+        let auth = Self {
+            inner: Arc::new(Inner {
+                pool,
+                required: config.required,
+                key_ttl: Duration::from_secs(config.key_cache_secs),
+                keys: Mutex::new(KeyCache::default()),
+                meter: Mutex::new(Meter::default()),
+            }),
+        };
+
+        let flusher = auth.clone();
+        let period = Duration::from_secs(config.usage_flush_secs);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                flusher.flush().await;
+            }
+        });
+        auth
+    }
+
+    /// Layer state for routes that need a key with `scope`.
+    pub fn guard(&self, scope: ApiScope) -> Guard {
+        Guard { auth: self.clone(), scope }
+    }
+
+    /// Writes pending usage counters; on failure they are kept for the next attempt.
+    pub async fn flush(&self) {
+        let pending = std::mem::take(&mut self.inner.meter.lock().unwrap().pending);
+        if pending.is_empty() {
+            return;
+        }
+        let deltas: Vec<UsageDelta> = pending
+            .iter()
+            .map(|(&(key_id, day), p)| UsageDelta { key_id, day, requests: p.requests, rejected: p.rejected })
+            .collect();
+
+        let repo = PostgresRepo::new(self.inner.pool.clone());
+        let result = repo.add_api_key_usage(&deltas).await;
+        let mut meter = self.inner.meter.lock().unwrap();
+        match result {
+            Ok(totals) => {
+                for (key_id, day, requests) in totals {
+                    meter.record(key_id, day, requests);
+                }
+            }
+            Err(e) => {
+                warn!(error = %e, keys = deltas.len(), "failed to record API key usage; will retry");
+                for (slot, p) in pending {
+                    let entry = meter.pending.entry(slot).or_default();
+                    entry.requests += p.requests;
+                    entry.rejected += p.rejected;
+                }
+            }
+        }
+    }
+
+    /// Drops a cached key so a revocation applies here immediately.
+    pub fn forget(&self, key_id: i64) {
+        self.inner
+            .keys
+            .lock()
+            .unwrap()
+            .known
+            .retain(|_, (key, _)| key.id != key_id);
+    }
+
+    async fn resolve(&self, secret: &str) -> AppResult<Option<Arc<ApiKey>>> {
+        let key_hash = hash(secret);
+        let cached = self.inner.keys.lock().unwrap().get(&key_hash, self.inner.key_ttl, Instant::now());
+        if let Some(key) = cached {
+            return Ok(key);
+        }
+
+        let repo = PostgresRepo::new(self.inner.pool.clone());
+        let key = repo.get_active_api_key(&key_hash).await?.map(Arc::new);
+        if let Some(key) = &key {
+            let today = Utc::now().date_naive();
+            let requests = repo.get_api_key_requests(key.id, today).await?;
+            self.inner.meter.lock().unwrap().record(key.id, today, requests);
+        }
+
+        self.inner
+            .keys
+            .lock()
+            .unwrap()
+            .insert(key_hash, key.clone(), self.inner.key_ttl, Instant::now());
+        Ok(key)
+    }
+
+    fn admit(&self, key: &ApiKey) -> Result<(), Refusal> {
+        self.inner
+            .meter
+            .lock()
+            .unwrap()
+            .admit(key, Instant::now(), Utc::now())
+    }
+}
+
+fn seconds_until_next_day(now: DateTime<Utc>) -> u64 {
+    let midnight = now
+        .date_naive()
+        .succ_opt()
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|t| t.and_utc());
+    midnight.map_or(1, |midnight| (midnight - now).num_seconds().max(1) as u64)
+}
+
+#[derive(Clone)]
+pub struct Guard {
+    auth: Auth,
+    scope: ApiScope,
+}
+
+/// Middleware admitting requests whose key holds the guard's scope and is within its rate
+/// limit and daily quota. The key is added to the request extensions for handlers.
+pub async fn authorize(State(guard): State<Guard>, mut request: Request, next: Next) -> Response {
+    let presented = presented_key(&request, guard.scope);
+    match guard.check(presented).await {
+        Ok(Some(key)) => {
+            request.extensions_mut().insert(key);
+            next.run(request).await
+        }
+        Ok(None) => next.run(request).await,
+        Err(response) => response,
+    }
+}
+
+impl Guard {
+    async fn check(&self, presented: Option<String>) -> Result<Option<Arc<ApiKey>>, Response> {
+        let Some(secret) = presented else {
+            // Key management stays behind a key even when anonymous reads are allowed.
+            if !self.auth.inner.required && self.scope != ApiScope::Admin {
+                return Ok(None);
+            }
+            return Err(refuse(
+                "missing",
+                AppError::Unauthorized(
+                    "an API key is required; send it as `Authorization: Bearer <key>` or `X-API-Key: <key>`"
+                        .to_string(),
+                ),
+            ));
+        };
+        let key = match self.auth.resolve(&secret).await {
+            Ok(Some(key)) => key,
+            Ok(None) => {
+                return Err(refuse("invalid", AppError::Unauthorized("unknown or revoked API key".to_string())));
+            }
+            Err(e) => return Err(e.into_response()),
+        };
+        if !key.allows(self.scope) {
+            return Err(refuse(
+                "scope",
+                AppError::Forbidden(format!("this key lacks the '{}' scope", self.scope.as_str())),
+            ));
+        }
+
+        match self.auth.admit(&key) {
+            Ok(()) => Ok(Some(key)),
+            Err(Refusal::RateLimited { retry_after }) => Err(retry_later(
+                refuse(
+                    "rate_limited",
+                    AppError::TooManyRequests(format!(
+                        "rate limit of {} requests per minute exceeded",
+                        key.rate_limit_per_minute
+                    )),
+                ),
+                retry_after,
+            )),
+            Err(Refusal::QuotaExhausted { retry_after }) => Err(retry_later(
+                refuse(
+                    "quota",
+                    AppError::TooManyRequests(format!(
+                        "daily quota of {} requests used up; it resets at 00:00 UTC",
+                        key.daily_quota
+                    )),
+                ),
+                retry_after,
+            )),
+        }
+    }
+}
+
+/// The key from `Authorization: Bearer`, `X-API-Key` or, for streams only, the `api_key`
+/// query parameter; query strings end up in access logs, so other routes do not accept it.
+fn presented_key(request: &Request, scope: ApiScope) -> Option<String> {
+    let headers = request.headers();
+    if let Some(value) = headers.get(header::AUTHORIZATION).and_then(|v| v.to_str().ok())
+        && let Some((scheme, token)) = value.split_once(' ')
+        && scheme.eq_ignore_ascii_case("bearer")
+    {
+        return Some(token.trim().to_string());
+    }
+    if let Some(value) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(value.trim().to_string());
+    }
+    if scope != ApiScope::Stream {
+        return None;
+    }
+    Query::<HashMap<String, String>>::try_from_uri(request.uri())
+        .ok()
+        .and_then(|Query(mut params)| params.remove(API_KEY_PARAM))
+}
+
+fn refuse(reason: &str, error: AppError) -> Response {
+    telemetry::metrics::observe_api_key_rejection(reason);
+    let unauthorized = matches!(error, AppError::Unauthorized(_));
+    let mut response = error.into_response();
+    if unauthorized {
+        response
+            .headers_mut()
+            .insert(header::WWW_AUTHENTICATE, HeaderValue::from_static("Bearer"));
+    }
+    response
+}
+
+fn retry_later(mut response: Response, seconds: u64) -> Response {
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(seconds));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn key(id: i64, rate_limit_per_minute: i32, daily_quota: i64) -> ApiKey {
+        ApiKey {
+            id,
+            name: "test".to_string(),
+            prefix: "usdc_00000000".to_string(),
+            scopes: vec![ApiScope::Read],
+            rate_limit_per_minute,
+            daily_quota,
+            created_at: Utc::now(),
+            revoked_at: None,
+        }
+    }
+
+    fn at(day: u32, hour: u32, min: u32, sec: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2025, 11, day, hour, min, sec).unwrap()
+    }
+
+    fn retry_after(result: Result<(), Refusal>) -> (&'static str, u64) {
+        match result {
+            Ok(()) => ("admitted", 0),
+            Err(Refusal::RateLimited { retry_after }) => ("rate", retry_after),
+            Err(Refusal::QuotaExhausted { retry_after }) => ("quota", retry_after),
+        }
+    }
+
+    fn anonymous_auth() -> Auth {
+        Auth {
+            inner: Arc::new(Inner {
+                pool: db::PgPoolOptions::new().connect_lazy("postgres://localhost/unused").unwrap(),
+                required: false,
+                key_ttl: Duration::from_secs(30),
+                keys: Mutex::new(KeyCache::default()),
+                meter: Mutex::new(Meter::default()),
+            }),
+        }
+    }
+
+    #[tokio::test]
+    async fn admin_scope_needs_a_key_even_when_keys_are_optional() {
+        let auth = anonymous_auth();
+
+        for scope in [ApiScope::Read, ApiScope::Stream, ApiScope::Export] {
+            assert!(matches!(auth.guard(scope).check(None).await, Ok(None)));
+        }
+        let refused = auth.guard(ApiScope::Admin).check(None).await.expect_err("refused");
+        assert_eq!(refused.status(), axum::http::StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn bucket_allows_a_burst_then_refills_at_the_rate() {
+        // 15 per minute: 0.25 tokens a second, 2.5 in a full bucket.
+        let key = key(1, 15, 1_000);
+        let mut meter = Meter::default();
+        let start = Instant::now();
+        let now = at(3, 12, 0, 0);
+
+        assert!(meter.admit(&key, start, now).is_ok());
+        assert!(meter.admit(&key, start, now).is_ok());
+        // 0.5 tokens left; the next one is 2s away.
+        assert_eq!(retry_after(meter.admit(&key, start, now)), ("rate", 2));
+        assert_eq!(retry_after(meter.admit(&key, start + Duration::from_secs(1), now)), ("rate", 1));
+        assert!(meter.admit(&key, start + Duration::from_secs(2), now).is_ok());
+
+        // A long pause refills no further than the burst.
+        let later = start + Duration::from_secs(3_600);
+        assert!(meter.admit(&key, later, now).is_ok());
+        assert!(meter.admit(&key, later, now).is_ok());
+        assert_eq!(retry_after(meter.admit(&key, later, now)), ("rate", 2));
+
+        let day = now.date_naive();
+        assert_eq!(meter.used(key.id, day), 5);
+        assert_eq!(meter.pending[&(key.id, day)].rejected, 3);
+    }
+
+    #[test]
+    fn retry_after_is_at_least_a_second() {
+        // 600 per minute: a token every 0.1s.
+        let key = key(1, 600, 1_000);
+        let mut meter = Meter::default();
+        let start = Instant::now();
+        for _ in 0..100 {
+            assert!(meter.admit(&key, start, at(3, 12, 0, 0)).is_ok());
+        }
+        assert_eq!(retry_after(meter.admit(&key, start, at(3, 12, 0, 0))), ("rate", 1));
+    }
+
+    #[test]
+    fn quota_is_exhausted_until_midnight_utc_and_rolls_over() {
+        let key = key(1, 6_000, 3);
+        let mut meter = Meter::default();
+        let start = Instant::now();
+
+        for i in 0..3 {
+            assert!(meter.admit(&key, start + Duration::from_secs(i), at(3, 23, 0, 0)).is_ok());
+        }
+        assert_eq!(retry_after(meter.admit(&key, start + Duration::from_secs(3), at(3, 23, 0, 0))), ("quota", 3_600));
+        assert_eq!(retry_after(meter.admit(&key, start + Duration::from_secs(4), at(3, 23, 59, 59))), ("quota", 1));
+
+        assert!(meter.admit(&key, start + Duration::from_secs(5), at(4, 0, 0, 1)).is_ok());
+        assert_eq!(meter.used(key.id, at(4, 0, 0, 0).date_naive()), 1);
+        assert_eq!(meter.pending[&(key.id, at(3, 0, 0, 0).date_naive())].rejected, 2);
+    }
+
+    #[test]
+    fn quota_counts_requests_recorded_by_other_instances() {
+        let key = key(1, 6_000, 10);
+        let mut meter = Meter::default();
+        let day = at(3, 0, 0, 0).date_naive();
+
+        meter.record(key.id, day, 9);
+        assert!(meter.admit(&key, Instant::now(), at(3, 8, 0, 0)).is_ok());
+        assert_eq!(retry_after(meter.admit(&key, Instant::now(), at(3, 8, 0, 0))).0, "quota");
+
+        // A late flush for the previous day does not replace today's count.
+        meter.record(key.id, day.pred_opt().unwrap(), 1);
+        assert_eq!(meter.used(key.id, day), 10);
+        // Nor does yesterday's count apply today.
+        assert_eq!(meter.used(key.id, day.succ_opt().unwrap()), 0);
+    }
+
+    #[test]
+    fn unknown_keys_do_not_evict_known_ones() {
+        let ttl = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut cache = KeyCache::default();
+        cache.insert("known".to_string(), Some(Arc::new(key(1, 60, 100))), ttl, now);
+
+        for i in 0..=MAX_CACHED_UNKNOWN {
+            cache.insert(format!("random-{}", i), None, ttl, now);
+        }
+
+        assert!(cache.unknown.len() <= MAX_CACHED_UNKNOWN);
+        assert_eq!(cache.get("known", ttl, now).flatten().map(|k| k.id), Some(1));
+        assert!(matches!(cache.get(&format!("random-{}", MAX_CACHED_UNKNOWN), ttl, now), Some(None)));
+    }
+
+    #[test]
+    fn cached_lookups_expire_after_the_ttl() {
+        let ttl = Duration::from_secs(60);
+        let now = Instant::now();
+        let mut cache = KeyCache::default();
+        cache.insert("known".to_string(), Some(Arc::new(key(1, 60, 100))), ttl, now);
+        cache.insert("unknown".to_string(), None, ttl, now);
+
+        assert!(cache.get("known", ttl, now + Duration::from_secs(59)).is_some());
+        assert!(cache.get("known", ttl, now + ttl).is_none());
+        assert!(cache.get("unknown", ttl, now + ttl).is_none());
+        assert!(cache.get("never", ttl, now).is_none());
+    }
+}
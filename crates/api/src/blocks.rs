@@ -0,0 +1,112 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::{Block, Transfer};
+use crate::extract::{ApiPath, ApiQuery};
+use crate::openapi::{BadRequest, Internal, NotFound, Unavailable};
+use crate::version::ApiVersion;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Closest {
+    /// Latest block at or before `ts`.
+    Before,
+    /// Earliest block at or after `ts`.
+    After,
+    /// Whichever of the two is closer in time; the earlier one on a tie.
+    #[default]
+    Nearest,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BlockByTimeQuery {
+    #[param(example = "2023-11-14T22:15:00Z")]
+    ts: DateTime<Utc>,
+    /// Defaults to `nearest`.
+    closest: Option<Closest>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BlockWithTransfers {
+    #[serde(flatten)]
+    block: Block,
+    /// Every USDC transfer in the block, by log index.
+    transfers: Vec<Transfer>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/block/{number}",
+    tag = "blocks",
+    params(("number" = i64, Path, description = "Block number")),
+    responses(
+        (status = 200, description = "The block header and its transfers", body = BlockWithTransfers),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_block(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiPath(number): ApiPath<i64>,
+) -> AppResult<Json<BlockWithTransfers>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let block = repo
+        .get_block(number)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("block {} has no indexed USDC transfers", number)))?;
+    let transfers = repo.list_transfers_in_blocks(number as u64, number as u64).await?;
+    Ok(Json(BlockWithTransfers {
+        block: Block::from(block),
+        transfers: Transfer::list(&transfers, version),
+    }))
+}
+
+/// Only blocks with USDC transfers are stored, so on a quiet chain the result can be a few
+/// blocks away from the true closest one. Timestamps outside the indexed range are not
+/// resolved to the first or last indexed block.
+#[utoipa::path(
+    get,
+    path = "/v1/block/by-time",
+    tag = "blocks",
+    params(BlockByTimeQuery),
+    responses(
+        (status = 200, description = "The block closest to `ts` in the requested direction", body = Block),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_block_by_time(
+    State(pool): State<Arc<PgPool>>,
+    ApiQuery(query): ApiQuery<BlockByTimeQuery>,
+) -> AppResult<Json<Block>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let ts = query.ts;
+    let (block, relation) = match query.closest.unwrap_or_default() {
+        Closest::Before => (repo.get_block_at_or_before(ts).await?, "at or before"),
+        Closest::After => (repo.get_block_at_or_after(ts).await?, "at or after"),
+        Closest::Nearest => {
+            let block = match repo.get_block_at_or_before(ts).await? {
+                Some(before) if before.block_time == ts => Some(before),
+                Some(before) => repo.get_block_at_or_after(ts).await?.map(|after| {
+                    if after.block_time - ts < ts - before.block_time { after } else { before }
+                }),
+                None => None,
+            };
+            (block, "on both sides of")
+        }
+    };
+    let block = block.ok_or_else(|| AppError::NotFound(format!("no indexed block {} {}", relation, ts.to_rfc3339())))?;
+    Ok(Json(Block::from(block)))
+}
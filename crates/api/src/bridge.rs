@@ -0,0 +1,156 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{BridgeStatus, BridgeTransferQuery, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::cursor;
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::validate::normalize_address;
+use crate::version::ApiVersion;
+use crate::AppState;
+
+const DEFAULT_PAGE_LIMIT: u32 = 20;
+const MAX_PAGE_LIMIT: u32 = 100;
+/// Names this list in its cursors.
+const CURSOR_LIST: &str = "bridge";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BridgeTransferFilter {
+    status: Option<BridgeStatus>,
+    /// CCTP domain of the burn, e.g. 0 for Ethereum.
+    source_domain: Option<u32>,
+    destination_domain: Option<u32>,
+    depositor: Option<String>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+    /// Page size, 1 to 100 (default 20).
+    limit: Option<u32>,
+}
+
+/// One side of a CCTP transfer.
+#[derive(Serialize, ToSchema)]
+pub struct BridgeLeg {
+    /// Name of the chain in `cctp.chains`.
+    #[schema(example = "ethereum")]
+    chain: String,
+    domain: i64,
+    tx_hash: String,
+    block_number: i64,
+    block_time: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BridgeTransfer {
+    /// Assigned by the source MessageTransmitter; unique per source domain.
+    nonce: i64,
+    status: BridgeStatus,
+    #[schema(example = "25000.000000")]
+    amount: String,
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    depositor: String,
+    /// bytes32, left-padded on chains with 20-byte addresses.
+    mint_recipient: String,
+    burn_token: String,
+    /// The `DepositForBurn`.
+    source: BridgeLeg,
+    destination_domain: i64,
+    /// Whether the destination domain is indexed; a pending burn to one that is not stays
+    /// pending.
+    destination_indexed: bool,
+    /// The `MessageReceived` that minted the USDC; null while pending.
+    destination: Option<BridgeLeg>,
+    /// Seconds from the burn to the mint.
+    latency_secs: Option<i64>,
+    /// Seconds since the burn, while pending.
+    pending_secs: Option<i64>,
+}
+
+/// Latest burns first. `next_cursor` is null on the last page.
+#[derive(Serialize, ToSchema)]
+pub struct BridgeTransferPage {
+    items: Vec<BridgeTransfer>,
+    next_cursor: Option<String>,
+}
+
+/// CCTP burns on the chains in `cctp.chains`, each matched by source domain and nonce to the
+/// `MessageReceived` that minted it on the destination chain.
+#[utoipa::path(
+    get,
+    path = "/v1/bridge/transfers",
+    tag = "bridge",
+    params(BridgeTransferFilter),
+    responses(
+        (status = 200, description = "One page of CCTP transfers, latest burns first", body = BridgeTransferPage),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_bridge_transfers(
+    State(state): State<AppState>,
+    version: ApiVersion,
+    ApiQuery(filter): ApiQuery<BridgeTransferFilter>,
+) -> AppResult<Json<BridgeTransferPage>> {
+    if filter.limit == Some(0) {
+        return Err(AppError::BadRequest("limit must be at least 1".to_string()));
+    }
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let query = BridgeTransferQuery {
+        status: filter.status,
+        source_domain: filter.source_domain,
+        destination_domain: filter.destination_domain,
+        depositor: filter.depositor.as_deref().map(|a| normalize_address("depositor", a)).transpose()?,
+        before: filter.cursor.as_deref().map(|c| cursor::decode_time_position(c, CURSOR_LIST)).transpose()?,
+        // One extra row tells whether there is a next page.
+        limit: limit + 1,
+    };
+
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let mut transfers = repo.list_bridge_transfers(&query).await?;
+    let next_cursor = if transfers.len() > limit as usize {
+        transfers.truncate(limit as usize);
+        transfers.last().map(|last| cursor::encode_position(CURSOR_LIST, last.source_block_time.timestamp(), last.id))
+    } else {
+        None
+    };
+
+    let now = Utc::now();
+    let items = transfers
+        .into_iter()
+        .map(|t| {
+            let destination = match (t.destination_chain, t.destination_tx_hash, t.destination_block_number, t.destination_block_time) {
+                (Some(chain), Some(tx_hash), Some(block_number), Some(block_time)) => {
+                    Some(BridgeLeg { chain, domain: t.destination_domain, tx_hash, block_number, block_time })
+                }
+                _ => None,
+            };
+            let latency_secs = destination.as_ref().map(|d| (d.block_time - t.source_block_time).num_seconds());
+            BridgeTransfer {
+                nonce: t.nonce,
+                status: if destination.is_some() { BridgeStatus::Matched } else { BridgeStatus::Pending },
+                amount: version.amount(t.amount),
+                depositor: version.address(&t.depositor),
+                mint_recipient: t.mint_recipient,
+                burn_token: version.address(&t.burn_token),
+                source: BridgeLeg {
+                    chain: t.source_chain,
+                    domain: t.source_domain,
+                    tx_hash: t.source_tx_hash,
+                    block_number: t.source_block_number,
+                    block_time: t.source_block_time,
+                },
+                destination_domain: t.destination_domain,
+                destination_indexed: state.config.cctp.chains.iter().any(|c| i64::from(c.domain) == t.destination_domain),
+                pending_secs: destination.is_none().then(|| (now - t.source_block_time).num_seconds()),
+                destination,
+                latency_secs,
+            }
+        })
+        .collect();
+
+    Ok(Json(BridgeTransferPage { items, next_cursor }))
+}
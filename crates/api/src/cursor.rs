@@ -0,0 +1,186 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{SortOrder, TransferCursor, TransferSort, UsdcTransfer};
+
+/// Encodes the position of `transfer` in the given order as an opaque, URL-safe cursor.
+pub fn encode(transfer: &UsdcTransfer, sort: TransferSort, order: SortOrder) -> String {
+    let position = match sort {
+        TransferSort::Time => format!("{}:{}", transfer.block_number, transfer.log_index),
+        TransferSort::Amount => format!("{}:{}:{}", transfer.amount, transfer.block_number, transfer.log_index),
+    };
+    URL_SAFE_NO_PAD.encode(format!("{}:{}", label(sort, order), position))
+}
+
+/// Decodes a cursor, rejecting ones issued for a different sort or order.
+pub fn decode(value: &str, sort: TransferSort, order: SortOrder) -> AppResult<TransferCursor> {
+    let invalid = || AppError::BadRequest(format!("cursor is not valid: '{}'", value));
+    let raw = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+    let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+    let mut parts = raw.split(':');
+
+    let issued_for = parts.next().ok_or_else(invalid)?;
+    if issued_for != label(sort, order) {
+        return Err(AppError::BadRequest(format!(
+            "cursor was issued for sort '{}', not '{}'",
+            issued_for,
+            label(sort, order)
+        )));
+    }
+    let amount = match sort {
+        TransferSort::Time => None,
+        TransferSort::Amount => Some(parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?),
+    };
+    let block_number = parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    let log_index = parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(TransferCursor { amount, block_number, log_index })
+}
+
+/// Encodes a position such as `(block_number, log_index)` for a list that is always latest
+/// first. `list` names the endpoint, so its cursors are not accepted by another one.
+pub fn encode_position(list: &str, first: i64, second: i64) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}:{}:{}", list, first, second))
+}
+
+/// Decodes a cursor of `encode_position`, rejecting ones issued for another list.
+pub fn decode_position(value: &str, list: &str) -> AppResult<(i64, i64)> {
+    let invalid = || AppError::BadRequest(format!("cursor is not valid: '{}'", value));
+    let raw = URL_SAFE_NO_PAD.decode(value).map_err(|_| invalid())?;
+    let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+    let mut parts = raw.split(':');
+
+    let issued_for = parts.next().ok_or_else(invalid)?;
+    if issued_for != list {
+        return Err(AppError::BadRequest(format!("cursor was issued for '{}', not '{}'", issued_for, list)));
+    }
+    let first = parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    let second = parts.next().ok_or_else(invalid)?.parse().map_err(|_| invalid())?;
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok((first, second))
+}
+
+/// Decodes a cursor of `encode_position` whose first part is a unix timestamp in seconds.
+pub fn decode_time_position(value: &str, list: &str) -> AppResult<(DateTime<Utc>, i64)> {
+    let (secs, second) = decode_position(value, list)?;
+    // Up to 9999-12-31T23:59:59Z, well inside what Postgres can compare against.
+    let time = DateTime::from_timestamp(secs, 0)
+        .filter(|_| (0..=253_402_300_799).contains(&secs))
+        .ok_or_else(|| AppError::BadRequest(format!("cursor is not valid: '{}'", value)))?;
+    Ok((time, second))
+}
+
+fn label(sort: TransferSort, order: SortOrder) -> &'static str {
+    match (sort, order) {
+        (TransferSort::Time, SortOrder::Asc) => "time.asc",
+        (TransferSort::Time, SortOrder::Desc) => "time.desc",
+        (TransferSort::Amount, SortOrder::Asc) => "amount.asc",
+        (TransferSort::Amount, SortOrder::Desc) => "amount.desc",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    const SORTS: [(TransferSort, SortOrder); 4] = [
+        (TransferSort::Time, SortOrder::Asc),
+        (TransferSort::Time, SortOrder::Desc),
+        (TransferSort::Amount, SortOrder::Asc),
+        (TransferSort::Amount, SortOrder::Desc),
+    ];
+
+    fn transfer() -> UsdcTransfer {
+        UsdcTransfer {
+            id: 7,
+            tx_hash: format!("0x{}", "ab".repeat(32)),
+            log_index: 12,
+            block_number: 21_000_000,
+            from_address: format!("0x{}", "11".repeat(20)),
+            to_address: format!("0x{}", "22".repeat(20)),
+            amount: Decimal::new(1_250_500_000, 6),
+            block_time: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn bad_request(result: AppResult<impl std::fmt::Debug>) -> String {
+        match result {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn round_trips_every_sort_and_order() {
+        for (sort, order) in SORTS {
+            let cursor = decode(&encode(&transfer(), sort, order), sort, order).unwrap();
+            let amount = (sort == TransferSort::Amount).then(|| Decimal::new(1_250_500_000, 6));
+            assert_eq!(cursor, TransferCursor { amount, block_number: 21_000_000, log_index: 12 });
+        }
+    }
+
+    #[test]
+    fn rejects_a_cursor_of_another_sort_or_order() {
+        for (issued_sort, issued_order) in SORTS {
+            let value = encode(&transfer(), issued_sort, issued_order);
+            for (sort, order) in SORTS {
+                if (sort, order) != (issued_sort, issued_order) {
+                    assert!(bad_request(decode(&value, sort, order)).starts_with("cursor was issued for sort"));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_cursors() {
+        let (sort, order) = (TransferSort::Time, SortOrder::Desc);
+        for raw in ["time.desc:21000000", "time.desc:21000000:12:3", "time.desc:x:12", "time.desc:21000000:", "time.desc"] {
+            let value = URL_SAFE_NO_PAD.encode(raw);
+            assert!(bad_request(decode(&value, sort, order)).starts_with("cursor is not valid"), "{}", raw);
+        }
+        assert!(bad_request(decode("not base64!", sort, order)).starts_with("cursor is not valid"));
+        let value = URL_SAFE_NO_PAD.encode([0xff, 0xfe]);
+        assert!(bad_request(decode(&value, sort, order)).starts_with("cursor is not valid"));
+
+        let value = URL_SAFE_NO_PAD.encode("amount.asc:ten:21000000:12");
+        assert!(bad_request(decode(&value, TransferSort::Amount, SortOrder::Asc)).starts_with("cursor is not valid"));
+    }
+
+    #[test]
+    fn position_round_trips_within_its_list() {
+        assert_eq!(decode_position(&encode_position("events", 21_000_000, 12), "events").unwrap(), (21_000_000, 12));
+        assert_eq!(decode_position(&encode_position("events", -1, 0), "events").unwrap(), (-1, 0));
+    }
+
+    #[test]
+    fn position_rejects_other_lists_and_malformed_values() {
+        let value = encode_position("events", 21_000_000, 12);
+        assert_eq!(bad_request(decode_position(&value, "bridge")), "cursor was issued for 'events', not 'bridge'");
+        // A `/tx` cursor is not a position.
+        let value = encode(&transfer(), TransferSort::Time, SortOrder::Desc);
+        assert!(bad_request(decode_position(&value, "events")).starts_with("cursor was issued for 'time.desc'"));
+
+        for raw in ["events:1", "events:1:2:3", "events:1:x", "events"] {
+            let value = URL_SAFE_NO_PAD.encode(raw);
+            assert!(bad_request(decode_position(&value, "events")).starts_with("cursor is not valid"), "{}", raw);
+        }
+        assert!(bad_request(decode_position("%%%", "events")).starts_with("cursor is not valid"));
+    }
+
+    #[test]
+    fn time_position_is_range_checked() {
+        let (time, id) = decode_time_position(&encode_position("bridge", 1_700_000_000, 42), "bridge").unwrap();
+        assert_eq!((time.timestamp(), id), (1_700_000_000, 42));
+
+        for secs in [-1, 253_402_300_800, i64::MAX] {
+            let value = encode_position("bridge", secs, 42);
+            assert!(bad_request(decode_time_position(&value, "bridge")).starts_with("cursor is not valid"), "{}", secs);
+        }
+    }
+}
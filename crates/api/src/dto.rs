@@ -0,0 +1,344 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use db::{AdminEventKind, ApiScope, UsdcTransfer};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::version::ApiVersion;
+
+/// Public shape of a transfer. Rows are converted rather than serialized so a schema migration
+/// cannot change the JSON; addresses and amounts are formatted for the route's [`ApiVersion`].
+#[derive(Serialize, ToSchema)]
+pub struct Transfer {
+    #[schema(example = 1024)]
+    pub id: i64,
+    #[schema(example = "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060")]
+    pub tx_hash: String,
+    #[schema(example = 3)]
+    pub log_index: i64,
+    #[schema(example = 18573214)]
+    pub block_number: i64,
+    /// EIP-55 checksummed; the zero address for mints.
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    pub from_address: String,
+    /// EIP-55 checksummed; the zero address for burns.
+    #[schema(example = "0xA9D1e08C7793af67e9d92fe308d5697FB81d3E43")]
+    pub to_address: String,
+    /// USDC with six decimals.
+    #[schema(example = "1250.500000")]
+    pub amount: String,
+    pub block_time: DateTime<Utc>,
+    /// When the transfer was indexed.
+    pub created_at: DateTime<Utc>,
+}
+
+impl Transfer {
+    pub fn new(row: &UsdcTransfer, version: ApiVersion) -> Self {
+        Self {
+            id: row.id,
+            tx_hash: row.tx_hash.clone(),
+            log_index: row.log_index,
+            block_number: row.block_number,
+            from_address: version.address(&row.from_address),
+            to_address: version.address(&row.to_address),
+            amount: version.amount(row.amount),
+            block_time: row.block_time,
+            created_at: row.created_at,
+        }
+    }
+
+    pub fn list(rows: &[UsdcTransfer], version: ApiVersion) -> Vec<Self> {
+        rows.iter().map(|row| Self::new(row, version)).collect()
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Counterparty {
+    #[schema(example = "0xA9D1e08C7793af67e9d92fe308d5697FB81d3E43")]
+    pub address: String,
+    /// Sent by the profiled address to this counterparty.
+    #[schema(example = "515.500000")]
+    pub sent: String,
+    /// Received by the profiled address from this counterparty.
+    #[schema(example = "3.500000")]
+    pub received: String,
+    #[schema(example = "519.000000")]
+    pub volume: String,
+    pub transfers: i64,
+}
+
+impl Counterparty {
+    pub fn new(row: &db::Counterparty, version: ApiVersion) -> Self {
+        Self {
+            address: version.address(&row.address),
+            sent: version.amount(row.sent),
+            received: version.amount(row.received),
+            volume: version.amount(row.volume),
+            transfers: row.transfers,
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Holder {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    pub address: String,
+    #[schema(example = "52013.250000")]
+    pub balance: String,
+    pub label: Option<String>,
+}
+
+impl Holder {
+    pub fn new(row: &db::Holder, version: ApiVersion) -> Self {
+        Self {
+            address: version.address(&row.address),
+            balance: version.amount(row.balance),
+            label: row.label.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Mover {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    pub address: String,
+    pub sent: String,
+    pub received: String,
+    /// Received minus sent; negative for net senders.
+    #[schema(example = "-1200.000000")]
+    pub net: String,
+    pub label: Option<String>,
+}
+
+impl Mover {
+    pub fn new(row: &db::Mover, version: ApiVersion) -> Self {
+        Self {
+            address: version.address(&row.address),
+            sent: version.amount(row.sent),
+            received: version.amount(row.received),
+            net: version.amount(row.net),
+            label: row.label.clone(),
+        }
+    }
+}
+
+/// Everything one address sent another over the requested window.
+#[derive(Serialize, ToSchema)]
+pub struct GraphEdge {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    pub from_address: String,
+    #[schema(example = "0xA9D1e08C7793af67e9d92fe308d5697FB81d3E43")]
+    pub to_address: String,
+    #[schema(example = "1250.500000")]
+    pub volume: String,
+    pub transfers: i64,
+}
+
+impl GraphEdge {
+    pub fn new(row: &db::GraphEdge, version: ApiVersion) -> Self {
+        Self {
+            from_address: version.address(&row.from_address),
+            to_address: version.address(&row.to_address),
+            volume: version.amount(row.volume),
+            transfers: row.transfers,
+        }
+    }
+}
+
+/// An administrative event of the USDC contract.
+#[derive(Serialize, ToSchema)]
+pub struct AdminEvent {
+    #[schema(example = "0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060")]
+    pub tx_hash: String,
+    pub log_index: i64,
+    #[schema(example = 18573214)]
+    pub block_number: i64,
+    pub block_time: DateTime<Utc>,
+    pub kind: AdminEventKind,
+    /// The (un)blacklisted account, the minter, the new owner, admin or role holder, or the new
+    /// implementation; null for `pause` and `unpause`.
+    #[schema(example = "0x7F367cC41522cE07553e823bf3be79A889DEbe1B")]
+    pub account: Option<String>,
+    /// The previous owner or admin.
+    pub previous_account: Option<String>,
+    /// The allowance set by `minter_configured`.
+    #[schema(example = "50000000.000000")]
+    pub amount: Option<String>,
+}
+
+impl AdminEvent {
+    pub fn new(row: &db::AdminEvent, version: ApiVersion) -> Self {
+        Self {
+            tx_hash: row.tx_hash.clone(),
+            log_index: row.log_index,
+            block_number: row.block_number,
+            block_time: row.block_time,
+            kind: row.kind,
+            account: row.account.as_deref().map(|a| version.address(a)),
+            previous_account: row.previous_account.as_deref().map(|a| version.address(a)),
+            amount: row.amount.map(|a| version.amount(a)),
+        }
+    }
+}
+
+/// Taint received from one blacklisted address.
+#[derive(Serialize, ToSchema)]
+pub struct RiskPath {
+    #[schema(example = "0x7F367cC41522cE07553e823bf3be79A889DEbe1B")]
+    pub source: String,
+    #[schema(example = "1250.500000")]
+    pub amount: String,
+    pub hops: i32,
+    /// Addresses from the source to the scored address, both included.
+    pub path: Vec<String>,
+}
+
+impl RiskPath {
+    pub fn new(row: &db::RiskPath, version: ApiVersion) -> Self {
+        Self {
+            source: version.address(&row.source),
+            amount: version.amount(row.amount),
+            hops: row.hops,
+            path: row.path.iter().map(|address| version.address(address)).collect(),
+        }
+    }
+}
+
+/// Unique senders and receivers exclude the zero address, whose activity is reported as
+/// mints and burns.
+#[derive(Serialize, ToSchema)]
+pub struct VolumeBucket {
+    pub bucket_start: DateTime<Utc>,
+    pub transfers: i64,
+    #[schema(example = "2532.100000")]
+    pub volume: String,
+    pub unique_senders: i64,
+    pub unique_receivers: i64,
+    pub mints: i64,
+    pub minted: String,
+    pub burns: i64,
+    pub burned: String,
+}
+
+impl VolumeBucket {
+    pub fn new(row: &db::VolumeBucket, version: ApiVersion) -> Self {
+        Self {
+            bucket_start: row.bucket_start,
+            transfers: row.transfers,
+            volume: version.amount(row.volume),
+            unique_senders: row.unique_senders,
+            unique_receivers: row.unique_receivers,
+            mints: row.mints,
+            minted: version.amount(row.minted),
+            burns: row.burns,
+            burned: version.amount(row.burned),
+        }
+    }
+}
+
+/// Supply as of the end of a bucket.
+#[derive(Serialize, ToSchema)]
+pub struct SupplyBucket {
+    pub bucket_start: DateTime<Utc>,
+    #[schema(example = "32518231002.515400")]
+    pub total_supply: String,
+    /// Held by addresses blacklisted at the end of the bucket.
+    #[schema(example = "108532115.003100")]
+    pub frozen: String,
+    pub circulating_excluding_frozen: String,
+}
+
+impl SupplyBucket {
+    pub fn new(row: &db::SupplyBucket, version: ApiVersion) -> Self {
+        Self {
+            bucket_start: row.bucket_start,
+            total_supply: version.amount(row.total_supply),
+            frozen: version.amount(row.frozen),
+            circulating_excluding_frozen: version.amount(row.total_supply - row.frozen),
+        }
+    }
+}
+
+/// A block header. `hash` and `parent_hash` are null for blocks indexed before headers were
+/// stored, until the indexer reads them again.
+#[derive(Serialize, ToSchema)]
+pub struct Block {
+    #[schema(example = 18573214)]
+    pub number: i64,
+    #[schema(example = "0x9f1c3b6a52e0d4b1d2f8a7c3e6b5d4a2f1e0c9b8a7d6e5f4c3b2a1908f7e6d5c")]
+    pub hash: Option<String>,
+    pub parent_hash: Option<String>,
+    pub block_time: DateTime<Utc>,
+}
+
+impl From<db::Block> for Block {
+    fn from(row: db::Block) -> Self {
+        Self {
+            number: row.number,
+            hash: row.hash,
+            parent_hash: row.parent_hash,
+            block_time: row.block_time,
+        }
+    }
+}
+
+/// An API key without its secret.
+#[derive(Serialize, ToSchema)]
+pub struct ApiKey {
+    #[schema(example = 7)]
+    pub id: i64,
+    #[schema(example = "acme-dashboard")]
+    pub name: String,
+    /// Leading characters of the key, enough to tell keys apart.
+    #[schema(example = "usdc_3f9a1c2e")]
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    #[schema(example = 600)]
+    pub rate_limit_per_minute: i32,
+    #[schema(example = 100000)]
+    pub daily_quota: i64,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl From<db::ApiKey> for ApiKey {
+    fn from(row: db::ApiKey) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            prefix: row.prefix,
+            scopes: row.scopes,
+            rate_limit_per_minute: row.rate_limit_per_minute,
+            daily_quota: row.daily_quota,
+            created_at: row.created_at,
+            revoked_at: row.revoked_at,
+        }
+    }
+}
+
+/// Requests one key made on one UTC day.
+#[derive(Serialize, ToSchema)]
+pub struct KeyUsage {
+    #[schema(example = 7)]
+    pub key_id: i64,
+    #[schema(example = "acme-dashboard")]
+    pub name: String,
+    pub day: NaiveDate,
+    /// Requests that were served.
+    #[schema(example = 48211)]
+    pub requests: i64,
+    /// Requests refused by the rate limit or the daily quota.
+    #[schema(example = 312)]
+    pub rejected: i64,
+}
+
+impl From<db::ApiKeyUsage> for KeyUsage {
+    fn from(row: db::ApiKeyUsage) -> Self {
+        Self {
+            key_id: row.key_id,
+            name: row.name,
+            day: row.day,
+            requests: row.requests,
+            rejected: row.rejected,
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use std::io::{self, Write};
+use std::sync::Arc;
+
+use async_stream::try_stream;
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData, UsdcTransfer};
+use futures::{stream, Stream, StreamExt};
+use serde::Deserialize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+use tracing::error;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::Transfer;
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, TooManyRequests, Unavailable};
+use crate::transfers::TransferFilter;
+use crate::version::ApiVersion;
+
+/// Each export holds a pool connection for its whole duration.
+const MAX_CONCURRENT_EXPORTS: usize = 2;
+/// Encoded rows are buffered up to this size before being sent as one chunk.
+const CHUNK_BYTES: usize = 64 * 1024;
+const CSV_HEADER: &str = "id,tx_hash,log_index,block_number,from_address,to_address,amount,block_time,created_at\n";
+
+static EXPORT_PERMITS: Semaphore = Semaphore::const_new(MAX_CONCURRENT_EXPORTS);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    fn content_type(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Ndjson => "application/x-ndjson",
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "csv",
+            ExportFormat::Ndjson => "ndjson",
+        }
+    }
+
+    fn write_row(self, buf: &mut Vec<u8>, row: &Transfer) -> io::Result<()> {
+        match self {
+            // Every field is hex, numeric or RFC 3339, so nothing needs quoting.
+            ExportFormat::Csv => writeln!(
+                buf,
+                "{},{},{},{},{},{},{},{},{}",
+                row.id,
+                row.tx_hash,
+                row.log_index,
+                row.block_number,
+                row.from_address,
+                row.to_address,
+                row.amount,
+                row.block_time.to_rfc3339(),
+                row.created_at.to_rfc3339(),
+            ),
+            ExportFormat::Ndjson => {
+                serde_json::to_writer(&mut *buf, row)?;
+                buf.push(b'\n');
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// Defaults to `csv`.
+    format: Option<ExportFormat>,
+}
+
+/// Streams every transfer matching the `/tx` filters; `limit` is ignored and `cursor` resumes
+/// an interrupted export.
+#[utoipa::path(
+    get,
+    path = "/v1/tx/export",
+    tag = "transfers",
+    params(ExportQuery, TransferFilter),
+    responses(
+        (status = 200, description = "Every matching transfer as CSV or NDJSON; gzip-encoded on request",
+            content(("text/csv"), ("application/x-ndjson"))),
+        (status = 400, response = BadRequest),
+        (status = 429, response = TooManyRequests),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn export_transfers(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(export): ApiQuery<ExportQuery>,
+    ApiQuery(filter): ApiQuery<TransferFilter>,
+) -> AppResult<Response> {
+    let query = filter.into_query()?;
+    let format = export.format.unwrap_or_default();
+    let permit = EXPORT_PERMITS.try_acquire().map_err(|_| {
+        AppError::TooManyRequests(format!("at most {} exports can run at once", MAX_CONCURRENT_EXPORTS))
+    })?;
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let mut rows = repo.stream_transfers(query);
+    // Surface connection and query errors as a proper status before the body starts.
+    let first = rows.next().await.transpose()?;
+    let rows = stream::iter(first.map(Ok)).chain(rows);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"usdc-transfers.{}\"", format.extension()),
+            ),
+        ],
+        Body::from_stream(encode(rows, format, version, permit)),
+    )
+        .into_response())
+}
+
+fn encode(
+    mut rows: impl Stream<Item = anyhow::Result<UsdcTransfer>> + Send + Unpin + 'static,
+    format: ExportFormat,
+    version: ApiVersion,
+    permit: SemaphorePermit<'static>,
+) -> impl Stream<Item = io::Result<Bytes>> + Send + 'static {
+    try_stream! {
+        let _permit = permit;
+        let mut buf = Vec::with_capacity(CHUNK_BYTES * 2);
+        if format == ExportFormat::Csv {
+            buf.extend_from_slice(CSV_HEADER.as_bytes());
+        }
+        while let Some(row) = rows.next().await {
+            let row = row.map_err(|e| {
+                error!(error = %e, "transfer export aborted");
+                io::Error::other(e.to_string())
+            })?;
+            format.write_row(&mut buf, &Transfer::new(&row, version))?;
+            if buf.len() >= CHUNK_BYTES {
+                yield Bytes::from(std::mem::take(&mut buf));
+            }
+        }
+        if !buf.is_empty() {
+            yield Bytes::from(buf);
+        }
+    }
+}
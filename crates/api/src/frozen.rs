@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::version::ApiVersion;
+
+const DEFAULT_LIMIT: u32 = 100;
+const MAX_LIMIT: u32 = 1000;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FrozenQuery {
+    /// Addresses returned, 1 to 1000 (default 100).
+    limit: Option<u32>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FrozenAddress {
+    #[schema(example = "0x7F367cC41522cE07553e823bf3be79A889DEbe1B")]
+    address: String,
+    #[schema(example = "1250.500000")]
+    balance: String,
+    /// The `Blacklisted` event that started the current freeze.
+    blacklisted_block: i64,
+    blacklisted_at: DateTime<Utc>,
+    blacklisted_tx_hash: String,
+    label: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FrozenList {
+    /// Every currently blacklisted address, not only those returned.
+    frozen_addresses: i64,
+    #[schema(example = "108532115.003100")]
+    total_frozen: String,
+    /// Largest balance first.
+    items: Vec<FrozenAddress>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/frozen",
+    tag = "compliance",
+    params(FrozenQuery),
+    responses(
+        (status = 200, description = "Currently blacklisted addresses and the USDC frozen in them", body = FrozenList),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_frozen(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<FrozenQuery>,
+) -> AppResult<Json<FrozenList>> {
+    if query.limit == Some(0) {
+        return Err(AppError::BadRequest("limit must be at least 1".to_string()));
+    }
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let frozen = repo.list_frozen_addresses(limit).await?;
+    let supply = repo.get_supply().await?;
+    let addresses: Vec<String> = frozen.iter().map(|f| f.address.clone()).collect();
+    let labels: HashMap<String, String> = repo
+        .list_address_labels(&addresses)
+        .await?
+        .into_iter()
+        .map(|l| (l.address, l.label))
+        .collect();
+
+    Ok(Json(FrozenList {
+        frozen_addresses: supply.frozen_addresses,
+        total_frozen: version.amount(supply.frozen),
+        items: frozen
+            .into_iter()
+            .map(|f| FrozenAddress {
+                address: version.address(&f.address),
+                balance: version.amount(f.balance),
+                blacklisted_block: f.block_number,
+                blacklisted_at: f.blacklisted_at,
+                blacklisted_tx_hash: f.tx_hash,
+                label: labels.get(&f.address).cloned(),
+            })
+            .collect(),
+    }))
+}
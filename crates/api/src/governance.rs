@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{AdminEventKind, AdminEventQuery, PgPool, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::cursor;
+use crate::dto::AdminEvent;
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::validate::{block_number, normalize_address};
+use crate::version::ApiVersion;
+
+const DEFAULT_PAGE_LIMIT: u32 = 20;
+const MAX_PAGE_LIMIT: u32 = 100;
+/// Names this list in its cursors.
+const CURSOR_LIST: &str = "events";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AdminEventFilter {
+    kind: Option<AdminEventKind>,
+    /// Events about this address, including as the previous owner or admin.
+    account: Option<String>,
+    /// Inclusive first block.
+    from_block: Option<u64>,
+    /// Inclusive last block.
+    to_block: Option<u64>,
+    /// `next_cursor` of the previous page.
+    cursor: Option<String>,
+    /// Page size, 1 to 100 (default 20).
+    limit: Option<u32>,
+}
+
+/// Latest events first. `next_cursor` is null on the last page.
+#[derive(Serialize, ToSchema)]
+pub struct AdminEventPage {
+    items: Vec<AdminEvent>,
+    next_cursor: Option<String>,
+}
+
+/// A span during which the contract was paused; the end is null while it still is.
+#[derive(Serialize, ToSchema)]
+pub struct PausedInterval {
+    paused_block: i64,
+    paused_at: DateTime<Utc>,
+    paused_tx_hash: String,
+    unpaused_block: Option<i64>,
+    unpaused_at: Option<DateTime<Utc>>,
+    unpaused_tx_hash: Option<String>,
+    duration_secs: Option<i64>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct PauseHistory {
+    /// Whether the latest event was `pause`.
+    paused: bool,
+    /// Oldest first.
+    intervals: Vec<PausedInterval>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Minter {
+    #[schema(example = "0x5B6122C109B78C6755486966148C1D70a50A47D7")]
+    address: String,
+    /// The allowance `minter_configured` set. Minting uses it up on chain, which is not
+    /// subtracted here, so this is an upper bound on what the minter can still mint.
+    #[schema(example = "50000000.000000")]
+    configured_allowance: String,
+    configured_block: i64,
+    configured_at: DateTime<Utc>,
+    configured_tx_hash: String,
+}
+
+/// Blacklisting, pauses, minter configuration, role and ownership changes and proxy upgrades.
+#[utoipa::path(
+    get,
+    path = "/v1/governance/events",
+    tag = "governance",
+    params(AdminEventFilter),
+    responses(
+        (status = 200, description = "One page of admin events, latest first", body = AdminEventPage),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_admin_events(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(filter): ApiQuery<AdminEventFilter>,
+) -> AppResult<Json<AdminEventPage>> {
+    let from_block = block_number("from_block", filter.from_block)?;
+    let to_block = block_number("to_block", filter.to_block)?;
+    if let (Some(from_block), Some(to_block)) = (from_block, to_block)
+        && from_block > to_block {
+        return Err(AppError::BadRequest("from_block must not exceed to_block".to_string()));
+    }
+    if filter.limit == Some(0) {
+        return Err(AppError::BadRequest("limit must be at least 1".to_string()));
+    }
+    let limit = filter.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT);
+    let query = AdminEventQuery {
+        kinds: filter.kind.into_iter().collect(),
+        account: filter.account.as_deref().map(|a| normalize_address("account", a)).transpose()?,
+        from_block,
+        to_block,
+        before: filter.cursor.as_deref().map(|c| cursor::decode_position(c, CURSOR_LIST)).transpose()?,
+        // One extra row tells whether there is a next page.
+        limit: limit + 1,
+    };
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let mut events = repo.list_admin_events(&query).await?;
+    let next_cursor = if events.len() > limit as usize {
+        events.truncate(limit as usize);
+        events.last().map(|last| cursor::encode_position(CURSOR_LIST, last.block_number, last.log_index))
+    } else {
+        None
+    };
+    Ok(Json(AdminEventPage {
+        items: events.iter().map(|e| AdminEvent::new(e, version)).collect(),
+        next_cursor,
+    }))
+}
+
+/// A `pause` while already paused or an `unpause` while not paused changes nothing and is
+/// left out.
+#[utoipa::path(
+    get,
+    path = "/v1/governance/paused",
+    tag = "governance",
+    responses(
+        (status = 200, description = "Every interval the contract was paused", body = PauseHistory),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_pause_history(State(pool): State<Arc<PgPool>>) -> AppResult<Json<PauseHistory>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let mut intervals: Vec<PausedInterval> = Vec::new();
+    let mut paused = false;
+
+    for event in repo.list_pause_events().await? {
+        match (event.kind, paused) {
+            (AdminEventKind::Pause, false) => {
+                intervals.push(PausedInterval {
+                    paused_block: event.block_number,
+                    paused_at: event.block_time,
+                    paused_tx_hash: event.tx_hash,
+                    unpaused_block: None,
+                    unpaused_at: None,
+                    unpaused_tx_hash: None,
+                    duration_secs: None,
+                });
+                paused = true;
+            }
+            (AdminEventKind::Unpause, true) => {
+                if let Some(interval) = intervals.last_mut() {
+                    interval.unpaused_block = Some(event.block_number);
+                    interval.unpaused_at = Some(event.block_time);
+                    interval.unpaused_tx_hash = Some(event.tx_hash);
+                    interval.duration_secs = Some((event.block_time - interval.paused_at).num_seconds());
+                }
+                paused = false;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(Json(PauseHistory { paused, intervals }))
+}
+
+/// Addresses whose latest minter event is `minter_configured`, largest configured allowance first.
+#[utoipa::path(
+    get,
+    path = "/v1/governance/minters",
+    tag = "governance",
+    responses(
+        (status = 200, description = "Current minters", body = Vec<Minter>),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_minters(State(pool): State<Arc<PgPool>>, version: ApiVersion) -> AppResult<Json<Vec<Minter>>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let minters = repo
+        .list_current_minters()
+        .await?
+        .into_iter()
+        .map(|m| Minter {
+            address: version.address(&m.address),
+            configured_allowance: version.amount(m.configured_allowance),
+            configured_block: m.block_number,
+            configured_at: m.block_time,
+            configured_tx_hash: m.tx_hash,
+        })
+        .collect();
+    Ok(Json(minters))
+}
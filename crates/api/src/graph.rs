@@ -0,0 +1,330 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData, ZERO_ADDRESS};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::GraphEdge;
+use crate::extract::ApiQuery;
+use crate::leaderboard::parse_window;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::validate::normalize_address;
+use crate::version::ApiVersion;
+
+const DEFAULT_DEPTH: u32 = 1;
+const MAX_DEPTH: u32 = 3;
+const DEFAULT_FAN_OUT: u32 = 20;
+const MAX_FAN_OUT: u32 = 50;
+/// Expansion stops adding nodes past this many; `truncated` is then set.
+const MAX_NODES: usize = 500;
+const DEFAULT_WINDOW: &str = "30d";
+const USDC_DECIMALS: u32 = 6;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    #[default]
+    Json,
+    Graphml,
+    Dot,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GraphQuery {
+    /// Address at the center of the graph.
+    #[param(example = "0x28c6c06298d514db089934071355e5743bf21d60")]
+    address: String,
+    /// Hops from `address`, 1 to 3 (default 1).
+    depth: Option<u32>,
+    /// Leave out edges whose total over the window is below this many USDC.
+    min_amount: Option<Decimal>,
+    /// Trailing window such as `24h`, `7d` or `2w`, at most 90 days (default `30d`).
+    #[param(example = "7d")]
+    window: Option<String>,
+    /// Counterparties kept per expanded node, largest combined volume first; 1 to 50 (default 20).
+    fan_out: Option<u32>,
+    /// Defaults to `json`.
+    format: Option<GraphFormat>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct GraphNode {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    address: String,
+    /// Hops from the center.
+    depth: u32,
+    label: Option<String>,
+}
+
+/// Nodes in discovery order, center first, and edges by volume. Only edges found while expanding
+/// a node are included, so edges between two nodes at the last hop are missing.
+#[derive(Serialize, ToSchema)]
+pub struct Graph {
+    address: String,
+    depth: u32,
+    window: String,
+    since: DateTime<Utc>,
+    /// Set when the node cap of 500 cut the expansion short.
+    truncated: bool,
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+}
+
+/// The zero address is included as a node but never expanded, since mints and burns would
+/// otherwise connect every holder.
+#[utoipa::path(
+    get,
+    path = "/v1/graph",
+    tag = "analytics",
+    params(GraphQuery),
+    responses(
+        (status = 200, description = "Counterparty graph around the address",
+            content(
+                (Graph = "application/json"),
+                (String = "application/graphml+xml"),
+                (String = "text/vnd.graphviz"),
+            )),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_graph(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<GraphQuery>,
+) -> AppResult<Response> {
+    let center = normalize_address("address", &query.address)?;
+    let depth = query.depth.unwrap_or(DEFAULT_DEPTH);
+    if !(1..=MAX_DEPTH).contains(&depth) {
+        return Err(AppError::BadRequest(format!("depth must be between 1 and {}", MAX_DEPTH)));
+    }
+    let fan_out = query.fan_out.unwrap_or(DEFAULT_FAN_OUT);
+    if !(1..=MAX_FAN_OUT).contains(&fan_out) {
+        return Err(AppError::BadRequest(format!("fan_out must be between 1 and {}", MAX_FAN_OUT)));
+    }
+    let min_amount = query.min_amount.unwrap_or_default();
+    if min_amount.is_sign_negative() || min_amount.scale() > USDC_DECIMALS {
+        return Err(AppError::BadRequest(format!(
+            "min_amount must be a non-negative amount with at most {} decimals",
+            USDC_DECIMALS
+        )));
+    }
+    let window = query.window.unwrap_or_else(|| DEFAULT_WINDOW.to_string());
+    let since = Utc::now() - parse_window(&window)?;
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let mut depths: HashMap<String, u32> = HashMap::from([(center.clone(), 0)]);
+    let mut nodes = vec![center.clone()];
+    let mut edges: BTreeMap<(String, String), db::GraphEdge> = BTreeMap::new();
+    let mut truncated = false;
+    let mut frontier = vec![center.clone()];
+
+    for hop in 1..=depth {
+        frontier.retain(|address| address != ZERO_ADDRESS);
+        if frontier.is_empty() {
+            break;
+        }
+        let mut next = Vec::new();
+        // Edges arrive by volume, so the cap keeps the heaviest counterparties.
+        for edge in repo.list_graph_edges(&frontier, since, min_amount, fan_out).await? {
+            for address in [&edge.from_address, &edge.to_address] {
+                if depths.contains_key(address) {
+                    continue;
+                }
+                if nodes.len() >= MAX_NODES {
+                    truncated = true;
+                    continue;
+                }
+                depths.insert(address.clone(), hop);
+                nodes.push(address.clone());
+                next.push(address.clone());
+            }
+            if depths.contains_key(&edge.from_address) && depths.contains_key(&edge.to_address) {
+                edges.entry((edge.from_address.clone(), edge.to_address.clone())).or_insert(edge);
+            }
+        }
+        frontier = next;
+    }
+
+    let labels: HashMap<String, String> = repo
+        .list_address_labels(&nodes)
+        .await?
+        .into_iter()
+        .map(|l| (l.address, l.label))
+        .collect();
+    let mut edges: Vec<db::GraphEdge> = edges.into_values().collect();
+    edges.sort_by_key(|edge| Reverse(edge.volume));
+
+    let graph = Graph {
+        address: version.address(&center),
+        depth,
+        window,
+        since,
+        truncated,
+        nodes: nodes
+            .iter()
+            .map(|address| GraphNode {
+                address: version.address(address),
+                depth: depths[address],
+                label: labels.get(address).cloned(),
+            })
+            .collect(),
+        edges: edges.iter().map(|e| GraphEdge::new(e, version)).collect(),
+    };
+
+    Ok(match query.format.unwrap_or_default() {
+        GraphFormat::Json => Json(graph).into_response(),
+        GraphFormat::Graphml => ([(header::CONTENT_TYPE, "application/graphml+xml")], graphml(&graph)).into_response(),
+        GraphFormat::Dot => ([(header::CONTENT_TYPE, "text/vnd.graphviz")], dot(&graph)).into_response(),
+    })
+}
+
+fn graphml(graph: &Graph) -> String {
+    let mut out = String::from(concat!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+        "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+        "  <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n",
+        "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        "  <key id=\"volume\" for=\"edge\" attr.name=\"volume\" attr.type=\"double\"/>\n",
+        "  <key id=\"transfers\" for=\"edge\" attr.name=\"transfers\" attr.type=\"long\"/>\n",
+        "  <graph id=\"usdc\" edgedefault=\"directed\">\n",
+    ));
+    for node in &graph.nodes {
+        let _ = write!(out, "    <node id=\"{}\"><data key=\"depth\">{}</data>", node.address, node.depth);
+        if let Some(label) = &node.label {
+            let _ = write!(out, "<data key=\"label\">{}</data>", xml_escape(label));
+        }
+        out.push_str("</node>\n");
+    }
+    for edge in &graph.edges {
+        let _ = writeln!(
+            out,
+            "    <edge source=\"{}\" target=\"{}\"><data key=\"volume\">{}</data><data key=\"transfers\">{}</data></edge>",
+            edge.from_address, edge.to_address, edge.volume, edge.transfers
+        );
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    out
+}
+
+fn dot(graph: &Graph) -> String {
+    let mut out = String::from("digraph usdc {\n");
+    for node in &graph.nodes {
+        let label = match &node.label {
+            Some(label) => format!("{}\\n{}", dot_escape(label), node.address),
+            None => node.address.clone(),
+        };
+        let _ = writeln!(out, "  \"{}\" [label=\"{}\", depth={}];", node.address, label, node.depth);
+    }
+    for edge in &graph.edges {
+        let _ = writeln!(
+            out,
+            "  \"{}\" -> \"{}\" [label=\"{}\", transfers={}];",
+            edge.from_address, edge.to_address, edge.volume, edge.transfers
+        );
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn dot_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const A: &str = "0x28C6c06298d514Db089934071355E5743bf21d60";
+    const B: &str = "0xA9D1e08C7793af67e9d92fe308d5697FB81d3E43";
+
+    fn two_nodes() -> Graph {
+        Graph {
+            address: A.to_string(),
+            depth: 1,
+            window: "30d".to_string(),
+            since: Utc::now(),
+            truncated: false,
+            nodes: vec![
+                GraphNode { address: A.to_string(), depth: 0, label: Some(r#"Acme "Hot" <1> & co\ wallet"#.to_string()) },
+                GraphNode { address: B.to_string(), depth: 1, label: None },
+            ],
+            edges: vec![GraphEdge {
+                from_address: A.to_string(),
+                to_address: B.to_string(),
+                volume: "1250.500000".to_string(),
+                transfers: 3,
+            }],
+        }
+    }
+
+    #[test]
+    fn graphml_escapes_labels() {
+        let expected = format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+                "  <key id=\"depth\" for=\"node\" attr.name=\"depth\" attr.type=\"int\"/>\n",
+                "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+                "  <key id=\"volume\" for=\"edge\" attr.name=\"volume\" attr.type=\"double\"/>\n",
+                "  <key id=\"transfers\" for=\"edge\" attr.name=\"transfers\" attr.type=\"long\"/>\n",
+                "  <graph id=\"usdc\" edgedefault=\"directed\">\n",
+                "    <node id=\"{a}\"><data key=\"depth\">0</data>",
+                "<data key=\"label\">Acme &quot;Hot&quot; &lt;1&gt; &amp; co\\ wallet</data></node>\n",
+                "    <node id=\"{b}\"><data key=\"depth\">1</data></node>\n",
+                "    <edge source=\"{a}\" target=\"{b}\"><data key=\"volume\">1250.500000</data>",
+                "<data key=\"transfers\">3</data></edge>\n",
+                "  </graph>\n",
+                "</graphml>\n",
+            ),
+            a = A,
+            b = B,
+        );
+        assert_eq!(graphml(&two_nodes()), expected);
+    }
+
+    #[test]
+    fn dot_escapes_labels() {
+        let expected = format!(
+            concat!(
+                "digraph usdc {{\n",
+                "  \"{a}\" [label=\"Acme \\\"Hot\\\" <1> & co\\\\ wallet\\n{a}\", depth=0];\n",
+                "  \"{b}\" [label=\"{b}\", depth=1];\n",
+                "  \"{a}\" -> \"{b}\" [label=\"1250.500000\", transfers=3];\n",
+                "}}\n",
+            ),
+            a = A,
+            b = B,
+        );
+        assert_eq!(dot(&two_nodes()), expected);
+    }
+
+    #[test]
+    fn escapes_apostrophes_for_xml_only() {
+        assert_eq!(xml_escape("O'Brien"), "O&apos;Brien");
+        assert_eq!(dot_escape("O'Brien"), "O'Brien");
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use async_graphql::dataloader::Loader;
+use chrono::{DateTime, Utc};
+use db::{AddressFlows, AddressLabel, AddressStats, Counterparty, PostgresRepo, ReadData, TransferSide, UsdcTransfer};
+
+use super::{to_gql, Order, Sort, TransferFilterInput};
+use crate::cursor;
+
+/// Batches `address_stats` lookups for every address resolved in the same tick.
+pub struct AddressStatsLoader(pub PostgresRepo);
+
+impl Loader<String> for AddressStatsLoader {
+    type Value = AddressStats;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, AddressStats>, Self::Error> {
+        let stats = self.0.list_address_stats(keys).await.map_err(to_gql)?;
+        Ok(stats.into_iter().map(|s| (s.address.clone(), s)).collect())
+    }
+}
+
+pub struct AddressLabelLoader(pub PostgresRepo);
+
+impl Loader<String> for AddressLabelLoader {
+    type Value = AddressLabel;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, AddressLabel>, Self::Error> {
+        let labels = self.0.list_address_labels(keys).await.map_err(to_gql)?;
+        Ok(labels.into_iter().map(|l| (l.address.clone(), l)).collect())
+    }
+}
+
+/// Stored headers of the requested blocks.
+pub struct BlockLoader(pub PostgresRepo);
+
+impl Loader<i64> for BlockLoader {
+    type Value = db::Block;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, db::Block>, Self::Error> {
+        let blocks = self.0.list_blocks(keys).await.map_err(to_gql)?;
+        Ok(blocks.into_iter().map(|b| (b.number, b)).collect())
+    }
+}
+
+/// Transfers of each requested block, in log order. Blocks without transfers map to nothing.
+pub struct BlockTransfersLoader(pub PostgresRepo);
+
+impl Loader<i64> for BlockTransfersLoader {
+    type Value = Vec<UsdcTransfer>;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[i64]) -> Result<HashMap<i64, Vec<UsdcTransfer>>, Self::Error> {
+        let transfers = self.0.list_transfers_by_block_numbers(keys).await.map_err(to_gql)?;
+        let mut by_block: HashMap<i64, Vec<UsdcTransfer>> = HashMap::new();
+        for transfer in transfers {
+            by_block.entry(transfer.block_number).or_default().push(transfer);
+        }
+        Ok(by_block)
+    }
+}
+
+/// `Address.flows` arguments; addresses sharing a window are summed in one query.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct FlowsKey {
+    pub address: String,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+pub struct AddressFlowsLoader(pub PostgresRepo);
+
+impl Loader<FlowsKey> for AddressFlowsLoader {
+    type Value = AddressFlows;
+    type Error = async_graphql::Error;
+
+    async fn load(&self, keys: &[FlowsKey]) -> Result<HashMap<FlowsKey, AddressFlows>, Self::Error> {
+        let mut loaded = HashMap::new();
+        for ((since, until), addresses) in group(keys, |k| (k.since, k.until), |k| k.address.clone()) {
+            let flows = self.0.list_address_flows(&addresses, since, until).await.map_err(to_gql)?;
+            loaded.extend(flows.into_iter().map(|f| (FlowsKey { address: f.address, since, until }, f.flows)));
+        }
+        Ok(loaded)
+    }
+}
+
+/// `Address.counterparties` arguments, with `limit` already capped.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct CounterpartiesKey {
+    pub address: String,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: u32,
+}
+
+pub struct CounterpartiesLoader(pub PostgresRepo);
+
+impl Loader<CounterpartiesKey> for CounterpartiesLoader {
+    type Value = Vec<Counterparty>;
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[CounterpartiesKey],
+    ) -> Result<HashMap<CounterpartiesKey, Vec<Counterparty>>, Self::Error> {
+        let mut loaded: HashMap<CounterpartiesKey, Vec<Counterparty>> = HashMap::new();
+        for ((since, until, limit), addresses) in group(keys, |k| (k.since, k.until, k.limit), |k| k.address.clone()) {
+            let entries = self
+                .0
+                .list_top_counterparties_of(&addresses, since, until, limit)
+                .await
+                .map_err(to_gql)?;
+            for entry in entries {
+                let key = CounterpartiesKey { address: entry.of, since, until, limit };
+                loaded.entry(key).or_default().push(entry.counterparty);
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+/// `Address.transfers` arguments.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct AddressTransfersKey {
+    pub address: String,
+    pub side: TransferSide,
+    pub sort: Sort,
+    pub order: Order,
+    pub first: u32,
+    pub after: Option<String>,
+}
+
+/// One page of an address's transfers and the cursor of the next, like `fetch_page`.
+pub struct AddressTransfersLoader(pub PostgresRepo);
+
+impl Loader<AddressTransfersKey> for AddressTransfersLoader {
+    type Value = (Vec<UsdcTransfer>, Option<String>);
+    type Error = async_graphql::Error;
+
+    async fn load(
+        &self,
+        keys: &[AddressTransfersKey],
+    ) -> Result<HashMap<AddressTransfersKey, Self::Value>, Self::Error> {
+        let mut loaded = HashMap::new();
+        let groups = group(
+            keys,
+            |k| (k.side, k.sort, k.order, k.first, k.after.clone()),
+            |k| k.address.clone(),
+        );
+        for ((side, sort, order, first, after), addresses) in groups {
+            let mut query = TransferFilterInput::default()
+                .into_filter(sort, order, first, after.clone())
+                .into_query()
+                .map_err(to_gql)?;
+            let limit = query.limit as usize;
+            // One extra row per address tells whether another page follows.
+            query.limit += 1;
+            let rows = self
+                .0
+                .list_transfers_of_addresses(&addresses, side, &query)
+                .await
+                .map_err(to_gql)?;
+
+            let mut pages: HashMap<String, Vec<UsdcTransfer>> =
+                addresses.into_iter().map(|address| (address, Vec::new())).collect();
+            for row in rows {
+                pages.entry(row.owner).or_default().push(row.transfer);
+            }
+            for (address, mut items) in pages {
+                let next_cursor = if items.len() > limit {
+                    items.truncate(limit);
+                    items.last().map(|last| cursor::encode(last, query.sort, query.order))
+                } else {
+                    None
+                };
+                let key = AddressTransfersKey { address, side, sort, order, first, after: after.clone() };
+                loaded.insert(key, (items, next_cursor));
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+/// Splits `keys` by the arguments other than the address, so each group is one query.
+fn group<K, G: Eq + Hash>(
+    keys: &[K],
+    args: impl Fn(&K) -> G,
+    address: impl Fn(&K) -> String,
+) -> HashMap<G, Vec<String>> {
+    let mut groups: HashMap<G, Vec<String>> = HashMap::new();
+    for key in keys {
+        groups.entry(args(key)).or_default().push(address(key));
+    }
+    groups
+}
@@ -0,0 +1,592 @@
+mod loaders;
+
+use async_graphql::{
+    dataloader::{DataLoader, HashMapCache, Loader},
+    http::GraphiQLSource,
+    Context, EmptyMutation, EmptySubscription, Enum, ErrorExtensions, InputObject, Object, Result, Schema,
+    SimpleObject,
+};
+use axum::{extract::State, response::Html, Json};
+use chrono::{DateTime, Utc};
+use common::AppError;
+use db::{AddressFlows, AddressLabel, AddressStats, Counterparty, PostgresRepo, ReadData, TransferSide, UsdcTransfer, VolumeBucket};
+use rust_decimal::Decimal;
+use tracing::error;
+
+use crate::address::{DEFAULT_COUNTERPARTIES, MAX_COUNTERPARTIES};
+use crate::extract::ApiJson;
+use crate::leaderboard::{parse_exclusion, parse_limit};
+use crate::stats::volume_range;
+use crate::transfers::{fetch_page, TransferFilter};
+use crate::validate::{normalize_address, normalize_tx_hash};
+use crate::AppState;
+use loaders::{
+    AddressFlowsLoader, AddressLabelLoader, AddressStatsLoader, AddressTransfersKey, AddressTransfersLoader,
+    BlockLoader, BlockTransfersLoader, CounterpartiesKey, CounterpartiesLoader, FlowsKey,
+};
+
+/// Deep enough for the introspection query GraphiQL sends; complexity bounds the real cost.
+const MAX_DEPTH: usize = 15;
+/// Every field costs 1 and list fields multiply their selection by the requested size, so
+/// e.g. 100 transfers with a dozen fields each stay well within the limit.
+const MAX_COMPLEXITY: usize = 5_000;
+/// Extra cost of fields that run their own query instead of going through a loader.
+const UNBATCHED_COST: usize = 5;
+const DEFAULT_PAGE_SIZE: u32 = 20;
+const DEFAULT_BLOCK_TRANSFERS: u32 = 100;
+
+pub type ApiSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn build_schema() -> ApiSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Executes one GraphQL request with fresh loaders, so batching and caching never span requests.
+#[utoipa::path(
+    post,
+    path = "/graphql",
+    tag = "transfers",
+    request_body(content = Object, description = "GraphQL request with `query`, `variables` and `operationName`",
+        example = json!({"query": "{ transfers(first: 5) { items { id amount from { address balance } } } }"})),
+    responses(
+        (status = 200, description = "GraphQL response with `data` and `errors`; GET serves GraphiQL", body = Object),
+        (status = 400, response = crate::openapi::BadRequest),
+    )
+)]
+pub async fn graphql(
+    State(state): State<AppState>,
+    ApiJson(request): ApiJson<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let repo = || PostgresRepo::new(state.pool.as_ref().clone());
+    let request = request
+        .data(repo())
+        .data(loader(AddressStatsLoader(repo())))
+        .data(loader(AddressLabelLoader(repo())))
+        .data(loader(AddressFlowsLoader(repo())))
+        .data(loader(CounterpartiesLoader(repo())))
+        .data(loader(AddressTransfersLoader(repo())))
+        .data(loader(BlockLoader(repo())))
+        .data(loader(BlockTransfersLoader(repo())));
+    Json(state.graphql.execute(request).await)
+}
+
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/graphql").finish())
+}
+
+fn loader<K, T>(inner: T) -> DataLoader<T, HashMapCache>
+where
+    K: Send + Sync + std::hash::Hash + Eq + Clone + 'static,
+    T: Loader<K>,
+{
+    DataLoader::with_cache(inner, tokio::spawn, HashMapCache::default())
+}
+
+/// Maps an error to the message and `code` (the problem `type`) the REST API would return.
+pub(crate) fn to_gql(err: impl Into<AppError>) -> async_graphql::Error {
+    let err = err.into();
+    if err.status().is_server_error() {
+        error!(error = %err, "graphql resolver failed");
+    }
+    let problem = err.problem();
+    async_graphql::Error::new(problem.detail).extend_with(|_, ext| ext.set("code", problem.kind))
+}
+
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[graphql(remote = "db::TransferSort")]
+enum Sort {
+    #[default]
+    Time,
+    Amount,
+}
+
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq, Hash)]
+#[graphql(remote = "db::SortOrder")]
+enum Order {
+    Asc,
+    #[default]
+    Desc,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "db::VolumeInterval")]
+enum Interval {
+    Hour,
+    Day,
+    Week,
+}
+
+/// Which side of a transfer the address must be on.
+#[derive(Enum, Clone, Copy, Default, PartialEq, Eq)]
+enum Direction {
+    #[default]
+    Any,
+    In,
+    Out,
+}
+
+/// Same filters as the `/tx` query string; lists match any of their entries.
+#[derive(InputObject, Default)]
+struct TransferFilterInput {
+    from: Option<Vec<String>>,
+    to: Option<Vec<String>>,
+    /// Either side.
+    address: Option<Vec<String>>,
+    exclude_zero_address: Option<bool>,
+    min_amount: Option<Decimal>,
+    max_amount: Option<Decimal>,
+    from_block: Option<u64>,
+    to_block: Option<u64>,
+    created_before: Option<DateTime<Utc>>,
+    created_after: Option<DateTime<Utc>>,
+}
+
+impl TransferFilterInput {
+    /// Goes through the REST filter so both APIs validate identically.
+    fn into_filter(self, sort: Sort, order: Order, first: u32, after: Option<String>) -> TransferFilter {
+        let list = |values: Option<Vec<String>>| values.map(|v| v.join(","));
+        TransferFilter {
+            from: list(self.from),
+            to: list(self.to),
+            address: list(self.address),
+            exclude_zero_address: self.exclude_zero_address,
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            from_block: self.from_block,
+            to_block: self.to_block,
+            created_before: self.created_before,
+            created_after: self.created_after,
+            sort: Some(sort.into()),
+            order: Some(order.into()),
+            cursor: after,
+            limit: Some(first),
+            page: None,
+        }
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object(name = "Query")]
+impl QueryRoot {
+    /// Last block the indexer has fully processed.
+    async fn last_block(&self, ctx: &Context<'_>) -> Result<u64> {
+        ctx.data::<PostgresRepo>()?.get_last_block().await.map_err(to_gql)
+    }
+
+    async fn transfer(&self, ctx: &Context<'_>, id: i64) -> Result<Option<Transfer>> {
+        let transfer = ctx.data::<PostgresRepo>()?.get_transfer_by_id(id).await.map_err(to_gql)?;
+        Ok(transfer.map(Transfer))
+    }
+
+    async fn transfers_by_hash(&self, ctx: &Context<'_>, tx_hash: String) -> Result<Vec<Transfer>> {
+        let tx_hash = normalize_tx_hash("txHash", &tx_hash).map_err(to_gql)?;
+        let transfers = ctx
+            .data::<PostgresRepo>()?
+            .get_transfers_by_tx_hash(&tx_hash)
+            .await
+            .map_err(to_gql)?;
+        Ok(transfers.into_iter().map(Transfer).collect())
+    }
+
+    /// Keyset-paginated like `/tx`: pass `nextCursor` as `after` with the same sort and order.
+    #[graphql(complexity = "(first as usize).saturating_mul(child_complexity)")]
+    async fn transfers(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] filter: TransferFilterInput,
+        #[graphql(default)] sort: Sort,
+        #[graphql(default)] order: Order,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] first: u32,
+        after: Option<String>,
+    ) -> Result<TransferPage> {
+        transfer_page(ctx, filter.into_filter(sort, order, first, after)).await
+    }
+
+    async fn address(&self, address: String) -> Result<Address> {
+        Ok(Address(normalize_address("address", &address).map_err(to_gql)?))
+    }
+
+    /// Null for blocks the indexer has not reached yet.
+    async fn block(&self, ctx: &Context<'_>, number: i64) -> Result<Option<Block>> {
+        let last_block = ctx.data::<PostgresRepo>()?.get_last_block().await.map_err(to_gql)?;
+        Ok((0..=last_block as i64).contains(&number).then_some(Block(number)))
+    }
+
+    /// Same series as `/stats/volume`.
+    async fn volume(
+        &self,
+        ctx: &Context<'_>,
+        interval: Interval,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<Vec<VolumeBucketNode>> {
+        let interval = interval.into();
+        let (from, to) = volume_range(interval, from, to).map_err(to_gql)?;
+        let buckets = ctx
+            .data::<PostgresRepo>()?
+            .list_volume_buckets(interval, from, to)
+            .await
+            .map_err(to_gql)?;
+        Ok(buckets.into_iter().map(VolumeBucketNode::from).collect())
+    }
+
+    /// `exclude` works as on `/leaderboard/holders`.
+    #[graphql(complexity = "UNBATCHED_COST + (limit as usize).saturating_mul(child_complexity)")]
+    async fn top_holders(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] limit: u32,
+        exclude: Option<String>,
+    ) -> Result<Vec<RankedHolder>> {
+        let limit = parse_limit(Some(limit)).map_err(to_gql)?;
+        let exclusion = parse_exclusion(exclude.as_deref()).map_err(to_gql)?;
+        let holders = ctx
+            .data::<PostgresRepo>()?
+            .list_top_holders(limit, &exclusion)
+            .await
+            .map_err(to_gql)?;
+        Ok(holders
+            .into_iter()
+            .enumerate()
+            .map(|(i, holder)| RankedHolder {
+                rank: i + 1,
+                address: Address(holder.address),
+                balance: holder.balance,
+            })
+            .collect())
+    }
+}
+
+async fn transfer_page(ctx: &Context<'_>, filter: TransferFilter) -> Result<TransferPage> {
+    let query = filter.into_query().map_err(to_gql)?;
+    let (items, next_cursor) = fetch_page(ctx.data::<PostgresRepo>()?, query).await.map_err(to_gql)?;
+    Ok(TransferPage { items: items.into_iter().map(Transfer).collect(), next_cursor })
+}
+
+#[derive(SimpleObject)]
+pub struct TransferPage {
+    items: Vec<Transfer>,
+    /// Null on the last page.
+    next_cursor: Option<String>,
+}
+
+pub struct Transfer(UsdcTransfer);
+
+#[Object]
+impl Transfer {
+    async fn id(&self) -> i64 {
+        self.0.id
+    }
+
+    async fn tx_hash(&self) -> &str {
+        &self.0.tx_hash
+    }
+
+    async fn log_index(&self) -> i64 {
+        self.0.log_index
+    }
+
+    async fn block_number(&self) -> i64 {
+        self.0.block_number
+    }
+
+    async fn block(&self) -> Block {
+        Block(self.0.block_number)
+    }
+
+    async fn from(&self) -> Address {
+        Address(self.0.from_address.clone())
+    }
+
+    async fn to(&self) -> Address {
+        Address(self.0.to_address.clone())
+    }
+
+    async fn amount(&self) -> Decimal {
+        self.0.amount
+    }
+
+    async fn block_time(&self) -> DateTime<Utc> {
+        self.0.block_time
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+}
+
+/// Any well-formed address; totals are zero and blocks null until it takes part in a transfer.
+pub struct Address(String);
+
+impl Address {
+    async fn stats(&self, ctx: &Context<'_>) -> Result<Option<AddressStats>> {
+        ctx.data::<DataLoader<AddressStatsLoader, HashMapCache>>()?
+            .load_one(self.0.clone())
+            .await
+    }
+
+    async fn label_entry(&self, ctx: &Context<'_>) -> Result<Option<AddressLabel>> {
+        ctx.data::<DataLoader<AddressLabelLoader, HashMapCache>>()?
+            .load_one(self.0.clone())
+            .await
+    }
+
+    async fn stat<T: Default>(&self, ctx: &Context<'_>, field: impl FnOnce(AddressStats) -> T) -> Result<T> {
+        Ok(self.stats(ctx).await?.map(field).unwrap_or_default())
+    }
+}
+
+#[Object]
+impl Address {
+    async fn address(&self) -> &str {
+        &self.0
+    }
+
+    async fn label(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        Ok(self.label_entry(ctx).await?.map(|l| l.label))
+    }
+
+    async fn label_category(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        Ok(self.label_entry(ctx).await?.map(|l| l.category))
+    }
+
+    async fn balance(&self, ctx: &Context<'_>) -> Result<Decimal> {
+        self.stat(ctx, |s| s.balance).await
+    }
+
+    async fn total_in(&self, ctx: &Context<'_>) -> Result<Decimal> {
+        self.stat(ctx, |s| s.total_in).await
+    }
+
+    async fn total_out(&self, ctx: &Context<'_>) -> Result<Decimal> {
+        self.stat(ctx, |s| s.total_out).await
+    }
+
+    async fn transfers_in(&self, ctx: &Context<'_>) -> Result<i64> {
+        self.stat(ctx, |s| s.transfers_in).await
+    }
+
+    async fn transfers_out(&self, ctx: &Context<'_>) -> Result<i64> {
+        self.stat(ctx, |s| s.transfers_out).await
+    }
+
+    async fn first_seen_block(&self, ctx: &Context<'_>) -> Result<Option<i64>> {
+        self.stat(ctx, |s| Some(s.first_block)).await
+    }
+
+    async fn last_seen_block(&self, ctx: &Context<'_>) -> Result<Option<i64>> {
+        self.stat(ctx, |s| Some(s.last_block)).await
+    }
+
+    /// Totals over `[since, until)` at hourly resolution.
+    async fn flows(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Flows> {
+        check_window(since, until)?;
+        let flows = ctx
+            .data::<DataLoader<AddressFlowsLoader, HashMapCache>>()?
+            .load_one(FlowsKey { address: self.0.clone(), since, until })
+            .await?;
+        Ok(flows.map(Flows::from).unwrap_or_default())
+    }
+
+    /// Other addresses by combined volume in both directions, windowed like `flows`.
+    #[graphql(complexity = "(limit as usize).saturating_mul(child_complexity)")]
+    async fn counterparties(
+        &self,
+        ctx: &Context<'_>,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        #[graphql(default_with = "DEFAULT_COUNTERPARTIES")] limit: u32,
+    ) -> Result<Vec<CounterpartyNode>> {
+        check_window(since, until)?;
+        if limit == 0 {
+            return Err(to_gql(AppError::BadRequest("limit must be at least 1".to_string())));
+        }
+        let key = CounterpartiesKey { address: self.0.clone(), since, until, limit: limit.min(MAX_COUNTERPARTIES) };
+        let counterparties = ctx
+            .data::<DataLoader<CounterpartiesLoader, HashMapCache>>()?
+            .load_one(key)
+            .await?;
+        Ok(counterparties.unwrap_or_default().into_iter().map(CounterpartyNode::from).collect())
+    }
+
+    #[graphql(complexity = "(first as usize).saturating_mul(child_complexity)")]
+    async fn transfers(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default)] direction: Direction,
+        #[graphql(default)] sort: Sort,
+        #[graphql(default)] order: Order,
+        #[graphql(default_with = "DEFAULT_PAGE_SIZE")] first: u32,
+        after: Option<String>,
+    ) -> Result<TransferPage> {
+        let side = match direction {
+            Direction::Any => TransferSide::Either,
+            Direction::In => TransferSide::To,
+            Direction::Out => TransferSide::From,
+        };
+        let key = AddressTransfersKey { address: self.0.clone(), side, sort, order, first, after };
+        let (items, next_cursor) = ctx
+            .data::<DataLoader<AddressTransfersLoader, HashMapCache>>()?
+            .load_one(key)
+            .await?
+            .unwrap_or_default();
+        Ok(TransferPage { items: items.into_iter().map(Transfer).collect(), next_cursor })
+    }
+}
+
+fn check_window(since: Option<DateTime<Utc>>, until: Option<DateTime<Utc>>) -> Result<()> {
+    match (since, until) {
+        (Some(since), Some(until)) if since >= until => {
+            Err(to_gql(AppError::BadRequest("since must be earlier than until".to_string())))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// A block up to the indexed head. Only blocks with USDC transfers are stored, so the others
+/// have no time or hash; the hashes of blocks indexed before headers were kept are null too.
+pub struct Block(i64);
+
+impl Block {
+    async fn header(&self, ctx: &Context<'_>) -> Result<Option<db::Block>> {
+        ctx.data::<DataLoader<BlockLoader, HashMapCache>>()?
+            .load_one(self.0)
+            .await
+    }
+
+    async fn load_transfers(&self, ctx: &Context<'_>) -> Result<Vec<UsdcTransfer>> {
+        let transfers = ctx
+            .data::<DataLoader<BlockTransfersLoader, HashMapCache>>()?
+            .load_one(self.0)
+            .await?;
+        Ok(transfers.unwrap_or_default())
+    }
+}
+
+#[Object]
+impl Block {
+    async fn number(&self) -> i64 {
+        self.0
+    }
+
+    async fn time(&self, ctx: &Context<'_>) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.header(ctx).await?.map(|b| b.block_time))
+    }
+
+    async fn hash(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        Ok(self.header(ctx).await?.and_then(|b| b.hash))
+    }
+
+    async fn parent_hash(&self, ctx: &Context<'_>) -> Result<Option<String>> {
+        Ok(self.header(ctx).await?.and_then(|b| b.parent_hash))
+    }
+
+    /// The first `first` transfers in log order; `transferCount` counts them all.
+    #[graphql(complexity = "(first as usize).saturating_mul(child_complexity)")]
+    async fn transfers(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default_with = "DEFAULT_BLOCK_TRANSFERS")] first: u32,
+    ) -> Result<Vec<Transfer>> {
+        let transfers = self.load_transfers(ctx).await?;
+        Ok(transfers.into_iter().take(first as usize).map(Transfer).collect())
+    }
+
+    async fn transfer_count(&self, ctx: &Context<'_>) -> Result<usize> {
+        Ok(self.load_transfers(ctx).await?.len())
+    }
+
+    async fn volume(&self, ctx: &Context<'_>) -> Result<Decimal> {
+        Ok(self.load_transfers(ctx).await?.iter().map(|t| t.amount).sum())
+    }
+}
+
+#[derive(SimpleObject, Default)]
+#[graphql(name = "Flows")]
+pub struct Flows {
+    inflow: Decimal,
+    outflow: Decimal,
+    transfers_in: i64,
+    transfers_out: i64,
+}
+
+impl From<AddressFlows> for Flows {
+    fn from(flows: AddressFlows) -> Self {
+        Self {
+            inflow: flows.inflow,
+            outflow: flows.outflow,
+            transfers_in: flows.transfers_in,
+            transfers_out: flows.transfers_out,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "Counterparty")]
+pub struct CounterpartyNode {
+    address: Address,
+    /// Sent by the profiled address to this counterparty.
+    sent: Decimal,
+    /// Received by the profiled address from this counterparty.
+    received: Decimal,
+    volume: Decimal,
+    transfers: i64,
+}
+
+impl From<Counterparty> for CounterpartyNode {
+    fn from(c: Counterparty) -> Self {
+        Self {
+            address: Address(c.address),
+            sent: c.sent,
+            received: c.received,
+            volume: c.volume,
+            transfers: c.transfers,
+        }
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct RankedHolder {
+    rank: usize,
+    address: Address,
+    balance: Decimal,
+}
+
+#[derive(SimpleObject)]
+#[graphql(name = "VolumeBucket")]
+pub struct VolumeBucketNode {
+    bucket_start: DateTime<Utc>,
+    transfers: i64,
+    volume: Decimal,
+    unique_senders: i64,
+    unique_receivers: i64,
+    mints: i64,
+    minted: Decimal,
+    burns: i64,
+    burned: Decimal,
+}
+
+impl From<VolumeBucket> for VolumeBucketNode {
+    fn from(b: VolumeBucket) -> Self {
+        Self {
+            bucket_start: b.bucket_start,
+            transfers: b.transfers,
+            volume: b.volume,
+            unique_senders: b.unique_senders,
+            unique_receivers: b.unique_receivers,
+            mints: b.mints,
+            minted: b.minted,
+            burns: b.burns,
+            burned: b.burned,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::{Duration, Instant};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::Serialize;
+use utoipa::ToSchema;
+use db::{PostgresRepo, ReadData};
+use telemetry::{ingest, metrics};
+
+use crate::AppState;
+
+#[derive(Serialize, ToSchema)]
+pub struct Readiness {
+    /// `ready` or `not_ready`.
+    status: &'static str,
+    checks: Checks,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Checks {
+    database: Check,
+    ws_subscription: Check,
+    last_block_age: Check,
+    lag: Check,
+}
+
+#[derive(Serialize, ToSchema)]
+struct Check {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    observed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    threshold: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<String>,
+}
+
+impl Check {
+    fn threshold(observed: Option<i64>, threshold: u64, unknown: &str) -> Self {
+        match observed {
+            Some(value) => Check {
+                ok: value <= threshold as i64,
+                observed: Some(value),
+                threshold: Some(threshold as i64),
+                message: None,
+            },
+            None => Check {
+                ok: false,
+                observed: None,
+                threshold: Some(threshold as i64),
+                message: Some(unknown.to_string()),
+            },
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "The process is up", body = Object, example = json!({"status": "ok"})))
+)]
+pub async fn live() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    tag = "health",
+    security(()),
+    responses(
+        (status = 200, description = "Every check passed", body = Readiness),
+        (status = 503, description = "At least one check failed", body = Readiness),
+    )
+)]
+pub async fn ready(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let cfg = &state.config.readiness;
+
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let started = Instant::now();
+    let database = match tokio::time::timeout(Duration::from_millis(cfg.db_timeout_ms), repo.ping()).await {
+        Ok(Ok(())) => Check {
+            ok: true,
+            observed: Some(started.elapsed().as_millis() as i64),
+            threshold: Some(cfg.db_timeout_ms as i64),
+            message: None,
+        },
+        Ok(Err(e)) => Check { ok: false, observed: None, threshold: None, message: Some(e.to_string()) },
+        Err(_) => Check {
+            ok: false,
+            observed: None,
+            threshold: Some(cfg.db_timeout_ms as i64),
+            message: Some("database ping timed out".to_string()),
+        },
+    };
+
+    let connected = ingest::ws_connected();
+    let ws_subscription = Check {
+        ok: connected || !cfg.require_ws,
+        observed: Some(connected as i64),
+        threshold: None,
+        message: (!connected).then(|| "live subscription is not connected".to_string()),
+    };
+
+    let last_block_age = Check::threshold(
+        ingest::seconds_since_last_block(),
+        cfg.max_block_age_secs,
+        "no block processed yet",
+    );
+
+    let chain_head = metrics::CHAIN_HEAD.get();
+    let lag_blocks = (chain_head > 0).then(|| (chain_head - metrics::INDEXED_BLOCK.get()).max(0));
+    let mut lag = Check::threshold(lag_blocks, cfg.max_lag_blocks, "chain head not observed yet");
+    // The head is polled from the node; once polling stalls it no longer shows how far behind
+    // the index is.
+    if let Some(age) = ingest::seconds_since_head_read()
+        && age > cfg.max_block_age_secs as i64 {
+        lag.ok = false;
+        lag.message = Some(format!("chain head last read {}s ago", age));
+    }
+
+    let ok = database.ok && ws_subscription.ok && last_block_age.ok && lag.ok;
+    let status = if ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+
+    (
+        status,
+        Json(Readiness {
+            status: if ok { "ready" } else { "not_ready" },
+            checks: Checks { database, ws_subscription, last_block_age, lag },
+        }),
+    )
+}
@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+use common::{AppError, AppResult};
+use db::{LabelExclusion, MoverRank, PgPool, PostgresRepo, ReadData};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::{Holder, Mover};
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::version::ApiVersion;
+
+const DEFAULT_LIMIT: u32 = 20;
+const MAX_LIMIT: u32 = 500;
+const DEFAULT_WINDOW: &str = "24h";
+const MAX_WINDOW_DAYS: i64 = 90;
+
+/// `exclude` is `all` (default: every labeled address), `none`, or a comma-separated list of
+/// label categories such as `system,treasury`.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HoldersQuery {
+    /// 1 to 500 (default 20).
+    limit: Option<u32>,
+    /// `all` (default), `none` or comma-separated label categories.
+    #[param(example = "system,treasury")]
+    exclude: Option<String>,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MoversQuery {
+    /// Trailing window such as `24h`, `7d` or `2w`, at most 90 days (default `24h`).
+    #[param(example = "7d")]
+    window: Option<String>,
+    /// Defaults to `net`.
+    by: Option<MoverRank>,
+    /// 1 to 500 (default 20).
+    limit: Option<u32>,
+    /// `all` (default), `none` or comma-separated label categories.
+    exclude: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Ranked<T> {
+    /// 1-based position.
+    rank: usize,
+    #[serde(flatten)]
+    entry: T,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HoldersBoard {
+    items: Vec<Ranked<Holder>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MoversBoard {
+    window: String,
+    by: MoverRank,
+    since: DateTime<Utc>,
+    items: Vec<Ranked<Mover>>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboard/holders",
+    tag = "analytics",
+    params(HoldersQuery),
+    responses(
+        (status = 200, description = "Largest balances", body = HoldersBoard),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn top_holders(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<HoldersQuery>,
+) -> AppResult<Json<HoldersBoard>> {
+    let limit = parse_limit(query.limit)?;
+    let exclusion = parse_exclusion(query.exclude.as_deref())?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let holders = repo.list_top_holders(limit, &exclusion).await?;
+    let holders = holders.iter().map(|h| Holder::new(h, version)).collect();
+    Ok(Json(HoldersBoard { items: ranked(holders) }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/leaderboard/movers",
+    tag = "analytics",
+    params(MoversQuery),
+    responses(
+        (status = 200, description = "Largest flows over the window", body = MoversBoard),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn top_movers(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<MoversQuery>,
+) -> AppResult<Json<MoversBoard>> {
+    let window = query.window.unwrap_or_else(|| DEFAULT_WINDOW.to_string());
+    let since = Utc::now() - parse_window(&window)?;
+    let by = query.by.unwrap_or(MoverRank::Net);
+    let limit = parse_limit(query.limit)?;
+    let exclusion = parse_exclusion(query.exclude.as_deref())?;
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let movers = repo.list_top_movers(since, by, limit, &exclusion).await?;
+    let movers = movers.iter().map(|m| Mover::new(m, version)).collect();
+    Ok(Json(MoversBoard { window, by, since, items: ranked(movers) }))
+}
+
+fn ranked<T>(entries: Vec<T>) -> Vec<Ranked<T>> {
+    entries
+        .into_iter()
+        .enumerate()
+        .map(|(i, entry)| Ranked { rank: i + 1, entry })
+        .collect()
+}
+
+pub(crate) fn parse_limit(limit: Option<u32>) -> AppResult<u32> {
+    match limit {
+        Some(0) => Err(AppError::BadRequest("limit must be at least 1".to_string())),
+        Some(limit) => Ok(limit.min(MAX_LIMIT)),
+        None => Ok(DEFAULT_LIMIT),
+    }
+}
+
+pub(crate) fn parse_exclusion(value: Option<&str>) -> AppResult<LabelExclusion> {
+    match value.map(str::trim) {
+        None | Some("all") => Ok(LabelExclusion::AllLabeled),
+        Some("none") => Ok(LabelExclusion::Nothing),
+        Some(list) => {
+            let categories: Vec<String> = list.split(',').map(|c| c.trim().to_string()).collect();
+            let valid = categories.iter().all(|c| {
+                !c.is_empty() && c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-')
+            });
+            if !valid {
+                return Err(AppError::BadRequest(format!(
+                    "exclude must be 'all', 'none' or a comma-separated list of label categories, got '{}'",
+                    list
+                )));
+            }
+            Ok(LabelExclusion::Categories(categories))
+        }
+    }
+}
+
+/// Parses windows such as `1h`, `24h`, `7d` or `2w`.
+pub(crate) fn parse_window(value: &str) -> AppResult<Duration> {
+    let invalid = || {
+        AppError::BadRequest(format!(
+            "window must look like 24h, 7d or 2w and span at most {} days, got '{}'",
+            MAX_WINDOW_DAYS, value
+        ))
+    };
+    let (amount, unit) = match value.char_indices().last() {
+        Some((i, unit)) => (&value[..i], unit),
+        None => return Err(invalid()),
+    };
+    let amount: i64 = amount.parse().map_err(|_| invalid())?;
+    let hours_per_unit = match unit {
+        'h' => 1,
+        'd' => 24,
+        'w' => 24 * 7,
+        _ => return Err(invalid()),
+    };
+    let hours = amount.checked_mul(hours_per_unit).ok_or_else(invalid)?;
+    if !(1..=MAX_WINDOW_DAYS * 24).contains(&hours) {
+        return Err(invalid());
+    }
+    Ok(Duration::hours(hours))
+}
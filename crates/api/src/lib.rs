@@ -1,66 +1,179 @@
+mod address;
+mod admin;
+pub mod auth;
+mod blocks;
+mod bridge;
+mod cursor;
+mod dto;
+mod export;
+mod extract;
+mod frozen;
+mod governance;
+mod graph;
+mod graphql;
+mod health;
+mod leaderboard;
+mod openapi;
+mod risk;
+mod stats;
+mod stream;
+mod transfers;
+mod validate;
+mod version;
+mod ws;
+
 use axum::{
-    extract::{Path, Query, State},
-    routing::get,
+    extract::{FromRef, State},
+    http::header,
+    middleware,
+    response::IntoResponse,
+    routing::{delete, get, post},
     Json, Router,
 };
-use serde::Deserialize;
-use chrono::{DateTime, Utc};
 use std::sync::Arc;
-use db::{PgPool, UsdcTransfer, PostgresRepo, ReadData};
-
-#[derive(Deserialize)]
-struct TransferFilter {
-    from: Option<String>,
-    to: Option<String>,
-    created_before: Option<DateTime<Utc>>,
-    created_after: Option<DateTime<Utc>>,
-    page: Option<u32>,
-    limit: Option<u32>,
+use tower_http::compression::CompressionLayer;
+use tower_http::trace::{DefaultMakeSpan, DefaultOnResponse, TraceLayer};
+use common::{AppError, AppResult};
+use config::AppConfig;
+use db::feed::TransferFeed;
+use db::{ApiScope, PgPool, PostgresRepo, ReadData};
+use serde::Serialize;
+use tracing::Level;
+use utoipa::ToSchema;
+
+pub use auth::Auth;
+pub use openapi::{openapi, ApiDoc};
+
+#[derive(Clone)]
+pub struct AppState {
+    pub pool: Arc<PgPool>,
+    pub config: Arc<AppConfig>,
+    pub feed: TransferFeed,
+    pub graphql: graphql::ApiSchema,
+    pub auth: Auth,
+}
+
+impl FromRef<AppState> for Arc<PgPool> {
+    fn from_ref(state: &AppState) -> Self {
+        state.pool.clone()
+    }
 }
 
-pub fn create_router(pool: Arc<PgPool>) -> Router {
-    Router::new()
-        .route("/health", get(health_check))
+pub fn create_router(pool: Arc<PgPool>, config: Arc<AppConfig>, feed: TransferFeed, auth: Auth) -> Router {
+    let scoped = |scope: ApiScope, routes: Router<AppState>| {
+        routes.route_layer(middleware::from_fn_with_state(auth.guard(scope), auth::authorize))
+    };
+
+    let public = Router::new()
+        .route("/health", get(health::live))
+        .route("/health/live", get(health::live))
+        .route("/health/ready", get(health::ready))
+        .route("/metrics", get(metrics))
+        .route("/graphql", get(graphql::graphiql))
+        .route("/openapi.json", get(openapi::openapi_json))
+        .route("/docs", get(openapi::docs));
+
+    let read = Router::new()
         .route("/last_block", get(get_last_block))
-        .route("/tx/{id}", get(get_transfer_by_id))
-        .route("/tx", get(list_transfers))
-        .with_state(pool)
+        .route("/tx/{id}", get(transfers::get_transfer_by_id))
+        .route("/tx/hash", post(transfers::lookup_transfers_by_tx_hashes))
+        .route("/tx/hash/{tx_hash}", get(transfers::get_transfers_by_tx_hash))
+        .route("/tx/hash/{tx_hash}/{log_index}", get(transfers::get_transfer_by_tx_hash_and_log_index))
+        .route("/tx", get(transfers::list_transfers))
+        .route("/address/{address}", get(address::get_address_profile))
+        .route("/stats/volume", get(stats::get_volume))
+        .route("/leaderboard/holders", get(leaderboard::top_holders))
+        .route("/leaderboard/movers", get(leaderboard::top_movers));
+
+    // Added after versioning, so there is no legacy alias.
+    let read_v1 = Router::new()
+        .route("/block/by-time", get(blocks::get_block_by_time))
+        .route("/block/{number}", get(blocks::get_block))
+        .route("/graph", get(graph::get_graph))
+        .route("/address/{address}/risk", get(risk::get_address_risk))
+        .route("/governance/events", get(governance::list_admin_events))
+        .route("/governance/paused", get(governance::get_pause_history))
+        .route("/governance/minters", get(governance::list_minters))
+        .route("/frozen", get(frozen::list_frozen))
+        .route("/stats/supply", get(stats::get_supply))
+        .route("/stats/supply/history", get(stats::get_supply_history))
+        .route("/bridge/transfers", get(bridge::list_bridge_transfers));
+
+    let graphql = Router::new().route("/graphql", post(graphql::graphql));
+
+    let export = Router::new().route(
+        "/tx/export",
+        get(export::export_transfers).layer(CompressionLayer::new().gzip(true)),
+    );
+
+    let stream = Router::new()
+        .route("/tx/stream", get(stream::stream_transfers))
+        .route("/ws", get(ws::subscribe));
+
+    let admin = Router::new()
+        .route("/admin/keys", get(admin::list_keys).post(admin::create_key))
+        .route("/admin/keys/{id}", delete(admin::revoke_key))
+        .route("/admin/usage", get(admin::usage));
+
+    // The same handlers back `/v1` and the deprecated unprefixed aliases; `ApiVersion` selects
+    // the response format.
+    let versioned = scoped(ApiScope::Read, read)
+        .merge(scoped(ApiScope::Export, export))
+        .merge(scoped(ApiScope::Stream, stream))
+        .merge(scoped(ApiScope::Admin, admin));
+
+    let state = AppState { pool, config, feed, graphql: graphql::build_schema(), auth: auth.clone() };
+    public
+        .merge(scoped(ApiScope::Read, graphql))
+        .nest("/v1", versioned.clone().merge(scoped(ApiScope::Read, read_v1)))
+        .merge(versioned.layer(middleware::from_fn(version::legacy)))
+        .fallback(not_found)
+        // At INFO so the default filter keeps a span per request, with its SQL spans under it.
+        .layer(
+            TraceLayer::new_for_http()
+                .make_span_with(DefaultMakeSpan::new().level(Level::INFO))
+                .on_response(DefaultOnResponse::new().level(Level::INFO)),
+        )
+        .with_state(state)
 }
 
-async fn health_check() -> Json<serde_json::Value> {
-    Json(serde_json::json!({ "status": "ok" }))
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    security(()),
+    responses((status = 200, description = "Prometheus text exposition", content_type = "text/plain", body = String))
+)]
+async fn metrics() -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        telemetry::metrics::render(),
+    )
 }
 
-async fn get_last_block(State(pool): State<Arc<PgPool>>) -> Json<serde_json::Value> {
-    let repo = PostgresRepo::new(pool.as_ref().clone());
-    let last_block = repo.get_last_block().await.unwrap_or(0);
-    Json(serde_json::json!({ "last_block": last_block }))
+async fn not_found() -> AppError {
+    AppError::NotFound("no such route".to_string())
 }
 
-async fn get_transfer_by_id(
-    State(pool): State<Arc<PgPool>>,
-    Path(id): Path<i64>,
-) -> Json<Option<UsdcTransfer>> {
-    let repo = PostgresRepo::new(pool.as_ref().clone());
-    let tx = repo.get_transfer_by_id(id).await.unwrap_or(None);
-    Json(tx)
+#[derive(Serialize, ToSchema)]
+struct LastBlock {
+    /// Last block the indexer has fully processed.
+    #[schema(example = 18573214)]
+    last_block: u64,
 }
 
-async fn list_transfers(
-    State(pool): State<Arc<PgPool>>,
-    Query(filter): Query<TransferFilter>,
-) -> Json<Vec<UsdcTransfer>> {
+#[utoipa::path(
+    get,
+    path = "/v1/last_block",
+    tag = "health",
+    responses(
+        (status = 200, body = LastBlock),
+        (status = 500, response = openapi::Internal),
+        (status = 503, response = openapi::Unavailable),
+    )
+)]
+async fn get_last_block(State(pool): State<Arc<PgPool>>) -> AppResult<Json<LastBlock>> {
     let repo = PostgresRepo::new(pool.as_ref().clone());
-    let txs = repo
-        .list_transfers(
-            filter.from,
-            filter.to,
-            filter.created_before,
-            filter.created_after,
-            filter.page,
-            filter.limit,
-        )
-        .await
-        .unwrap_or_default();
-    Json(txs)
+    let last_block = repo.get_last_block().await?;
+    Ok(Json(LastBlock { last_block }))
 }
@@ -0,0 +1,223 @@
+use axum::{response::Html, Json};
+use common::Problem;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::openapi::{Ref, RefOr};
+use utoipa::{Modify, OpenApi, ToResponse};
+
+use crate::auth::{API_KEY_HEADER, API_KEY_PARAM};
+use crate::{address, admin, blocks, bridge, export, frozen, governance, graph, graphql, health, leaderboard, risk, stats, stream, transfers, ws};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "USDC transfer tracker",
+        description = "Indexed USDC `Transfer` events with filters, aggregates and live feeds. \
+                       Errors are RFC 9457 problem details; amounts are decimal strings with six \
+                       decimals and addresses are EIP-55 checksummed. \
+                       Requests need an API key with the scope of the endpoint (`read`, `stream`, \
+                       `export` or `admin`, which implies the others), sent as `Authorization: Bearer` \
+                       or `X-API-Key`; `/v1/tx/stream` and `/v1/ws` also accept an `api_key` query parameter. \
+                       Keys over their rate limit or daily quota get 429 with `Retry-After`. \
+                       Every `/v1` route is also served without the prefix in the pre-versioning format \
+                       (lowercase addresses, amounts as stored); those aliases are deprecated and answer \
+                       with `Deprecation`, `Sunset` and a `Link` to their `/v1` successor."
+    ),
+    modifiers(&Security),
+    security(("bearer" = []), ("api_key" = [])),
+    paths(
+        health::live,
+        health::ready,
+        crate::metrics,
+        crate::get_last_block,
+        transfers::get_transfer_by_id,
+        transfers::lookup_transfers_by_tx_hashes,
+        transfers::get_transfers_by_tx_hash,
+        transfers::get_transfer_by_tx_hash_and_log_index,
+        transfers::list_transfers,
+        export::export_transfers,
+        stream::stream_transfers,
+        ws::subscribe,
+        blocks::get_block,
+        blocks::get_block_by_time,
+        address::get_address_profile,
+        risk::get_address_risk,
+        frozen::list_frozen,
+        governance::list_admin_events,
+        governance::get_pause_history,
+        governance::list_minters,
+        stats::get_volume,
+        stats::get_supply,
+        stats::get_supply_history,
+        graph::get_graph,
+        bridge::list_bridge_transfers,
+        leaderboard::top_holders,
+        leaderboard::top_movers,
+        graphql::graphql,
+        admin::list_keys,
+        admin::create_key,
+        admin::revoke_key,
+        admin::usage,
+        openapi_json,
+    ),
+    // Types only used by parameters and response components are not collected automatically.
+    components(
+        schemas(Problem, db::TransferSort, db::SortOrder, export::ExportFormat, blocks::Closest, graph::GraphFormat, db::RiskModel, db::BridgeStatus),
+        responses(BadRequest, Unauthorized, Forbidden, NotFound, TooManyRequests, Internal, Unavailable)
+    ),
+    tags(
+        (name = "health", description = "Liveness, readiness and metrics"),
+        (name = "transfers", description = "Individual transfers, filtered lists, exports and live feeds"),
+        (name = "blocks", description = "Block headers, the transfers in them and time-to-block lookups"),
+        (name = "analytics", description = "Address profiles, volume and supply series and leaderboards"),
+        (name = "compliance", description = "Blacklisted addresses, their frozen balances and exposure to them"),
+        (name = "governance", description = "Administration of the USDC contract: blacklisting, pauses, minters, roles and upgrades"),
+        (name = "bridge", description = "Cross-chain CCTP transfers: burns on one chain matched to mints on another"),
+        (name = "admin", description = "API keys and their usage; needs the `admin` scope"),
+        (name = "meta", description = "Schemas of this API"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Declares the key schemes and adds the auth failures to every operation that needs a key,
+/// i.e. those that do not override the document-wide requirement with an empty one.
+struct Security;
+
+impl Modify for Security {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+        components.add_security_scheme(
+            "api_key_query",
+            SecurityScheme::ApiKey(ApiKey::Query(ApiKeyValue::new(API_KEY_PARAM))),
+        );
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [&mut item.get, &mut item.put, &mut item.post, &mut item.delete, &mut item.patch];
+            for operation in operations.into_iter().flatten() {
+                if operation.security.as_ref().is_some_and(|requirements| {
+                    requirements.iter().any(|r| *r == Default::default())
+                }) {
+                    continue;
+                }
+                for (status, name) in [("401", "Unauthorized"), ("403", "Forbidden"), ("429", "TooManyRequests")] {
+                    operation
+                        .responses
+                        .responses
+                        .entry(status.to_string())
+                        .or_insert_with(|| RefOr::Ref(Ref::from_response_name(name)));
+                }
+            }
+        }
+    }
+}
+
+/// The document served at `/openapi.json`.
+pub fn openapi() -> utoipa::openapi::OpenApi {
+    ApiDoc::openapi()
+}
+
+// The response types below only describe error bodies in the spec; handlers return `AppError`.
+
+/// Invalid path, query or body parameters.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "bad-request", "title": "Bad Request", "status": 400, "detail": "min_amount must not exceed max_amount"})
+)]
+#[allow(dead_code)]
+pub struct BadRequest(Problem);
+
+/// No API key was sent, or it is unknown or revoked.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "unauthorized", "title": "Unauthorized", "status": 401, "detail": "unknown or revoked API key"})
+)]
+#[allow(dead_code)]
+pub struct Unauthorized(Problem);
+
+/// The API key lacks the scope this endpoint needs.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "forbidden", "title": "Forbidden", "status": 403, "detail": "this key lacks the 'export' scope"})
+)]
+#[allow(dead_code)]
+pub struct Forbidden(Problem);
+
+/// The requested resource does not exist.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "not-found", "title": "Not Found", "status": 404, "detail": "transfer 42 does not exist"})
+)]
+#[allow(dead_code)]
+pub struct NotFound(Problem);
+
+/// The key's rate limit or daily quota, or a concurrency limit, was reached; key limits send
+/// `Retry-After` with the seconds to wait.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "too-many-requests", "title": "Too Many Requests", "status": 429, "detail": "rate limit of 600 requests per minute exceeded"})
+)]
+#[allow(dead_code)]
+pub struct TooManyRequests(Problem);
+
+/// Unexpected server-side failure.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "internal", "title": "Internal Server Error", "status": 500, "detail": "an internal error occurred"})
+)]
+#[allow(dead_code)]
+pub struct Internal(Problem);
+
+/// The database is unreachable.
+#[derive(ToResponse)]
+#[response(
+    content_type = "application/problem+json",
+    example = json!({"type": "unavailable", "title": "Service Unavailable", "status": 503, "detail": "the database is currently unavailable"})
+)]
+#[allow(dead_code)]
+pub struct Unavailable(Problem);
+
+#[utoipa::path(
+    get,
+    path = "/openapi.json",
+    tag = "meta",
+    security(()),
+    responses((status = 200, description = "This document", content_type = "application/json", body = Object))
+)]
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi())
+}
+
+/// Swagger UI for `/openapi.json`, loaded from a CDN.
+pub async fn docs() -> Html<&'static str> {
+    Html(DOCS_HTML)
+}
+
+const DOCS_HTML: &str = r##"<!doctype html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>USDC transfer tracker API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js" crossorigin></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
@@ -0,0 +1,110 @@
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use common::{AppError, AppResult};
+use db::{PostgresRepo, ReadData, RiskModel};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::RiskPath;
+use crate::extract::{ApiPath, ApiQuery};
+use crate::openapi::{BadRequest, Internal, NotFound, Unavailable};
+use crate::validate::normalize_address;
+use crate::version::ApiVersion;
+use crate::AppState;
+
+const SCORE_SCALE: u32 = 8;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RiskQuery {
+    /// Defaults to the model of the scheduled scoring job (`risk.model`).
+    model: Option<RiskModel>,
+}
+
+/// The scoring run the scores come from.
+#[derive(Serialize, ToSchema)]
+pub struct RiskRunSummary {
+    id: i64,
+    /// Transfers after this block are not reflected yet.
+    through_block: i64,
+    finished_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct AddressRisk {
+    #[schema(example = "0x28C6c06298d514Db089934071355E5743bf21d60")]
+    address: String,
+    model: RiskModel,
+    /// Share of everything the address received that traces back to a blacklisted address,
+    /// from 0 to 1; 1 for blacklisted addresses.
+    #[schema(example = "0.03125000")]
+    score: String,
+    #[schema(example = "1250.500000")]
+    tainted_received: String,
+    /// Fewest transfers between a blacklisted address and this one; null when untainted.
+    hops: Option<i32>,
+    /// Whether the latest blacklist event for the address is `Blacklisted`.
+    blacklisted: bool,
+    /// Time of that event.
+    blacklisted_at: Option<DateTime<Utc>>,
+    run: RiskRunSummary,
+    /// The largest sources of taint, with the shortest route from each.
+    paths: Vec<RiskPath>,
+}
+
+/// Scores are computed by a batch job (`tracker risk`, or every `risk.interval_secs`), so
+/// they lag the chain; `run.through_block` tells by how much. Addresses the job found no
+/// taint for get a score of 0.
+#[utoipa::path(
+    get,
+    path = "/v1/address/{address}/risk",
+    tag = "compliance",
+    params(("address" = String, Path, description = "0x-prefixed address"), RiskQuery),
+    responses(
+        (status = 200, description = "Exposure of the address to blacklisted addresses", body = AddressRisk),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_address_risk(
+    State(state): State<AppState>,
+    version: ApiVersion,
+    ApiPath(address): ApiPath<String>,
+    ApiQuery(query): ApiQuery<RiskQuery>,
+) -> AppResult<Json<AddressRisk>> {
+    let address = normalize_address("address", &address)?;
+    let model = match query.model {
+        Some(model) => model,
+        // Validated when the config is loaded.
+        None => state.config.risk.model.parse().unwrap_or_default(),
+    };
+
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let run = repo
+        .get_latest_risk_run(model)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("no {} risk scores have been computed yet", model.as_str())))?;
+    let risk = repo.get_address_risk(model, &address).await?;
+    let paths = match &risk {
+        Some(_) => repo.list_address_risk_paths(model, &address).await?,
+        None => Vec::new(),
+    };
+    let mut score = risk.as_ref().map_or(Decimal::ZERO, |r| r.score);
+    score.rescale(SCORE_SCALE);
+    let blacklist = repo.get_latest_blacklist_event(&address).await?.filter(|event| event.blacklisted);
+
+    Ok(Json(AddressRisk {
+        address: version.address(&address),
+        model,
+        score: score.to_string(),
+        tainted_received: version.amount(risk.as_ref().map_or(Decimal::ZERO, |r| r.tainted_received)),
+        hops: risk.as_ref().map(|r| r.hops),
+        blacklisted: blacklist.is_some(),
+        blacklisted_at: blacklist.map(|event| event.block_time),
+        run: RiskRunSummary { id: run.id, through_block: run.through_block, finished_at: run.finished_at },
+        paths: paths.iter().map(|p| RiskPath::new(p, version)).collect(),
+    }))
+}
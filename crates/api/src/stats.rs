@@ -0,0 +1,202 @@
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Duration, Utc};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData, VolumeInterval};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::dto::{SupplyBucket, VolumeBucket};
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::version::ApiVersion;
+
+/// Buckets returned when `from` is omitted.
+const DEFAULT_BUCKETS: i32 = 48;
+const MAX_BUCKETS: i64 = 2_000;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VolumeQuery {
+    interval: VolumeInterval,
+    /// Start of the series, rounded down to the interval (default 48 buckets before `to`).
+    from: Option<DateTime<Utc>>,
+    /// Exclusive end of the series (default now).
+    to: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct VolumeSeries {
+    interval: VolumeInterval,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    buckets: Vec<VolumeBucket>,
+}
+
+/// Totals as of `indexed_through_block`.
+#[derive(Serialize, ToSchema)]
+pub struct Supply {
+    /// Minted minus burned.
+    #[schema(example = "32518231002.515400")]
+    total_supply: String,
+    /// Held by currently blacklisted addresses, which cannot move it.
+    #[schema(example = "108532115.003100")]
+    frozen: String,
+    #[schema(example = "32409698887.512300")]
+    circulating_excluding_frozen: String,
+    frozen_addresses: i64,
+    indexed_through_block: u64,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SupplySeries {
+    interval: VolumeInterval,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    buckets: Vec<SupplyBucket>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats/volume",
+    tag = "analytics",
+    params(VolumeQuery),
+    responses(
+        (status = 200, description = "Every bucket in the range, empty ones included (at most 2000)", body = VolumeSeries),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_volume(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<VolumeQuery>,
+) -> AppResult<Json<VolumeSeries>> {
+    let (from, to) = volume_range(query.interval, query.from, query.to)?;
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let buckets = repo.list_volume_buckets(query.interval, from, to).await?;
+    let buckets = buckets.iter().map(|b| VolumeBucket::new(b, version)).collect();
+    Ok(Json(VolumeSeries { interval: query.interval, from, to, buckets }))
+}
+
+/// Resolves the optional bounds of a volume series and checks the bucket count.
+pub(crate) fn volume_range(
+    interval: VolumeInterval,
+    from: Option<DateTime<Utc>>,
+    to: Option<DateTime<Utc>>,
+) -> AppResult<(DateTime<Utc>, DateTime<Utc>)> {
+    let step = interval.duration();
+    let to = to.unwrap_or_else(Utc::now);
+    let from = from.unwrap_or(to - step * DEFAULT_BUCKETS);
+    if from >= to {
+        return Err(AppError::BadRequest("from must be earlier than to".to_string()));
+    }
+    // The series starts at the bucket holding `from`, so its partial first bucket counts too.
+    let step_ms = step.num_milliseconds();
+    let buckets = ((to - bucket_start(interval, from)).num_milliseconds() + step_ms - 1) / step_ms;
+    if buckets > MAX_BUCKETS {
+        return Err(AppError::BadRequest(format!(
+            "at most {} {} buckets per request",
+            MAX_BUCKETS,
+            interval.as_str()
+        )));
+    }
+    Ok((from, to))
+}
+
+/// Start of the bucket holding `at`, as `date_trunc` computes it in UTC.
+fn bucket_start(interval: VolumeInterval, at: DateTime<Utc>) -> DateTime<Utc> {
+    // date_trunc('week') starts weeks on Monday; 1970-01-05 is the first Monday after the epoch.
+    let origin = match interval {
+        VolumeInterval::Week => DateTime::UNIX_EPOCH + Duration::days(4),
+        VolumeInterval::Hour | VolumeInterval::Day => DateTime::UNIX_EPOCH,
+    };
+    let step_ms = interval.duration().num_milliseconds();
+    origin + Duration::milliseconds((at - origin).num_milliseconds().div_euclid(step_ms) * step_ms)
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/stats/supply",
+    tag = "analytics",
+    responses(
+        (status = 200, description = "Current total, frozen and circulating supply", body = Supply),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_supply(State(pool): State<Arc<PgPool>>, version: ApiVersion) -> AppResult<Json<Supply>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let supply = repo.get_supply().await?;
+    let indexed_through_block = repo.get_last_block().await?;
+    Ok(Json(Supply {
+        total_supply: version.amount(supply.total_supply),
+        frozen: version.amount(supply.frozen),
+        circulating_excluding_frozen: version.amount(supply.total_supply - supply.frozen),
+        frozen_addresses: supply.frozen_addresses,
+        indexed_through_block,
+    }))
+}
+
+/// Each bucket counts the addresses blacklisted at its end, with their balances from the
+/// hourly ledger behind `/v1/address/{address}`.
+#[utoipa::path(
+    get,
+    path = "/v1/stats/supply/history",
+    tag = "analytics",
+    params(VolumeQuery),
+    responses(
+        (status = 200, description = "Supply at the end of every bucket in the range (at most 2000)", body = SupplySeries),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_supply_history(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(query): ApiQuery<VolumeQuery>,
+) -> AppResult<Json<SupplySeries>> {
+    let (from, to) = volume_range(query.interval, query.from, query.to)?;
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let buckets = repo.list_supply_buckets(query.interval, from, to).await?;
+    let buckets = buckets.iter().map(|b| SupplyBucket::new(b, version)).collect();
+    Ok(Json(SupplySeries { interval: query.interval, from, to, buckets }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn bucket_start_matches_date_trunc() {
+        assert_eq!(bucket_start(VolumeInterval::Hour, at("2025-11-20T10:59:59.5Z")), at("2025-11-20T10:00:00Z"));
+        assert_eq!(bucket_start(VolumeInterval::Day, at("2025-11-20T10:15:00Z")), at("2025-11-20T00:00:00Z"));
+        // 2025-11-20 is a Thursday.
+        assert_eq!(bucket_start(VolumeInterval::Week, at("2025-11-20T10:15:00Z")), at("2025-11-17T00:00:00Z"));
+        assert_eq!(bucket_start(VolumeInterval::Week, at("2025-11-17T00:00:00Z")), at("2025-11-17T00:00:00Z"));
+    }
+
+    #[test]
+    fn counts_the_partial_first_bucket() {
+        let to = at("2025-11-20T10:00:00Z");
+
+        let from = to - Duration::hours(MAX_BUCKETS);
+        assert!(volume_range(VolumeInterval::Hour, Some(from), Some(to)).is_ok());
+
+        // Less than 2001 hours before `to`, but its bucket starts 2001 hours before it.
+        let from = from - Duration::minutes(30);
+        assert!(matches!(
+            volume_range(VolumeInterval::Hour, Some(from), Some(to)),
+            Err(AppError::BadRequest(_))
+        ));
+    }
+}
@@ -0,0 +1,122 @@
+use std::convert::Infallible;
+
+use async_stream::stream;
+use axum::{
+    extract::State,
+    http::HeaderMap,
+    response::sse::{Event, KeepAlive, Sse},
+};
+use common::{AppError, AppResult};
+use db::feed::FeedEvent;
+use db::{PostgresRepo, ReadData, SequencedTransfer};
+use futures::Stream;
+use tokio::sync::broadcast::error::RecvError;
+use tracing::{error, warn};
+
+use crate::dto::Transfer;
+use crate::extract::ApiQuery;
+use crate::openapi::{BadRequest, Internal, Unavailable};
+use crate::transfers::TransferFilter;
+use crate::version::ApiVersion;
+use crate::AppState;
+
+/// Rows fetched per query while catching a client up from the database.
+const REPLAY_BATCH: u32 = 500;
+
+/// Live feed of committed transfers matching the `/tx` filters (sort, cursor and limit are
+/// ignored). Event ids follow commit order, so a `Last-Event-ID` header replays every matching
+/// transfer committed after that event before switching to live events.
+#[utoipa::path(
+    get,
+    path = "/v1/tx/stream",
+    tag = "transfers",
+    security(("bearer" = []), ("api_key" = []), ("api_key_query" = [])),
+    params(
+        ("Last-Event-ID" = Option<i64>, Header, description = "Replay transfers committed after this event"),
+        TransferFilter,
+    ),
+    responses(
+        (status = 200, description = "Server-sent `transfer` events whose data is a `Transfer`",
+            content_type = "text/event-stream", body = Transfer),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn stream_transfers(
+    State(state): State<AppState>,
+    version: ApiVersion,
+    headers: HeaderMap,
+    ApiQuery(filter): ApiQuery<TransferFilter>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    let query = filter.into_query()?;
+    let last_event_id = headers
+        .get("last-event-id")
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|v| v.trim().parse::<i64>().ok())
+                .ok_or_else(|| AppError::BadRequest("Last-Event-ID must be an event id".to_string()))
+        })
+        .transpose()?;
+
+    // Subscribe before reading the starting point so nothing committed in between is lost.
+    let mut events = state.feed.subscribe();
+    let repo = PostgresRepo::new(state.pool.as_ref().clone());
+    let start_seq = match last_event_id {
+        Some(seq) => seq,
+        None => repo.get_max_stream_seq().await?,
+    };
+
+    let stream = stream! {
+        // Last position already sent or skipped by the filters.
+        let mut seen_seq = start_seq;
+        let mut catch_up = last_event_id.is_some();
+        loop {
+            while catch_up {
+                match repo.list_transfers_after_seq(&query, seen_seq, REPLAY_BATCH).await {
+                    Ok(batch) => {
+                        catch_up = batch.len() == REPLAY_BATCH as usize;
+                        for transfer in batch {
+                            seen_seq = transfer.stream_seq;
+                            yield Ok(event(&transfer, version));
+                        }
+                    }
+                    Err(e) => {
+                        // Ending the stream makes the client reconnect with its Last-Event-ID.
+                        error!(error = %e, after_seq = seen_seq, "transfer stream replay failed");
+                        return;
+                    }
+                }
+            }
+            match events.recv().await {
+                Ok(FeedEvent::Transfer(transfer)) => {
+                    if transfer.stream_seq <= seen_seq {
+                        continue;
+                    }
+                    seen_seq = transfer.stream_seq;
+                    if query.matches(&transfer.transfer) {
+                        yield Ok(event(&transfer, version));
+                    }
+                }
+                Ok(FeedEvent::Resync) => catch_up = true,
+                Err(RecvError::Lagged(skipped)) => {
+                    warn!(skipped, "transfer stream subscriber lagged; replaying from the database");
+                    catch_up = true;
+                }
+                Err(RecvError::Closed) => return,
+            }
+        }
+    };
+
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+fn event(transfer: &SequencedTransfer, version: ApiVersion) -> Event {
+    Event::default()
+        .id(transfer.stream_seq.to_string())
+        .event("transfer")
+        .json_data(Transfer::new(&transfer.transfer, version))
+        .unwrap_or_else(|_| Event::default().comment("unserializable transfer"))
+}
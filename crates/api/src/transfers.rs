@@ -0,0 +1,435 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+
+use axum::{extract::State, Json};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use common::{AppError, AppResult};
+use db::{PgPool, PostgresRepo, ReadData, SortOrder, TransferQuery, TransferSort, UsdcTransfer};
+use rust_decimal::Decimal;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::cursor;
+use crate::dto::Transfer;
+use crate::extract::{ApiJson, ApiPath, ApiQuery};
+use crate::openapi::{BadRequest, Internal, NotFound, Unavailable};
+use crate::validate::{block_number, normalize_address_list, normalize_tx_hash};
+use crate::version::ApiVersion;
+
+/// Upper bound on hashes accepted by the bulk lookup.
+const MAX_BULK_TX_HASHES: usize = 500;
+const DEFAULT_PAGE_LIMIT: u32 = 20;
+const MAX_PAGE_LIMIT: u32 = 100;
+/// Upper bound on addresses in each of `from`, `to` and `address`.
+const MAX_FILTER_ADDRESSES: usize = 50;
+const USDC_DECIMALS: u32 = 6;
+
+/// Query string of `/tx`. `from`, `to` and `address` take comma-separated lists; amounts are
+/// in USDC units.
+#[derive(Deserialize, Default, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransferFilter {
+    /// Sender addresses, comma-separated (at most 50).
+    #[param(example = "0x28c6c06298d514db089934071355e5743bf21d60")]
+    pub(crate) from: Option<String>,
+    /// Recipient addresses, comma-separated (at most 50).
+    pub(crate) to: Option<String>,
+    /// Addresses on either side, comma-separated (at most 50).
+    pub(crate) address: Option<String>,
+    /// Leave out mints and burns.
+    pub(crate) exclude_zero_address: Option<bool>,
+    /// Inclusive lower bound in USDC, at most six decimals.
+    #[param(example = "1000")]
+    pub(crate) min_amount: Option<Decimal>,
+    /// Inclusive upper bound in USDC, at most six decimals.
+    pub(crate) max_amount: Option<Decimal>,
+    /// Inclusive first block.
+    pub(crate) from_block: Option<u64>,
+    /// Inclusive last block.
+    pub(crate) to_block: Option<u64>,
+    /// Exclusive upper bound on block time.
+    pub(crate) created_before: Option<DateTime<Utc>>,
+    /// Exclusive lower bound on block time.
+    pub(crate) created_after: Option<DateTime<Utc>>,
+    /// Defaults to `time`.
+    pub(crate) sort: Option<TransferSort>,
+    /// Defaults to `desc`.
+    pub(crate) order: Option<SortOrder>,
+    /// `next_cursor` of the previous page, valid only with the same sort and order.
+    pub(crate) cursor: Option<String>,
+    /// Page size, 1 to 100 (default 20).
+    pub(crate) limit: Option<u32>,
+    /// The pre-cursor page number, refused rather than ignored so clients looping over it
+    /// do not get the first page forever.
+    #[param(ignore)]
+    pub(crate) page: Option<String>,
+}
+
+impl TransferFilter {
+    pub(crate) fn into_query(self) -> AppResult<TransferQuery> {
+        let addresses = |field: &str, value: Option<&str>| {
+            value
+                .map(|v| normalize_address_list(field, v, MAX_FILTER_ADDRESSES))
+                .transpose()
+                .map(Option::unwrap_or_default)
+        };
+        if self.page.is_some() {
+            return Err(AppError::BadRequest(
+                "page is no longer supported; pass the next_cursor of the previous page as cursor".to_string(),
+            ));
+        }
+        let from = addresses("from", self.from.as_deref())?;
+        let to = addresses("to", self.to.as_deref())?;
+        let address = addresses("address", self.address.as_deref())?;
+
+        for (field, amount) in [("min_amount", self.min_amount), ("max_amount", self.max_amount)] {
+            if let Some(amount) = amount
+                && (amount.is_sign_negative() || amount.scale() > USDC_DECIMALS) {
+                return Err(AppError::BadRequest(format!(
+                    "{} must be a non-negative amount with at most {} decimals",
+                    field, USDC_DECIMALS
+                )));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount)
+            && min > max {
+            return Err(AppError::BadRequest("min_amount must not exceed max_amount".to_string()));
+        }
+        let from_block = block_number("from_block", self.from_block)?;
+        let to_block = block_number("to_block", self.to_block)?;
+        if let (Some(from_block), Some(to_block)) = (from_block, to_block)
+            && from_block > to_block {
+            return Err(AppError::BadRequest("from_block must not exceed to_block".to_string()));
+        }
+        if self.limit == Some(0) {
+            return Err(AppError::BadRequest("limit must be at least 1".to_string()));
+        }
+        if let (Some(before), Some(after)) = (self.created_before, self.created_after)
+            && after >= before {
+            return Err(AppError::BadRequest("created_after must be earlier than created_before".to_string()));
+        }
+
+        let sort = self.sort.unwrap_or_default();
+        let order = self.order.unwrap_or_default();
+        let after = self
+            .cursor
+            .as_deref()
+            .map(|c| cursor::decode(c, sort, order))
+            .transpose()?;
+
+        Ok(TransferQuery {
+            from,
+            to,
+            address,
+            exclude_zero_address: self.exclude_zero_address.unwrap_or(false),
+            min_amount: self.min_amount,
+            max_amount: self.max_amount,
+            from_block,
+            to_block,
+            created_before: self.created_before,
+            created_after: self.created_after,
+            sort,
+            order,
+            after,
+            limit: self.limit.unwrap_or(DEFAULT_PAGE_LIMIT).min(MAX_PAGE_LIMIT),
+        })
+    }
+}
+
+/// One page of `/tx` in the requested order. `next_cursor` is null on the last page.
+#[derive(Serialize, ToSchema)]
+pub struct TransferPage {
+    items: Vec<Transfer>,
+    #[schema(example = "dGltZS5kZXNjOjE4NTczMjE0OjM")]
+    next_cursor: Option<String>,
+    /// Last block the indexer has fully processed.
+    indexed_through_block: u64,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct BulkLookupRequest {
+    /// At most 500 transaction hashes.
+    #[schema(example = json!(["0x5c504ed432cb51138bcf09aa5e8a410dd4a1e204ef84bfed1be16dfba1b22060"]))]
+    tx_hashes: Vec<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BulkLookupResponse {
+    transfers: Vec<Transfer>,
+    /// Requested hashes without USDC transfers.
+    not_found: Vec<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tx/{id}",
+    tag = "transfers",
+    params(("id" = i64, Path, description = "Row id of the transfer")),
+    responses(
+        (status = 200, description = "The transfer", body = Transfer),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_transfer_by_id(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiPath(id): ApiPath<i64>,
+) -> AppResult<Json<Transfer>> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let tx = repo
+        .get_transfer_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("transfer {} does not exist", id)))?;
+    Ok(Json(Transfer::new(&tx, version)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tx/hash/{tx_hash}",
+    tag = "transfers",
+    params(("tx_hash" = String, Path, description = "0x-prefixed transaction hash")),
+    responses(
+        (status = 200, description = "Every USDC transfer in the transaction, by log index", body = Vec<Transfer>),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_transfers_by_tx_hash(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiPath(tx_hash): ApiPath<String>,
+) -> AppResult<Json<Vec<Transfer>>> {
+    let tx_hash = normalize_tx_hash("tx_hash", &tx_hash)?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let txs = repo.get_transfers_by_tx_hash(&tx_hash).await?;
+    if txs.is_empty() {
+        return Err(AppError::NotFound(format!("no USDC transfers in transaction {}", tx_hash)));
+    }
+    Ok(Json(Transfer::list(&txs, version)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tx/hash/{tx_hash}/{log_index}",
+    tag = "transfers",
+    params(
+        ("tx_hash" = String, Path, description = "0x-prefixed transaction hash"),
+        ("log_index" = i64, Path, description = "Log index within the block"),
+    ),
+    responses(
+        (status = 200, description = "The transfer", body = Transfer),
+        (status = 400, response = BadRequest),
+        (status = 404, response = NotFound),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn get_transfer_by_tx_hash_and_log_index(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiPath((tx_hash, log_index)): ApiPath<(String, i64)>,
+) -> AppResult<Json<Transfer>> {
+    let tx_hash = normalize_tx_hash("tx_hash", &tx_hash)?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let tx = repo
+        .get_transfer_by_tx_hash_and_log_index(&tx_hash, log_index)
+        .await?
+        .ok_or_else(|| AppError::NotFound(format!("no USDC transfer at {} log {}", tx_hash, log_index)))?;
+    Ok(Json(Transfer::new(&tx, version)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/v1/tx/hash",
+    tag = "transfers",
+    request_body = BulkLookupRequest,
+    responses(
+        (status = 200, description = "Transfers of every known hash", body = BulkLookupResponse),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn lookup_transfers_by_tx_hashes(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiJson(request): ApiJson<BulkLookupRequest>,
+) -> AppResult<Json<BulkLookupResponse>> {
+    if request.tx_hashes.is_empty() {
+        return Err(AppError::BadRequest("tx_hashes must not be empty".to_string()));
+    }
+    if request.tx_hashes.len() > MAX_BULK_TX_HASHES {
+        return Err(AppError::BadRequest(format!(
+            "at most {} tx_hashes per request, got {}",
+            MAX_BULK_TX_HASHES,
+            request.tx_hashes.len()
+        )));
+    }
+    let tx_hashes = request
+        .tx_hashes
+        .iter()
+        .map(|hash| normalize_tx_hash("tx_hashes", hash))
+        .collect::<AppResult<BTreeSet<String>>>()?;
+    let tx_hashes: Vec<String> = tx_hashes.into_iter().collect();
+
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let transfers = repo.list_transfers_by_tx_hashes(&tx_hashes).await?;
+
+    let found: BTreeSet<&str> = transfers.iter().map(|tx| tx.tx_hash.as_str()).collect();
+    let not_found = tx_hashes
+        .iter()
+        .filter(|hash| !found.contains(hash.as_str()))
+        .cloned()
+        .collect();
+
+    let transfers = Transfer::list(&transfers, version);
+    Ok(Json(BulkLookupResponse { transfers, not_found }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/v1/tx",
+    tag = "transfers",
+    params(TransferFilter),
+    responses(
+        (status = 200, description = "One page of matching transfers", body = TransferPage),
+        (status = 400, response = BadRequest),
+        (status = 500, response = Internal),
+        (status = 503, response = Unavailable),
+    )
+)]
+pub async fn list_transfers(
+    State(pool): State<Arc<PgPool>>,
+    version: ApiVersion,
+    ApiQuery(filter): ApiQuery<TransferFilter>,
+) -> AppResult<Json<TransferPage>> {
+    let query = filter.into_query()?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let indexed_through_block = repo.get_last_block().await?;
+    let (items, next_cursor) = fetch_page(&repo, query).await?;
+    let items = Transfer::list(&items, version);
+    Ok(Json(TransferPage { items, next_cursor, indexed_through_block }))
+}
+
+/// Up to `query.limit` rows and the cursor of the next page, if there is one.
+pub(crate) async fn fetch_page(
+    repo: &PostgresRepo,
+    mut query: TransferQuery,
+) -> AppResult<(Vec<UsdcTransfer>, Option<String>)> {
+    let limit = query.limit as usize;
+    // One extra row tells whether another page follows.
+    query.limit += 1;
+    let mut items = repo.list_transfers(&query).await?;
+    let next_cursor = if items.len() > limit {
+        items.truncate(limit);
+        items.last().map(|last| cursor::encode(last, query.sort, query.order))
+    } else {
+        None
+    };
+    Ok((items, next_cursor))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bad_request(filter: TransferFilter) -> String {
+        match filter.into_query() {
+            Err(AppError::BadRequest(message)) => message,
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn rejects_the_old_page_parameter() {
+        let filter = TransferFilter { page: Some("2".to_string()), ..Default::default() };
+        assert_eq!(bad_request(filter), "page is no longer supported; pass the next_cursor of the previous page as cursor");
+    }
+
+    #[test]
+    fn rejects_min_amount_above_max_amount() {
+        let filter = TransferFilter {
+            min_amount: Some(Decimal::new(2, 0)),
+            max_amount: Some(Decimal::new(1, 0)),
+            ..Default::default()
+        };
+        assert_eq!(bad_request(filter), "min_amount must not exceed max_amount");
+
+        let filter = TransferFilter { min_amount: Some(Decimal::ONE), max_amount: Some(Decimal::ONE), ..Default::default() };
+        assert!(filter.into_query().is_ok());
+    }
+
+    #[test]
+    fn rejects_amounts_finer_than_usdc_or_negative() {
+        let filter = TransferFilter { min_amount: Some(Decimal::new(1, 7)), ..Default::default() };
+        assert_eq!(bad_request(filter), "min_amount must be a non-negative amount with at most 6 decimals");
+        let filter = TransferFilter { max_amount: Some(Decimal::new(-1, 0)), ..Default::default() };
+        assert_eq!(bad_request(filter), "max_amount must be a non-negative amount with at most 6 decimals");
+
+        let filter = TransferFilter { min_amount: Some(Decimal::new(1_000_001, 6)), ..Default::default() };
+        assert_eq!(filter.into_query().unwrap().min_amount, Some(Decimal::new(1_000_001, 6)));
+    }
+
+    #[test]
+    fn checks_the_block_range() {
+        let filter = TransferFilter { from_block: Some(11), to_block: Some(10), ..Default::default() };
+        assert_eq!(bad_request(filter), "from_block must not exceed to_block");
+
+        let filter = TransferFilter { from_block: Some(u64::MAX), ..Default::default() };
+        assert_eq!(bad_request(filter), format!("from_block must be at most {}", i64::MAX));
+        let filter = TransferFilter { to_block: Some(i64::MAX as u64 + 1), ..Default::default() };
+        assert_eq!(bad_request(filter), format!("to_block must be at most {}", i64::MAX));
+
+        let filter = TransferFilter { from_block: Some(10), to_block: Some(i64::MAX as u64), ..Default::default() };
+        let query = filter.into_query().unwrap();
+        assert_eq!((query.from_block, query.to_block), (Some(10), Some(i64::MAX)));
+    }
+
+    #[test]
+    fn normalizes_and_deduplicates_addresses() {
+        let upper = "0x28C6c06298d514Db089934071355E5743bf21d60";
+        let lower = upper.to_lowercase();
+        let filter = TransferFilter {
+            from: Some(format!("{}, {}", upper, lower)),
+            address: Some(lower.clone()),
+            ..Default::default()
+        };
+        let query = filter.into_query().unwrap();
+        assert_eq!(query.from, vec![lower.clone()]);
+        assert_eq!(query.address, vec![lower]);
+        assert!(query.to.is_empty());
+
+        let filter = TransferFilter { to: Some("0x1234".to_string()), ..Default::default() };
+        assert_eq!(bad_request(filter), "to is not a valid address: '0x1234'");
+
+        let too_many = (0..=MAX_FILTER_ADDRESSES).map(|i| format!("0x{:040x}", i)).collect::<Vec<_>>().join(",");
+        let filter = TransferFilter { address: Some(too_many), ..Default::default() };
+        assert_eq!(bad_request(filter), "address accepts at most 50 addresses");
+    }
+
+    #[test]
+    fn excludes_the_zero_address_only_when_asked() {
+        assert!(!TransferFilter::default().into_query().unwrap().exclude_zero_address);
+        let filter = TransferFilter { exclude_zero_address: Some(true), ..Default::default() };
+        assert!(filter.into_query().unwrap().exclude_zero_address);
+    }
+
+    #[test]
+    fn applies_paging_defaults_and_bounds() {
+        let query = TransferFilter::default().into_query().unwrap();
+        assert_eq!((query.sort, query.order, query.limit), (TransferSort::Time, SortOrder::Desc, DEFAULT_PAGE_LIMIT));
+        let filter = TransferFilter { limit: Some(1_000), ..Default::default() };
+        assert_eq!(filter.into_query().unwrap().limit, MAX_PAGE_LIMIT);
+        let filter = TransferFilter { limit: Some(0), ..Default::default() };
+        assert_eq!(bad_request(filter), "limit must be at least 1");
+
+        let now = Utc::now();
+        let filter = TransferFilter { created_before: Some(now), created_after: Some(now), ..Default::default() };
+        assert_eq!(bad_request(filter), "created_after must be earlier than created_before");
+    }
+}
@@ -0,0 +1,43 @@
+use common::{AppError, AppResult};
+
+/// Validates a 0x-prefixed hex address and lowercases it to match the stored form.
+pub fn normalize_address(field: &str, value: &str) -> AppResult<String> {
+    normalize_hex(field, value, 40, "address")
+}
+
+/// Parses a comma-separated list of addresses, normalizing each and dropping duplicates.
+pub fn normalize_address_list(field: &str, value: &str, max: usize) -> AppResult<Vec<String>> {
+    let mut addresses: Vec<String> = Vec::new();
+    for item in value.split(',').map(str::trim) {
+        let address = normalize_address(field, item)?;
+        if !addresses.contains(&address) {
+            addresses.push(address);
+        }
+    }
+    if addresses.len() > max {
+        return Err(AppError::BadRequest(format!("{} accepts at most {} addresses", field, max)));
+    }
+    Ok(addresses)
+}
+
+/// Checks that a block number fits the `BIGINT` it is compared with.
+pub fn block_number(field: &str, value: Option<u64>) -> AppResult<Option<i64>> {
+    value
+        .map(|block| i64::try_from(block).map_err(|_| AppError::BadRequest(format!("{} must be at most {}", field, i64::MAX))))
+        .transpose()
+}
+
+/// Validates a 0x-prefixed 32-byte transaction hash and lowercases it.
+pub fn normalize_tx_hash(field: &str, value: &str) -> AppResult<String> {
+    normalize_hex(field, value, 64, "transaction hash")
+}
+
+fn normalize_hex(field: &str, value: &str, digits: usize, kind: &str) -> AppResult<String> {
+    let valid = value.len() == digits + 2
+        && value.starts_with("0x")
+        && value[2..].chars().all(|c| c.is_ascii_hexdigit());
+    if !valid {
+        return Err(AppError::BadRequest(format!("{} is not a valid {}: '{}'", field, kind, value)));
+    }
+    Ok(value.to_ascii_lowercase())
+}
@@ -0,0 +1,95 @@
+use std::convert::Infallible;
+
+use axum::{
+    extract::{FromRequestParts, Request},
+    http::{header, request::Parts, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use rust_decimal::Decimal;
+use sha3::{Digest, Keccak256};
+
+/// USDC has six decimals; v1 amounts always carry all of them.
+const AMOUNT_SCALE: u32 = 6;
+/// RFC 9745 `Deprecation` of the unprefixed routes: 2026-10-18T00:00:00Z.
+const LEGACY_DEPRECATION: &str = "@1792281600";
+/// RFC 8594 `Sunset`: the unprefixed routes may be removed after this date.
+const LEGACY_SUNSET: &str = "Fri, 30 Apr 2027 00:00:00 GMT";
+
+/// Response contract of a route. `/v1/...` serves `V1`; the unprefixed aliases serve `Legacy`,
+/// which keeps the JSON those routes returned before versioning.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ApiVersion {
+    Legacy,
+    V1,
+}
+
+impl ApiVersion {
+    /// EIP-55 checksummed in v1; the stored lowercase form on legacy routes.
+    pub fn address(self, address: &str) -> String {
+        match self {
+            ApiVersion::Legacy => address.to_string(),
+            ApiVersion::V1 => checksum(address),
+        }
+    }
+
+    /// A decimal string, with exactly six decimals in v1 and as stored on legacy routes.
+    pub fn amount(self, amount: Decimal) -> String {
+        match self {
+            ApiVersion::Legacy => amount.to_string(),
+            ApiVersion::V1 => {
+                let mut amount = amount;
+                amount.rescale(AMOUNT_SCALE);
+                amount.to_string()
+            }
+        }
+    }
+}
+
+impl<S> FromRequestParts<S> for ApiVersion
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts.extensions.get::<ApiVersion>().copied().unwrap_or(ApiVersion::V1))
+    }
+}
+
+/// EIP-55 mixed-case checksum of a 0x-prefixed 20-byte hex address; anything else is
+/// returned unchanged.
+pub fn checksum(address: &str) -> String {
+    let Some(hex) = address
+        .strip_prefix("0x")
+        .filter(|hex| hex.len() == 40 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+    else {
+        return address.to_string();
+    };
+    let lower = hex.to_ascii_lowercase();
+    let hash = Keccak256::digest(lower.as_bytes());
+
+    let mut checksummed = String::with_capacity(42);
+    checksummed.push_str("0x");
+    for (i, c) in lower.chars().enumerate() {
+        let nibble = if i % 2 == 0 { hash[i / 2] >> 4 } else { hash[i / 2] & 0x0f };
+        checksummed.push(if nibble >= 8 { c.to_ascii_uppercase() } else { c });
+    }
+    checksummed
+}
+
+/// Compatibility layer for the unprefixed routes: serves the legacy contract and marks every
+/// response deprecated, linking to the `/v1` successor.
+pub async fn legacy(mut request: Request, next: Next) -> Response {
+    let successor = HeaderValue::from_str(&format!("</v1{}>; rel=\"successor-version\"", request.uri().path()));
+    request.extensions_mut().insert(ApiVersion::Legacy);
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static(LEGACY_DEPRECATION));
+    headers.insert("sunset", HeaderValue::from_static(LEGACY_SUNSET));
+    if let Ok(successor) = successor {
+        headers.append(header::LINK, successor);
+    }
+    response
+}
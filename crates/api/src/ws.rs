@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use common::AppError;
+use db::feed::{FeedEvent, TransferFeed};
+use db::{TransferQuery, UsdcTransfer, ZERO_ADDRESS};
+use futures::{SinkExt, StreamExt};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio::time::{interval, Instant};
+use tracing::debug;
+
+use crate::dto::Transfer;
+use crate::validate::normalize_address;
+use crate::version::ApiVersion;
+use crate::AppState;
+
+/// Messages queued for a connection; events beyond this are dropped and reported as `lagged`.
+const OUTBOUND_CAPACITY: usize = 256;
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(20);
+/// Connections that send nothing (not even a pong) for this long are closed.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_SUBSCRIPTIONS: usize = 32;
+const MAX_ADDRESSES: usize = 100;
+const MAX_MESSAGE_BYTES: usize = 64 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventKind {
+    Transfer,
+    Mint,
+    Burn,
+}
+
+impl EventKind {
+    fn of(transfer: &UsdcTransfer) -> Self {
+        if transfer.from_address == ZERO_ADDRESS {
+            EventKind::Mint
+        } else if transfer.to_address == ZERO_ADDRESS {
+            EventKind::Burn
+        } else {
+            EventKind::Transfer
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+enum ClientMessage {
+    Subscribe {
+        id: String,
+        #[serde(default)]
+        filter: SubscriptionFilter,
+    },
+    Unsubscribe {
+        id: String,
+    },
+    Ping,
+}
+
+/// Empty lists match everything; `addresses` matches either side.
+#[derive(Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SubscriptionFilter {
+    #[serde(default)]
+    addresses: Vec<String>,
+    #[serde(default)]
+    from: Vec<String>,
+    #[serde(default)]
+    to: Vec<String>,
+    min_amount: Option<Decimal>,
+    max_amount: Option<Decimal>,
+    #[serde(default)]
+    types: Vec<EventKind>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ServerMessage<'a> {
+    Subscribed { id: &'a str },
+    Unsubscribed { id: &'a str },
+    Pong,
+    Event {
+        subscriptions: Vec<&'a str>,
+        kind: EventKind,
+        transfer: Transfer,
+    },
+    /// Events were dropped because the client did not read fast enough.
+    Lagged { dropped: u64 },
+    /// The server may have missed events; clients should reconcile through `/tx`.
+    Resync,
+    Error { message: String },
+}
+
+impl ServerMessage<'_> {
+    fn into_message(self) -> Message {
+        Message::Text(serde_json::to_string(&self).unwrap_or_default().into())
+    }
+}
+
+struct Subscription {
+    query: TransferQuery,
+    kinds: Vec<EventKind>,
+}
+
+impl Subscription {
+    fn matches(&self, transfer: &UsdcTransfer, kind: EventKind) -> bool {
+        (self.kinds.is_empty() || self.kinds.contains(&kind)) && self.query.matches(transfer)
+    }
+}
+
+/// WebSocket at `/ws`: clients send `subscribe`/`unsubscribe`/`ping` commands as JSON text
+/// frames and receive one `event` message per matching transfer.
+#[utoipa::path(
+    get,
+    path = "/v1/ws",
+    tag = "transfers",
+    security(("bearer" = []), ("api_key" = []), ("api_key_query" = [])),
+    responses((status = 101, description = "Upgraded to a WebSocket. Send \
+        `{\"op\":\"subscribe\",\"id\":\"big\",\"filter\":{\"min_amount\":\"1000000\",\"types\":[\"transfer\"]}}`, \
+        `{\"op\":\"unsubscribe\",\"id\":\"big\"}` or `{\"op\":\"ping\"}`; filters also take `addresses`, `from`, \
+        `to` and `max_amount`. The server replies `subscribed`, `unsubscribed`, `pong` or `error`, and pushes \
+        `event`, `lagged` (events dropped for a slow reader) and `resync` messages."))
+)]
+pub async fn subscribe(ws: WebSocketUpgrade, State(state): State<AppState>, version: ApiVersion) -> Response {
+    ws.max_message_size(MAX_MESSAGE_BYTES)
+        .on_upgrade(move |socket| handle_socket(socket, state.feed, version))
+}
+
+async fn handle_socket(socket: WebSocket, feed: TransferFeed, version: ApiVersion) {
+    let (mut sink, mut incoming) = socket.split();
+    let (outbound, mut queued) = mpsc::channel::<Message>(OUTBOUND_CAPACITY);
+    let writer = tokio::spawn(async move {
+        while let Some(message) = queued.recv().await {
+            if sink.send(message).await.is_err() {
+                break;
+            }
+        }
+        let _ = sink.close().await;
+    });
+
+    let mut events = feed.subscribe();
+    let mut subscriptions: HashMap<String, Subscription> = HashMap::new();
+    let mut heartbeat = interval(HEARTBEAT_INTERVAL);
+    let mut last_heard = Instant::now();
+    let mut dropped: u64 = 0;
+
+    loop {
+        tokio::select! {
+            message = incoming.next() => {
+                let Some(Ok(message)) = message else { break };
+                last_heard = Instant::now();
+                let reply = match message {
+                    Message::Text(text) => handle_command(&text, &mut subscriptions),
+                    Message::Binary(_) => Some(error("binary frames are not supported")),
+                    Message::Close(_) => break,
+                    Message::Ping(_) | Message::Pong(_) => None,
+                };
+                // Replies wait for queue space; only events are ever dropped.
+                if let Some(reply) = reply
+                    && outbound.send(reply).await.is_err() {
+                    break;
+                }
+            }
+            event = events.recv() => {
+                let message = match event {
+                    Ok(FeedEvent::Transfer(transfer)) => {
+                        let transfer = &transfer.transfer;
+                        let kind = EventKind::of(transfer);
+                        let matched: Vec<&str> = subscriptions
+                            .iter()
+                            .filter(|(_, sub)| sub.matches(transfer, kind))
+                            .map(|(id, _)| id.as_str())
+                            .collect();
+                        if matched.is_empty() {
+                            continue;
+                        }
+                        let transfer = Transfer::new(transfer, version);
+                        ServerMessage::Event { subscriptions: matched, kind, transfer }.into_message()
+                    }
+                    Ok(FeedEvent::Resync) => ServerMessage::Resync.into_message(),
+                    Err(RecvError::Lagged(skipped)) => {
+                        dropped += skipped;
+                        continue;
+                    }
+                    Err(RecvError::Closed) => break,
+                };
+                match outbound.try_send(message) {
+                    Ok(()) => {}
+                    Err(mpsc::error::TrySendError::Full(_)) => dropped += 1,
+                    Err(mpsc::error::TrySendError::Closed(_)) => break,
+                }
+            }
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > CLIENT_TIMEOUT {
+                    debug!("closing unresponsive websocket client");
+                    break;
+                }
+                let _ = outbound.try_send(Message::Ping(Default::default()));
+            }
+        }
+
+        if dropped > 0
+            && outbound.try_send(ServerMessage::Lagged { dropped }.into_message()).is_ok() {
+            dropped = 0;
+        }
+    }
+
+    drop(outbound);
+    let _ = writer.await;
+}
+
+fn handle_command(text: &str, subscriptions: &mut HashMap<String, Subscription>) -> Option<Message> {
+    let command = match serde_json::from_str::<ClientMessage>(text) {
+        Ok(command) => command,
+        Err(e) => return Some(error(format!("invalid command: {}", e))),
+    };
+    let reply = match command {
+        ClientMessage::Subscribe { id, filter } => {
+            if !subscriptions.contains_key(&id) && subscriptions.len() >= MAX_SUBSCRIPTIONS {
+                return Some(error(format!("at most {} subscriptions per connection", MAX_SUBSCRIPTIONS)));
+            }
+            match build_subscription(filter) {
+                Ok(subscription) => {
+                    let reply = ServerMessage::Subscribed { id: &id }.into_message();
+                    subscriptions.insert(id, subscription);
+                    reply
+                }
+                Err(message) => error(message),
+            }
+        }
+        ClientMessage::Unsubscribe { id } => match subscriptions.remove(&id) {
+            Some(_) => ServerMessage::Unsubscribed { id: &id }.into_message(),
+            None => error(format!("no subscription '{}'", id)),
+        },
+        ClientMessage::Ping => ServerMessage::Pong.into_message(),
+    };
+    Some(reply)
+}
+
+fn build_subscription(filter: SubscriptionFilter) -> Result<Subscription, String> {
+    let addresses = |field: &str, values: Vec<String>| -> Result<Vec<String>, String> {
+        if values.len() > MAX_ADDRESSES {
+            return Err(format!("{} accepts at most {} addresses", field, MAX_ADDRESSES));
+        }
+        values
+            .iter()
+            .map(|v| {
+                normalize_address(field, v).map_err(|e| match e {
+                    AppError::BadRequest(message) => message,
+                    other => other.to_string(),
+                })
+            })
+            .collect()
+    };
+    if let (Some(min), Some(max)) = (filter.min_amount, filter.max_amount)
+        && min > max {
+        return Err("min_amount must not exceed max_amount".to_string());
+    }
+    Ok(Subscription {
+        query: TransferQuery {
+            address: addresses("addresses", filter.addresses)?,
+            from: addresses("from", filter.from)?,
+            to: addresses("to", filter.to)?,
+            min_amount: filter.min_amount,
+            max_amount: filter.max_amount,
+            ..TransferQuery::default()
+        },
+        kinds: filter.types,
+    })
+}
+
+fn error(message: impl Into<String>) -> Message {
+    ServerMessage::Error { message: message.into() }.into_message()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const HOLDER: &str = "0x28c6c06298d514db089934071355e5743bf21d60";
+    const OTHER: &str = "0x7f367cc41522ce07553e823bf3be79a889debe1b";
+
+    fn send(text: &str, subscriptions: &mut HashMap<String, Subscription>) -> Value {
+        match handle_command(text, subscriptions) {
+            Some(Message::Text(reply)) => serde_json::from_str(reply.as_str()).unwrap(),
+            other => panic!("expected a text reply, got {:?}", other),
+        }
+    }
+
+    fn subscribe(id: &str, filter: Value) -> String {
+        json!({ "op": "subscribe", "id": id, "filter": filter }).to_string()
+    }
+
+    fn filter_error(filter: Value) -> String {
+        let mut subscriptions = HashMap::new();
+        let reply = send(&subscribe("a", filter), &mut subscriptions);
+        assert_eq!(reply["type"], "error");
+        assert!(subscriptions.is_empty());
+        reply["message"].as_str().unwrap().to_string()
+    }
+
+    fn transfer(from: &str, to: &str) -> UsdcTransfer {
+        UsdcTransfer {
+            id: 1,
+            tx_hash: format!("0x{}", "ab".repeat(32)),
+            log_index: 0,
+            block_number: 100,
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: Decimal::new(1_500_000, 6),
+            block_time: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn subscribe_unsubscribe_and_ping_replies() {
+        let mut subscriptions = HashMap::new();
+
+        assert_eq!(send(&subscribe("big", json!({ "min_amount": "1000" })), &mut subscriptions), json!({ "type": "subscribed", "id": "big" }));
+        assert!(subscriptions.contains_key("big"));
+
+        assert_eq!(send(r#"{"op":"ping"}"#, &mut subscriptions), json!({ "type": "pong" }));
+
+        assert_eq!(send(r#"{"op":"unsubscribe","id":"big"}"#, &mut subscriptions), json!({ "type": "unsubscribed", "id": "big" }));
+        assert!(subscriptions.is_empty());
+
+        let reply = send(r#"{"op":"unsubscribe","id":"big"}"#, &mut subscriptions);
+        assert_eq!(reply, json!({ "type": "error", "message": "no subscription 'big'" }));
+    }
+
+    #[test]
+    fn rejects_unparseable_commands() {
+        let mut subscriptions = HashMap::new();
+        for text in ["not json", r#"{"op":"listen"}"#, r#"{"op":"subscribe","id":"a","filter":{"size":1}}"#] {
+            let reply = send(text, &mut subscriptions);
+            assert_eq!(reply["type"], "error");
+            assert!(reply["message"].as_str().unwrap().starts_with("invalid command: "));
+        }
+    }
+
+    #[test]
+    fn limits_subscriptions_per_connection() {
+        let mut subscriptions = HashMap::new();
+        for i in 0..MAX_SUBSCRIPTIONS {
+            assert_eq!(send(&subscribe(&i.to_string(), json!({})), &mut subscriptions)["type"], "subscribed");
+        }
+
+        let reply = send(&subscribe("one-more", json!({})), &mut subscriptions);
+        assert_eq!(reply["message"], format!("at most {} subscriptions per connection", MAX_SUBSCRIPTIONS));
+        assert_eq!(subscriptions.len(), MAX_SUBSCRIPTIONS);
+
+        // Replacing an existing subscription does not count against the limit.
+        assert_eq!(send(&subscribe("0", json!({ "types": ["mint"] })), &mut subscriptions)["type"], "subscribed");
+        assert_eq!(subscriptions["0"].kinds, [EventKind::Mint]);
+    }
+
+    #[test]
+    fn rejects_min_amount_above_max_amount() {
+        assert_eq!(filter_error(json!({ "min_amount": "10", "max_amount": "5" })), "min_amount must not exceed max_amount");
+        assert!(build_subscription(SubscriptionFilter {
+            min_amount: Some(Decimal::TEN),
+            max_amount: Some(Decimal::TEN),
+            ..Default::default()
+        }).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_addresses() {
+        assert_eq!(filter_error(json!({ "from": ["0x1234"] })), "from is not a valid address: '0x1234'");
+        assert_eq!(
+            filter_error(json!({ "addresses": [HOLDER, format!("{}zz", &HOLDER[..40])] })),
+            format!("addresses is not a valid address: '{}zz'", &HOLDER[..40])
+        );
+
+        let too_many = vec![HOLDER; MAX_ADDRESSES + 1];
+        assert_eq!(filter_error(json!({ "to": too_many })), format!("to accepts at most {} addresses", MAX_ADDRESSES));
+    }
+
+    #[test]
+    fn normalizes_filter_addresses() {
+        let subscription = build_subscription(SubscriptionFilter {
+            addresses: vec![HOLDER.to_uppercase().replace("0X", "0x")],
+            ..Default::default()
+        }).unwrap();
+
+        let kind = EventKind::Transfer;
+        assert!(subscription.matches(&transfer(HOLDER, OTHER), kind));
+        assert!(subscription.matches(&transfer(OTHER, HOLDER), kind));
+        assert!(!subscription.matches(&transfer(OTHER, OTHER), kind));
+    }
+
+    #[test]
+    fn event_kind_of_mints_and_burns() {
+        assert_eq!(EventKind::of(&transfer(ZERO_ADDRESS, HOLDER)), EventKind::Mint);
+        assert_eq!(EventKind::of(&transfer(HOLDER, ZERO_ADDRESS)), EventKind::Burn);
+        assert_eq!(EventKind::of(&transfer(HOLDER, OTHER)), EventKind::Transfer);
+    }
+
+    #[test]
+    fn types_filter_matches_event_kind() {
+        let subscription = build_subscription(SubscriptionFilter { types: vec![EventKind::Burn], ..Default::default() }).unwrap();
+
+        let burn = transfer(HOLDER, ZERO_ADDRESS);
+        assert!(subscription.matches(&burn, EventKind::of(&burn)));
+        let mint = transfer(ZERO_ADDRESS, HOLDER);
+        assert!(!subscription.matches(&mint, EventKind::of(&mint)));
+    }
+}
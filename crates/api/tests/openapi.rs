@@ -0,0 +1,59 @@
+//! Keeps the committed `openapi.json` in step with the handlers and types it is generated from.
+//! After an intentional API change, regenerate it with
+//! `UPDATE_OPENAPI=1 cargo test -p api --test openapi`.
+
+use std::collections::BTreeSet;
+
+use serde_json::Value;
+
+const SPEC_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/openapi.json");
+
+fn generated() -> String {
+    let mut spec = api::openapi().to_pretty_json().expect("spec serializes");
+    spec.push('\n');
+    spec
+}
+
+#[test]
+fn committed_spec_matches_code() {
+    let generated = generated();
+    if std::env::var_os("UPDATE_OPENAPI").is_some() {
+        std::fs::write(SPEC_PATH, &generated).expect("write openapi.json");
+        return;
+    }
+    let committed = std::fs::read_to_string(SPEC_PATH).expect("read openapi.json");
+    assert!(
+        committed == generated,
+        "crates/api/openapi.json is out of date; regenerate it with \
+         `UPDATE_OPENAPI=1 cargo test -p api --test openapi` and review the diff"
+    );
+}
+
+#[test]
+fn every_reference_resolves() {
+    let spec: Value = serde_json::from_str(&generated()).expect("spec is JSON");
+    let mut refs = BTreeSet::new();
+    collect_refs(&spec, &mut refs);
+    let missing: Vec<&String> = refs
+        .iter()
+        .filter(|r| spec.pointer(r.trim_start_matches('#')).is_none())
+        .collect();
+    assert!(missing.is_empty(), "unresolved references: {:?}", missing);
+}
+
+fn collect_refs(value: &Value, refs: &mut BTreeSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("$ref", Value::String(r)) => {
+                        refs.insert(r.clone());
+                    }
+                    _ => collect_refs(value, refs),
+                }
+            }
+        }
+        Value::Array(values) => values.iter().for_each(|v| collect_refs(v, refs)),
+        _ => {}
+    }
+}
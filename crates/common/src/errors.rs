@@ -1,4 +1,11 @@
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
 use thiserror::Error;
+use utoipa::ToSchema;
 
 #[derive(Error, Debug)]
 pub enum AppError {
@@ -10,6 +17,111 @@ pub enum AppError {
 
     #[error("Unknown error: {0}")]
     Unknown(String),
+
+    #[error("Not found: {0}")]
+    NotFound(String),
+
+    #[error("Bad request: {0}")]
+    BadRequest(String),
+
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
+
+    #[error("Too many requests: {0}")]
+    TooManyRequests(String),
+
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
 }
 
 pub type AppResult<T> = Result<T, AppError>;
+
+/// RFC 9457 problem details body.
+#[derive(Serialize, Debug, ToSchema)]
+pub struct Problem {
+    /// Machine-readable kind, e.g. `bad-request`, `not-found` or `internal`.
+    #[serde(rename = "type")]
+    #[schema(example = "bad-request")]
+    pub kind: &'static str,
+    #[schema(example = "Bad Request")]
+    pub title: &'static str,
+    #[schema(example = 400)]
+    pub status: u16,
+    #[schema(example = "limit must be at least 1")]
+    pub detail: String,
+}
+
+impl AppError {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::TooManyRequests(_) => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::Network(_) => StatusCode::BAD_GATEWAY,
+            AppError::Database(_) | AppError::Unknown(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    pub fn problem(&self) -> Problem {
+        let status = self.status();
+        let (kind, detail) = match self {
+            AppError::NotFound(msg) => ("not-found", msg.clone()),
+            AppError::BadRequest(msg) => ("bad-request", msg.clone()),
+            AppError::TooManyRequests(msg) => ("too-many-requests", msg.clone()),
+            AppError::Unauthorized(msg) => ("unauthorized", msg.clone()),
+            AppError::Forbidden(msg) => ("forbidden", msg.clone()),
+            AppError::Unavailable(_) => ("unavailable", "the database is currently unavailable".to_string()),
+            AppError::Network(_) => ("upstream", "an upstream service failed".to_string()),
+            AppError::Database(_) | AppError::Unknown(_) => ("internal", "an internal error occurred".to_string()),
+        };
+        Problem {
+            kind,
+            title: status.canonical_reason().unwrap_or("Error"),
+            status: status.as_u16(),
+            detail,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let problem = self.problem();
+        if self.status().is_server_error() {
+            tracing::error!(error = %self, "request failed");
+        }
+        (
+            self.status(),
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            Json(problem),
+        )
+            .into_response()
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        match err {
+            sqlx::Error::RowNotFound => AppError::NotFound("row not found".to_string()),
+            sqlx::Error::PoolTimedOut
+            | sqlx::Error::PoolClosed
+            | sqlx::Error::Io(_)
+            | sqlx::Error::Tls(_) => AppError::Unavailable(err.to_string()),
+            other => AppError::Database(other.to_string()),
+        }
+    }
+}
+
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<sqlx::Error>() {
+            Ok(sqlx_err) => sqlx_err.into(),
+            Err(err) => AppError::Unknown(format!("{:#}", err)),
+        }
+    }
+}
@@ -1,2 +1,2 @@
 pub mod errors;
-pub use errors::{AppError, AppResult};
+pub use errors::{AppError, AppResult, Problem};
@@ -0,0 +1,18 @@
+CREATE TABLE IF NOT EXISTS verification_runs (
+    id              BIGSERIAL PRIMARY KEY,
+    from_block      BIGINT NOT NULL,
+    to_block        BIGINT NOT NULL,
+    missing         BIGINT NOT NULL,
+    extra           BIGINT NOT NULL,
+    mismatched      BIGINT NOT NULL,
+    repaired        BOOLEAN NOT NULL,
+    details         JSONB NOT NULL,
+    started_at      TIMESTAMPTZ NOT NULL,
+    finished_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_verification_runs_range
+    ON verification_runs (from_block, to_block);
+
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_block_logindex
+    ON usdc_transfers (block_number, log_index);
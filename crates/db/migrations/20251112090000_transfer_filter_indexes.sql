@@ -0,0 +1,11 @@
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_from_block_logindex
+    ON usdc_transfers (from_address, block_number, log_index);
+
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_to_block_logindex
+    ON usdc_transfers (to_address, block_number, log_index);
+
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_amount_block_logindex
+    ON usdc_transfers (amount, block_number, log_index);
+
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_block_time
+    ON usdc_transfers (block_time);
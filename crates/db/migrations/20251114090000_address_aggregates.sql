@@ -0,0 +1,158 @@
+CREATE TABLE IF NOT EXISTS address_stats (
+    address         CHAR(42) PRIMARY KEY,
+    balance         NUMERIC(38,6) NOT NULL DEFAULT 0,
+    total_in        NUMERIC(38,6) NOT NULL DEFAULT 0,
+    total_out       NUMERIC(38,6) NOT NULL DEFAULT 0,
+    transfers_in    BIGINT NOT NULL DEFAULT 0,
+    transfers_out   BIGINT NOT NULL DEFAULT 0,
+    first_block     BIGINT NOT NULL,
+    last_block      BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS address_flows_hourly (
+    address         CHAR(42) NOT NULL,
+    hour            TIMESTAMPTZ NOT NULL,
+    inflow          NUMERIC(38,6) NOT NULL DEFAULT 0,
+    outflow         NUMERIC(38,6) NOT NULL DEFAULT 0,
+    transfers_in    BIGINT NOT NULL DEFAULT 0,
+    transfers_out   BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (address, hour)
+);
+
+CREATE TABLE IF NOT EXISTS transfer_edges_hourly (
+    from_address    CHAR(42) NOT NULL,
+    to_address      CHAR(42) NOT NULL,
+    hour            TIMESTAMPTZ NOT NULL,
+    volume          NUMERIC(38,6) NOT NULL DEFAULT 0,
+    transfers       BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (from_address, to_address, hour)
+);
+
+CREATE INDEX IF NOT EXISTS idx_transfer_edges_hourly_to
+    ON transfer_edges_hourly (to_address, hour);
+
+-- Adds (sign = 1) or removes (sign = -1) one side of a transfer for a single address.
+CREATE OR REPLACE FUNCTION apply_address_side(
+    addr CHAR(42), block BIGINT, bucket TIMESTAMPTZ,
+    amount_in NUMERIC, amount_out NUMERIC, count_in BIGINT, count_out BIGINT, sign INT
+) RETURNS void AS $$
+BEGIN
+    INSERT INTO address_stats AS s
+        (address, balance, total_in, total_out, transfers_in, transfers_out, first_block, last_block)
+    VALUES (addr, sign * (amount_in - amount_out), sign * amount_in, sign * amount_out,
+            sign * count_in, sign * count_out, block, block)
+    ON CONFLICT (address) DO UPDATE
+    SET balance = s.balance + EXCLUDED.balance,
+        total_in = s.total_in + EXCLUDED.total_in,
+        total_out = s.total_out + EXCLUDED.total_out,
+        transfers_in = s.transfers_in + EXCLUDED.transfers_in,
+        transfers_out = s.transfers_out + EXCLUDED.transfers_out,
+        first_block = CASE WHEN sign > 0 THEN LEAST(s.first_block, EXCLUDED.first_block) ELSE s.first_block END,
+        last_block = CASE WHEN sign > 0 THEN GREATEST(s.last_block, EXCLUDED.last_block) ELSE s.last_block END;
+
+    INSERT INTO address_flows_hourly AS f (address, hour, inflow, outflow, transfers_in, transfers_out)
+    VALUES (addr, bucket, sign * amount_in, sign * amount_out, sign * count_in, sign * count_out)
+    ON CONFLICT (address, hour) DO UPDATE
+    SET inflow = f.inflow + EXCLUDED.inflow,
+        outflow = f.outflow + EXCLUDED.outflow,
+        transfers_in = f.transfers_in + EXCLUDED.transfers_in,
+        transfers_out = f.transfers_out + EXCLUDED.transfers_out;
+
+    IF sign < 0 THEN
+        DELETE FROM address_flows_hourly
+        WHERE address = addr AND hour = bucket AND transfers_in = 0 AND transfers_out = 0;
+        DELETE FROM address_stats
+        WHERE address = addr AND transfers_in = 0 AND transfers_out = 0;
+        -- The removed transfer may have been the first or last one seen for the address.
+        UPDATE address_stats s
+        SET first_block = LEAST(
+                (SELECT min(block_number) FROM usdc_transfers WHERE from_address = addr),
+                (SELECT min(block_number) FROM usdc_transfers WHERE to_address = addr)),
+            last_block = GREATEST(
+                (SELECT max(block_number) FROM usdc_transfers WHERE from_address = addr),
+                (SELECT max(block_number) FROM usdc_transfers WHERE to_address = addr))
+        WHERE s.address = addr AND block IN (s.first_block, s.last_block);
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION apply_transfer_to_address_aggregates(t usdc_transfers, sign INT)
+RETURNS void AS $$
+DECLARE
+    bucket TIMESTAMPTZ := date_trunc('hour', t.block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC';
+BEGIN
+    -- Rows are always locked in address order so concurrent inserts cannot deadlock.
+    IF t.from_address = t.to_address THEN
+        PERFORM apply_address_side(t.from_address, t.block_number, bucket, t.amount, t.amount, 1, 1, sign);
+    ELSIF t.from_address < t.to_address THEN
+        PERFORM apply_address_side(t.from_address, t.block_number, bucket, 0, t.amount, 0, 1, sign);
+        PERFORM apply_address_side(t.to_address, t.block_number, bucket, t.amount, 0, 1, 0, sign);
+    ELSE
+        PERFORM apply_address_side(t.to_address, t.block_number, bucket, t.amount, 0, 1, 0, sign);
+        PERFORM apply_address_side(t.from_address, t.block_number, bucket, 0, t.amount, 0, 1, sign);
+    END IF;
+
+    INSERT INTO transfer_edges_hourly AS e (from_address, to_address, hour, volume, transfers)
+    VALUES (t.from_address, t.to_address, bucket, sign * t.amount, sign)
+    ON CONFLICT (from_address, to_address, hour) DO UPDATE
+    SET volume = e.volume + EXCLUDED.volume,
+        transfers = e.transfers + EXCLUDED.transfers;
+
+    IF sign < 0 THEN
+        DELETE FROM transfer_edges_hourly
+        WHERE from_address = t.from_address AND to_address = t.to_address AND hour = bucket AND transfers = 0;
+    END IF;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION usdc_transfers_address_aggregates() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        PERFORM apply_transfer_to_address_aggregates(OLD, -1);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_transfer_to_address_aggregates(NEW, 1);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+LOCK TABLE usdc_transfers IN SHARE MODE;
+
+INSERT INTO address_stats
+    (address, balance, total_in, total_out, transfers_in, transfers_out, first_block, last_block)
+SELECT address, sum(inflow) - sum(outflow), sum(inflow), sum(outflow),
+       sum(count_in), sum(count_out), min(block_number), max(block_number)
+FROM (
+    SELECT to_address AS address, amount AS inflow, 0 AS outflow, 1 AS count_in, 0 AS count_out, block_number
+    FROM usdc_transfers
+    UNION ALL
+    SELECT from_address, 0, amount, 0, 1, block_number
+    FROM usdc_transfers
+) sides
+GROUP BY address
+ON CONFLICT (address) DO NOTHING;
+
+INSERT INTO address_flows_hourly (address, hour, inflow, outflow, transfers_in, transfers_out)
+SELECT address, hour, sum(inflow), sum(outflow), sum(count_in), sum(count_out)
+FROM (
+    SELECT to_address AS address, date_trunc('hour', block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS hour,
+           amount AS inflow, 0 AS outflow, 1 AS count_in, 0 AS count_out
+    FROM usdc_transfers
+    UNION ALL
+    SELECT from_address, date_trunc('hour', block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC', 0, amount, 0, 1
+    FROM usdc_transfers
+) sides
+GROUP BY address, hour
+ON CONFLICT (address, hour) DO NOTHING;
+
+INSERT INTO transfer_edges_hourly (from_address, to_address, hour, volume, transfers)
+SELECT from_address, to_address, date_trunc('hour', block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC', sum(amount), count(*)
+FROM usdc_transfers
+GROUP BY from_address, to_address, date_trunc('hour', block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+ON CONFLICT (from_address, to_address, hour) DO NOTHING;
+
+DROP TRIGGER IF EXISTS trg_usdc_transfers_address_aggregates ON usdc_transfers;
+CREATE TRIGGER trg_usdc_transfers_address_aggregates
+    AFTER INSERT OR UPDATE OR DELETE ON usdc_transfers
+    FOR EACH ROW EXECUTE FUNCTION usdc_transfers_address_aggregates();
@@ -0,0 +1,147 @@
+CREATE TABLE IF NOT EXISTS volume_rollups (
+    granularity         TEXT NOT NULL CHECK (granularity IN ('hour', 'day', 'week')),
+    bucket              TIMESTAMPTZ NOT NULL,
+    transfers           BIGINT NOT NULL DEFAULT 0,
+    volume              NUMERIC(38,6) NOT NULL DEFAULT 0,
+    unique_senders      BIGINT NOT NULL DEFAULT 0,
+    unique_receivers    BIGINT NOT NULL DEFAULT 0,
+    mints               BIGINT NOT NULL DEFAULT 0,
+    minted              NUMERIC(38,6) NOT NULL DEFAULT 0,
+    burns               BIGINT NOT NULL DEFAULT 0,
+    burned              NUMERIC(38,6) NOT NULL DEFAULT 0,
+    PRIMARY KEY (granularity, bucket)
+);
+
+-- Reference counts behind unique_senders/unique_receivers; role is 's' or 'r'.
+CREATE TABLE IF NOT EXISTS volume_participants (
+    granularity     TEXT NOT NULL,
+    bucket          TIMESTAMPTZ NOT NULL,
+    role            CHAR(1) NOT NULL,
+    address         CHAR(42) NOT NULL,
+    transfers       BIGINT NOT NULL,
+    PRIMARY KEY (granularity, bucket, role, address)
+);
+
+-- Adjusts the refcount of one participant and returns the change to the unique count.
+CREATE OR REPLACE FUNCTION apply_volume_participant(
+    g TEXT, b TIMESTAMPTZ, r CHAR(1), addr CHAR(42), sign INT
+) RETURNS INT AS $$
+DECLARE
+    remaining BIGINT;
+BEGIN
+    INSERT INTO volume_participants AS p (granularity, bucket, role, address, transfers)
+    VALUES (g, b, r, addr, sign)
+    ON CONFLICT (granularity, bucket, role, address) DO UPDATE
+    SET transfers = p.transfers + EXCLUDED.transfers
+    RETURNING transfers INTO remaining;
+
+    IF sign > 0 AND remaining = 1 THEN
+        RETURN 1;
+    ELSIF sign < 0 AND remaining = 0 THEN
+        DELETE FROM volume_participants
+        WHERE granularity = g AND bucket = b AND role = r AND address = addr;
+        RETURN -1;
+    END IF;
+    RETURN 0;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION apply_transfer_to_volume_rollups(t usdc_transfers, sign INT)
+RETURNS void AS $$
+DECLARE
+    zero CONSTANT CHAR(42) := '0x0000000000000000000000000000000000000000';
+    is_mint BOOLEAN := t.from_address = zero;
+    is_burn BOOLEAN := t.to_address = zero;
+    g TEXT;
+    b TIMESTAMPTZ;
+    senders INT;
+    receivers INT;
+BEGIN
+    FOREACH g IN ARRAY ARRAY['hour', 'day', 'week'] LOOP
+        b := date_trunc(g, t.block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC';
+
+        -- Upserting the bucket row first serializes writers of the same bucket.
+        INSERT INTO volume_rollups AS v (granularity, bucket, transfers, volume, mints, minted, burns, burned)
+        VALUES (g, b, sign, sign * t.amount,
+                CASE WHEN is_mint THEN sign ELSE 0 END, CASE WHEN is_mint THEN sign * t.amount ELSE 0 END,
+                CASE WHEN is_burn THEN sign ELSE 0 END, CASE WHEN is_burn THEN sign * t.amount ELSE 0 END)
+        ON CONFLICT (granularity, bucket) DO UPDATE
+        SET transfers = v.transfers + EXCLUDED.transfers,
+            volume = v.volume + EXCLUDED.volume,
+            mints = v.mints + EXCLUDED.mints,
+            minted = v.minted + EXCLUDED.minted,
+            burns = v.burns + EXCLUDED.burns,
+            burned = v.burned + EXCLUDED.burned;
+
+        senders := 0;
+        receivers := 0;
+        IF NOT is_mint THEN
+            senders := apply_volume_participant(g, b, 's', t.from_address, sign);
+        END IF;
+        IF NOT is_burn THEN
+            receivers := apply_volume_participant(g, b, 'r', t.to_address, sign);
+        END IF;
+
+        UPDATE volume_rollups
+        SET unique_senders = unique_senders + senders,
+            unique_receivers = unique_receivers + receivers
+        WHERE granularity = g AND bucket = b AND (senders <> 0 OR receivers <> 0);
+
+        IF sign < 0 THEN
+            DELETE FROM volume_rollups WHERE granularity = g AND bucket = b AND transfers = 0;
+        END IF;
+    END LOOP;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION usdc_transfers_volume_rollups() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        PERFORM apply_transfer_to_volume_rollups(OLD, -1);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        PERFORM apply_transfer_to_volume_rollups(NEW, 1);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+LOCK TABLE usdc_transfers IN SHARE MODE;
+
+INSERT INTO volume_participants (granularity, bucket, role, address, transfers)
+SELECT g, date_trunc(g, t.block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC', side.role, side.address, count(*)
+FROM usdc_transfers t
+CROSS JOIN unnest(ARRAY['hour', 'day', 'week']) AS g
+CROSS JOIN LATERAL (VALUES ('s', t.from_address), ('r', t.to_address)) AS side (role, address)
+WHERE side.address <> '0x0000000000000000000000000000000000000000'
+GROUP BY 1, 2, 3, 4
+ON CONFLICT (granularity, bucket, role, address) DO NOTHING;
+
+INSERT INTO volume_rollups
+    (granularity, bucket, transfers, volume, unique_senders, unique_receivers, mints, minted, burns, burned)
+SELECT g, date_trunc(g, t.block_time AT TIME ZONE 'UTC') AT TIME ZONE 'UTC' AS bucket,
+       count(*), sum(t.amount), 0, 0,
+       count(*) FILTER (WHERE t.from_address = '0x0000000000000000000000000000000000000000'),
+       COALESCE(sum(t.amount) FILTER (WHERE t.from_address = '0x0000000000000000000000000000000000000000'), 0),
+       count(*) FILTER (WHERE t.to_address = '0x0000000000000000000000000000000000000000'),
+       COALESCE(sum(t.amount) FILTER (WHERE t.to_address = '0x0000000000000000000000000000000000000000'), 0)
+FROM usdc_transfers t
+CROSS JOIN unnest(ARRAY['hour', 'day', 'week']) AS g
+GROUP BY 1, 2
+ON CONFLICT (granularity, bucket) DO NOTHING;
+
+UPDATE volume_rollups v
+SET unique_senders = p.senders, unique_receivers = p.receivers
+FROM (
+    SELECT granularity, bucket,
+           count(*) FILTER (WHERE role = 's') AS senders,
+           count(*) FILTER (WHERE role = 'r') AS receivers
+    FROM volume_participants
+    GROUP BY granularity, bucket
+) p
+WHERE v.granularity = p.granularity AND v.bucket = p.bucket;
+
+DROP TRIGGER IF EXISTS trg_usdc_transfers_volume_rollups ON usdc_transfers;
+CREATE TRIGGER trg_usdc_transfers_volume_rollups
+    AFTER INSERT OR UPDATE OR DELETE ON usdc_transfers
+    FOR EACH ROW EXECUTE FUNCTION usdc_transfers_volume_rollups();
@@ -0,0 +1,19 @@
+CREATE TABLE IF NOT EXISTS address_labels (
+    address         CHAR(42) PRIMARY KEY,
+    label           TEXT NOT NULL,
+    category        TEXT NOT NULL,
+    created_at      TIMESTAMPTZ DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_address_labels_category
+    ON address_labels (category);
+
+INSERT INTO address_labels (address, label, category)
+VALUES ('0x0000000000000000000000000000000000000000', 'Zero address (mint/burn)', 'system')
+    ON CONFLICT (address) DO NOTHING;
+
+CREATE INDEX IF NOT EXISTS idx_address_stats_balance
+    ON address_stats (balance DESC);
+
+CREATE INDEX IF NOT EXISTS idx_address_flows_hourly_hour
+    ON address_flows_hourly (hour);
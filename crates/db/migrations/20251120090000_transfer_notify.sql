@@ -0,0 +1,21 @@
+CREATE OR REPLACE FUNCTION notify_usdc_transfer() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('usdc_transfers', json_build_object(
+        'id', NEW.id,
+        'tx_hash', NEW.tx_hash,
+        'log_index', NEW.log_index,
+        'block_number', NEW.block_number,
+        'from_address', NEW.from_address,
+        'to_address', NEW.to_address,
+        'amount', NEW.amount::text,
+        'block_time', NEW.block_time,
+        'created_at', NEW.created_at
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS trg_usdc_transfers_notify ON usdc_transfers;
+CREATE TRIGGER trg_usdc_transfers_notify
+    AFTER INSERT ON usdc_transfers
+    FOR EACH ROW EXECUTE FUNCTION notify_usdc_transfer();
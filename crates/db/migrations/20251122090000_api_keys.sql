@@ -0,0 +1,32 @@
+DO $$
+BEGIN
+    CREATE TYPE api_scope AS ENUM ('read', 'stream', 'export', 'admin');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+-- Only the SHA-256 of each key is stored; the key itself is shown once, when it is created.
+CREATE TABLE IF NOT EXISTS api_keys (
+    id                      BIGSERIAL PRIMARY KEY,
+    name                    TEXT NOT NULL,
+    prefix                  TEXT NOT NULL,
+    key_hash                CHAR(64) NOT NULL UNIQUE,
+    scopes                  api_scope[] NOT NULL,
+    rate_limit_per_minute   INTEGER NOT NULL CHECK (rate_limit_per_minute > 0),
+    daily_quota             BIGINT NOT NULL CHECK (daily_quota > 0),
+    created_at              TIMESTAMPTZ NOT NULL DEFAULT now(),
+    revoked_at              TIMESTAMPTZ
+);
+
+-- Requests per key and UTC day: `requests` were served, `rejected` hit the rate limit or quota.
+CREATE TABLE IF NOT EXISTS api_key_usage (
+    key_id      BIGINT NOT NULL REFERENCES api_keys (id) ON DELETE CASCADE,
+    day         DATE NOT NULL,
+    requests    BIGINT NOT NULL DEFAULT 0,
+    rejected    BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (key_id, day)
+);
+
+CREATE INDEX IF NOT EXISTS idx_api_key_usage_day
+    ON api_key_usage (day);
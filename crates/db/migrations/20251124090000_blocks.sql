@@ -0,0 +1,19 @@
+-- Headers of the blocks the fetcher has read, i.e. every block with at least one USDC transfer.
+-- A header fetched again after a reorg replaces the stored one.
+CREATE TABLE IF NOT EXISTS blocks (
+    number          BIGINT PRIMARY KEY,
+    -- NULL for blocks backfilled from usdc_transfers below until the fetcher reads them again.
+    hash            CHAR(66),
+    parent_hash     CHAR(66),
+    block_time      TIMESTAMPTZ NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS idx_blocks_block_time
+    ON blocks (block_time);
+
+INSERT INTO blocks (number, block_time)
+SELECT DISTINCT ON (block_number) block_number, block_time
+FROM usdc_transfers
+ORDER BY block_number
+ON CONFLICT (number) DO NOTHING;
@@ -0,0 +1,65 @@
+-- `Blacklisted(address)` and `UnBlacklisted(address)` events of the USDC contract.
+CREATE TABLE IF NOT EXISTS blacklist_events (
+    id              BIGSERIAL PRIMARY KEY,
+    tx_hash         CHAR(66) NOT NULL,
+    log_index       BIGINT NOT NULL,
+    block_number    BIGINT NOT NULL,
+    block_time      TIMESTAMPTZ NOT NULL,
+    address         CHAR(42) NOT NULL,
+    -- false for UnBlacklisted.
+    blacklisted     BOOLEAN NOT NULL,
+    created_at      TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_blacklist_events_txhash_logindex
+    ON blacklist_events (tx_hash, log_index);
+
+CREATE INDEX IF NOT EXISTS idx_blacklist_events_address
+    ON blacklist_events (address, block_number DESC, log_index DESC);
+
+DO $$
+BEGIN
+    CREATE TYPE risk_model AS ENUM ('haircut', 'fifo');
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+CREATE TABLE IF NOT EXISTS risk_runs (
+    id              BIGSERIAL PRIMARY KEY,
+    model           risk_model NOT NULL,
+    through_block   BIGINT NOT NULL,
+    sources         INTEGER NOT NULL,
+    scored          BIGINT NOT NULL,
+    started_at      TIMESTAMPTZ NOT NULL,
+    finished_at     TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+-- Latest scores per model; each run replaces the rows of its model.
+CREATE TABLE IF NOT EXISTS address_risk (
+    model               risk_model NOT NULL,
+    address             CHAR(42) NOT NULL,
+    run_id              BIGINT NOT NULL REFERENCES risk_runs (id) ON DELETE CASCADE,
+    -- Share of everything the address received that is traced to a blacklisted address.
+    score               NUMERIC(9,8) NOT NULL CHECK (score BETWEEN 0 AND 1),
+    tainted_received    NUMERIC(38,6) NOT NULL,
+    -- Fewest transfers between a blacklisted address and this one; 0 for blacklisted addresses.
+    hops                INTEGER NOT NULL,
+    PRIMARY KEY (model, address)
+);
+
+CREATE INDEX IF NOT EXISTS idx_address_risk_score
+    ON address_risk (model, score DESC);
+
+-- The largest taint sources of each scored address, with the shortest path from each.
+CREATE TABLE IF NOT EXISTS address_risk_paths (
+    model       risk_model NOT NULL,
+    address     CHAR(42) NOT NULL,
+    source      CHAR(42) NOT NULL,
+    amount      NUMERIC(38,6) NOT NULL,
+    hops        INTEGER NOT NULL,
+    -- From the source to the address, both included.
+    path        TEXT[] NOT NULL,
+    PRIMARY KEY (model, address, source),
+    FOREIGN KEY (model, address) REFERENCES address_risk (model, address) ON DELETE CASCADE
+);
@@ -0,0 +1,52 @@
+DO $$
+BEGIN
+    CREATE TYPE admin_event_kind AS ENUM (
+        'blacklisted', 'unblacklisted',
+        'pause', 'unpause',
+        'minter_configured', 'minter_removed',
+        'master_minter_changed', 'pauser_changed', 'blacklister_changed', 'rescuer_changed',
+        'ownership_transferred', 'admin_changed', 'upgraded'
+    );
+EXCEPTION
+    WHEN duplicate_object THEN NULL;
+END
+$$;
+
+-- Administrative events of the USDC proxy and its implementation; replaces blacklist_events.
+CREATE TABLE IF NOT EXISTS admin_events (
+    id                  BIGSERIAL PRIMARY KEY,
+    tx_hash             CHAR(66) NOT NULL,
+    log_index           BIGINT NOT NULL,
+    block_number        BIGINT NOT NULL,
+    block_time          TIMESTAMPTZ NOT NULL,
+    kind                admin_event_kind NOT NULL,
+    -- The address the event is about: the (un)blacklisted account, the minter, the new owner,
+    -- admin or role holder, or the new implementation. NULL for pause and unpause.
+    account             CHAR(42),
+    -- The previous owner or admin, for ownership_transferred and admin_changed.
+    previous_account    CHAR(42),
+    -- The allowance of minter_configured.
+    amount              NUMERIC(38,6),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_admin_events_txhash_logindex
+    ON admin_events (tx_hash, log_index);
+
+CREATE INDEX IF NOT EXISTS idx_admin_events_block
+    ON admin_events (block_number DESC, log_index DESC);
+
+CREATE INDEX IF NOT EXISTS idx_admin_events_kind
+    ON admin_events (kind, block_number DESC, log_index DESC);
+
+CREATE INDEX IF NOT EXISTS idx_admin_events_account
+    ON admin_events (account, block_number DESC, log_index DESC);
+
+INSERT INTO admin_events (tx_hash, log_index, block_number, block_time, kind, account, created_at)
+SELECT tx_hash, log_index, block_number, block_time,
+       CASE WHEN blacklisted THEN 'blacklisted' ELSE 'unblacklisted' END::admin_event_kind,
+       address, created_at
+FROM blacklist_events
+ON CONFLICT (tx_hash, log_index) DO NOTHING;
+
+DROP TABLE IF EXISTS blacklist_events;
@@ -0,0 +1,63 @@
+-- Currently blacklisted addresses, whose USDC cannot move. Maintained from admin_events, so it
+-- follows the blacklist events of each address whatever order they are indexed in.
+CREATE TABLE IF NOT EXISTS frozen_addresses (
+    address         CHAR(42) PRIMARY KEY,
+    -- The `Blacklisted` event that started the current freeze.
+    block_number    BIGINT NOT NULL,
+    blacklisted_at  TIMESTAMPTZ NOT NULL,
+    tx_hash         CHAR(66) NOT NULL
+);
+
+-- FiatToken emits `Blacklisted` again for an address that is already blacklisted, so the freeze
+-- starts at the earliest `Blacklisted` after the latest `UnBlacklisted`, not at the latest one.
+CREATE OR REPLACE FUNCTION refresh_frozen_address(addr CHAR(42)) RETURNS void AS $$
+BEGIN
+    DELETE FROM frozen_addresses WHERE address = addr;
+
+    INSERT INTO frozen_addresses (address, block_number, blacklisted_at, tx_hash)
+    SELECT b.account, b.block_number, b.block_time, b.tx_hash
+    FROM admin_events b
+    WHERE b.account = addr
+      AND b.kind = 'blacklisted'
+      AND NOT EXISTS (
+          SELECT 1 FROM admin_events u
+          WHERE u.account = addr
+            AND u.kind = 'unblacklisted'
+            AND (u.block_number, u.log_index) > (b.block_number, b.log_index)
+      )
+    ORDER BY b.block_number, b.log_index
+    LIMIT 1;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION admin_events_frozen_addresses() RETURNS trigger AS $$
+BEGIN
+    IF TG_OP IN ('DELETE', 'UPDATE') AND OLD.kind IN ('blacklisted', 'unblacklisted') THEN
+        PERFORM refresh_frozen_address(OLD.account);
+    END IF;
+    IF TG_OP IN ('INSERT', 'UPDATE') AND NEW.kind IN ('blacklisted', 'unblacklisted') THEN
+        PERFORM refresh_frozen_address(NEW.account);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+LOCK TABLE admin_events IN SHARE MODE;
+
+INSERT INTO frozen_addresses (address, block_number, blacklisted_at, tx_hash)
+SELECT DISTINCT ON (b.account) b.account, b.block_number, b.block_time, b.tx_hash
+FROM admin_events b
+WHERE b.kind = 'blacklisted'
+  AND NOT EXISTS (
+      SELECT 1 FROM admin_events u
+      WHERE u.account = b.account
+        AND u.kind = 'unblacklisted'
+        AND (u.block_number, u.log_index) > (b.block_number, b.log_index)
+  )
+ORDER BY b.account, b.block_number, b.log_index
+ON CONFLICT (address) DO NOTHING;
+
+DROP TRIGGER IF EXISTS trg_admin_events_frozen_addresses ON admin_events;
+CREATE TRIGGER trg_admin_events_frozen_addresses
+    AFTER INSERT OR UPDATE OR DELETE ON admin_events
+    FOR EACH ROW EXECUTE FUNCTION admin_events_frozen_addresses();
@@ -0,0 +1,52 @@
+-- CCTP `DepositForBurn` events of the configured chains' TokenMessenger. A burn is identified
+-- by its source domain and the nonce the source MessageTransmitter assigned to its message.
+CREATE TABLE IF NOT EXISTS cctp_burns (
+    id                  BIGSERIAL PRIMARY KEY,
+    chain               TEXT NOT NULL,
+    source_domain       BIGINT NOT NULL,
+    nonce               BIGINT NOT NULL,
+    tx_hash             CHAR(66) NOT NULL,
+    log_index           BIGINT NOT NULL,
+    block_number        BIGINT NOT NULL,
+    block_time          TIMESTAMPTZ NOT NULL,
+    burn_token          CHAR(42) NOT NULL,
+    depositor           CHAR(42) NOT NULL,
+    amount              NUMERIC(38,6) NOT NULL,
+    -- bytes32, as not every destination uses 20-byte addresses.
+    mint_recipient      CHAR(66) NOT NULL,
+    destination_domain  BIGINT NOT NULL,
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (source_domain, nonce)
+);
+
+CREATE INDEX IF NOT EXISTS idx_cctp_burns_time
+    ON cctp_burns (block_time DESC, id DESC);
+
+CREATE INDEX IF NOT EXISTS idx_cctp_burns_depositor
+    ON cctp_burns (depositor, block_time DESC);
+
+-- `MessageReceived` events of the configured chains' MessageTransmitter. Amount and recipient
+-- come from the message body and are NULL for messages that are not token burns.
+CREATE TABLE IF NOT EXISTS cctp_receives (
+    id                  BIGSERIAL PRIMARY KEY,
+    chain               TEXT NOT NULL,
+    destination_domain  BIGINT NOT NULL,
+    source_domain       BIGINT NOT NULL,
+    nonce               BIGINT NOT NULL,
+    tx_hash             CHAR(66) NOT NULL,
+    log_index           BIGINT NOT NULL,
+    block_number        BIGINT NOT NULL,
+    block_time          TIMESTAMPTZ NOT NULL,
+    caller              CHAR(42) NOT NULL,
+    amount              NUMERIC(38,6),
+    mint_recipient      CHAR(66),
+    created_at          TIMESTAMPTZ NOT NULL DEFAULT now(),
+    UNIQUE (source_domain, nonce)
+);
+
+-- Last block indexed on each chain.
+CREATE TABLE IF NOT EXISTS cctp_sync_state (
+    chain               TEXT PRIMARY KEY,
+    last_block          BIGINT NOT NULL,
+    updated_at          TIMESTAMPTZ NOT NULL DEFAULT now()
+);
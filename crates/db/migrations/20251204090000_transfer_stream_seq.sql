@@ -0,0 +1,85 @@
+-- Position of each transfer in commit order, which the live feed resumes from. Ids are taken at
+-- insert time, so with the live loop, gap backfill and verify repair writing at once a lower id
+-- can commit after a higher one has already been streamed.
+ALTER TABLE usdc_transfers ADD COLUMN IF NOT EXISTS stream_seq BIGINT;
+
+CREATE SEQUENCE IF NOT EXISTS usdc_transfers_stream_seq;
+
+-- Setting `stream_seq` must not re-apply a transfer to the aggregates.
+DROP TRIGGER IF EXISTS trg_usdc_transfers_address_aggregates ON usdc_transfers;
+CREATE TRIGGER trg_usdc_transfers_address_aggregates
+    AFTER INSERT OR UPDATE OF block_number, from_address, to_address, amount, block_time OR DELETE ON usdc_transfers
+    FOR EACH ROW EXECUTE FUNCTION usdc_transfers_address_aggregates();
+
+DROP TRIGGER IF EXISTS trg_usdc_transfers_volume_rollups ON usdc_transfers;
+CREATE TRIGGER trg_usdc_transfers_volume_rollups
+    AFTER INSERT OR UPDATE OF block_number, from_address, to_address, amount, block_time OR DELETE ON usdc_transfers
+    FOR EACH ROW EXECUTE FUNCTION usdc_transfers_volume_rollups();
+
+-- Rows already stored keep their id, so a `Last-Event-ID` sent before this migration still
+-- resumes close to where it left off.
+LOCK TABLE usdc_transfers IN SHARE ROW EXCLUSIVE MODE;
+UPDATE usdc_transfers SET stream_seq = id WHERE stream_seq IS NULL;
+SELECT setval('usdc_transfers_stream_seq', COALESCE((SELECT max(stream_seq) FROM usdc_transfers), 0) + 1, false);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_usdc_transfers_stream_seq ON usdc_transfers (stream_seq);
+
+-- Rows of a transaction that have not been given their position yet.
+CREATE INDEX IF NOT EXISTS idx_usdc_transfers_stream_seq_pending ON usdc_transfers (id) WHERE stream_seq IS NULL;
+
+-- Runs when the inserting transaction commits. The first row to fire numbers every row the
+-- transaction inserted in one UPDATE and notifies them; the rows after it return at once. The
+-- advisory lock is held until the commit is visible, so transactions take positions in the
+-- order they become visible and a reader that sees a position has already seen every lower one.
+CREATE OR REPLACE FUNCTION notify_usdc_transfer() RETURNS trigger AS $$
+DECLARE
+    numbered_upto BIGINT := COALESCE(NULLIF(current_setting('usdc_transfers.numbered_upto', true), ''), '0')::BIGINT;
+    t RECORD;
+BEGIN
+    -- Numbered by an earlier row of this transaction, or deleted again before the commit.
+    IF NEW.id <= numbered_upto THEN
+        RETURN NULL;
+    END IF;
+    PERFORM pg_advisory_xact_lock(hashtext('usdc_transfers_stream_seq'));
+    -- Under the lock no other transaction has uncommitted rows visible here, so the rows
+    -- still without a position are this transaction's own.
+    FOR t IN
+        UPDATE usdc_transfers u
+        SET stream_seq = p.seq
+        FROM (
+            SELECT id, nextval('usdc_transfers_stream_seq') AS seq
+            FROM (SELECT id FROM usdc_transfers WHERE stream_seq IS NULL ORDER BY id) pending
+        ) p
+        WHERE u.id = p.id
+        RETURNING u.*
+    LOOP
+        numbered_upto := GREATEST(numbered_upto, t.id);
+        PERFORM pg_notify('usdc_transfers', json_build_object(
+            'stream_seq', t.stream_seq,
+            'id', t.id,
+            'tx_hash', t.tx_hash,
+            'log_index', t.log_index,
+            'block_number', t.block_number,
+            'from_address', t.from_address,
+            'to_address', t.to_address,
+            'amount', t.amount::text,
+            'block_time', t.block_time,
+            'created_at', t.created_at
+        )::text);
+    END LOOP;
+    PERFORM set_config('usdc_transfers.numbered_upto', GREATEST(numbered_upto, NEW.id)::text, true);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+COMMENT ON FUNCTION notify_usdc_transfer() IS
+    'Assigns stream_seq under a transaction-level advisory lock when an inserting transaction '
+    'commits. Every writer of usdc_transfers (live loop, gap backfill, verify --repair, backfill) '
+    'queues on that lock from its first deferred notify trigger until it commits, so commits '
+    'are serialized and a transaction that commits slowly delays ingestion.';
+
+DROP TRIGGER IF EXISTS trg_usdc_transfers_notify ON usdc_transfers;
+CREATE CONSTRAINT TRIGGER trg_usdc_transfers_notify
+    AFTER INSERT ON usdc_transfers
+    DEFERRABLE INITIALLY DEFERRED
+    FOR EACH ROW EXECUTE FUNCTION notify_usdc_transfer();
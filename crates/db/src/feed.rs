@@ -0,0 +1,66 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use sqlx::postgres::PgListener;
+use tokio::sync::broadcast;
+use tracing::{error, warn};
+
+use crate::{PgPool, SequencedTransfer};
+
+/// Channel notified by the `trg_usdc_transfers_notify` trigger with each committed row as JSON.
+pub const TRANSFER_CHANNEL: &str = "usdc_transfers";
+const CAPACITY: usize = 4096;
+const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug)]
+pub enum FeedEvent {
+    Transfer(Arc<SequencedTransfer>),
+    /// The listener lost its connection and notifications may have been missed; subscribers
+    /// should catch up from the database.
+    Resync,
+}
+
+/// Fans out committed transfers from Postgres LISTEN/NOTIFY to in-process subscribers, so it
+/// works whether or not the indexer runs in this process.
+#[derive(Clone)]
+pub struct TransferFeed {
+    sender: broadcast::Sender<FeedEvent>,
+}
+
+impl TransferFeed {
+    pub async fn start(pool: &PgPool) -> Result<Self> {
+        let mut listener = PgListener::connect_with(pool).await?;
+        listener.listen(TRANSFER_CHANNEL).await?;
+        let (sender, _) = broadcast::channel(CAPACITY);
+        tokio::spawn(run(listener, sender.clone()));
+        Ok(Self { sender })
+    }
+
+    /// A receiver that lags by more than the buffer gets `RecvError::Lagged` and should resync.
+    pub fn subscribe(&self) -> broadcast::Receiver<FeedEvent> {
+        self.sender.subscribe()
+    }
+}
+
+async fn run(mut listener: PgListener, sender: broadcast::Sender<FeedEvent>) {
+    loop {
+        match listener.try_recv().await {
+            Ok(Some(notification)) => match serde_json::from_str::<SequencedTransfer>(notification.payload()) {
+                Ok(transfer) => {
+                    let _ = sender.send(FeedEvent::Transfer(Arc::new(transfer)));
+                }
+                Err(e) => warn!(error = %e, "undecodable transfer notification"),
+            },
+            Ok(None) => {
+                warn!("transfer feed lost its connection; reconnecting");
+                let _ = sender.send(FeedEvent::Resync);
+            }
+            Err(e) => {
+                error!(error = %e, "transfer feed listener failed");
+                let _ = sender.send(FeedEvent::Resync);
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            }
+        }
+    }
+}
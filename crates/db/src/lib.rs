@@ -1,9 +1,18 @@
 use anyhow::Result;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 pub use sqlx::{postgres::PgPoolOptions, PgPool, FromRow, migrate::Migrator};
-use serde::Serialize;
+
+pub const ZERO_ADDRESS: &str = "0x0000000000000000000000000000000000000000";
+use sqlx::{Postgres, QueryBuilder};
+use serde::{Deserialize, Serialize};
+use async_stream::try_stream;
 use async_trait::async_trait;
+use futures::{stream::BoxStream, TryStreamExt};
+use tracing::instrument;
+use utoipa::ToSchema;
+
+pub mod feed;
 
 static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
 
@@ -16,49 +25,841 @@ pub async fn init_pool(database_url: &str) -> Result<PgPool> {
     Ok(pool)
 }
 
-#[derive(Serialize, FromRow, Debug)]
+#[derive(Clone, Serialize, Deserialize, FromRow, Debug)]
 pub struct UsdcTransfer {
     pub id: i64,
     pub tx_hash: String,
     pub log_index: i64,
     pub block_number: i64,
+    /// Lowercase hex; the zero address for mints.
+    pub from_address: String,
+    /// Lowercase hex; the zero address for burns.
+    pub to_address: String,
+    /// USDC units with six decimals, as a string.
+    pub amount: Decimal,
+    pub block_time: DateTime<Utc>,
+    /// When the row was indexed.
+    pub created_at: DateTime<Utc>,
+}
+
+/// A transfer with its position in commit order, which the live feed resumes from.
+#[derive(Clone, Deserialize, FromRow, Debug)]
+pub struct SequencedTransfer {
+    pub stream_seq: i64,
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub transfer: UsdcTransfer,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct NewTransfer {
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
     pub from_address: String,
     pub to_address: String,
     pub amount: Decimal,
     pub block_time: DateTime<Utc>,
+}
+
+/// A stored block header. `hash` and `parent_hash` are null for blocks backfilled from
+/// `usdc_transfers` that the fetcher has not read since.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct Block {
+    pub number: i64,
+    pub hash: Option<String>,
+    pub parent_hash: Option<String>,
+    pub block_time: DateTime<Utc>,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewBlock {
+    pub number: u64,
+    pub hash: String,
+    pub parent_hash: String,
+    pub block_time: DateTime<Utc>,
+}
+
+/// All-time totals for one address, maintained by triggers on `usdc_transfers`.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct AddressStats {
+    pub address: String,
+    pub balance: Decimal,
+    pub total_in: Decimal,
+    pub total_out: Decimal,
+    pub transfers_in: i64,
+    pub transfers_out: i64,
+    pub first_block: i64,
+    pub last_block: i64,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct AddressFlows {
+    pub inflow: Decimal,
+    pub outflow: Decimal,
+    pub transfers_in: i64,
+    pub transfers_out: i64,
+}
+
+/// `AddressFlows` of one of several addresses looked up together.
+#[derive(FromRow, Debug)]
+pub struct AddressFlowsEntry {
+    pub address: String,
+    #[sqlx(flatten)]
+    pub flows: AddressFlows,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct Counterparty {
+    pub address: String,
+    /// Sent by the profiled address to this counterparty.
+    pub sent: Decimal,
+    /// Received by the profiled address from this counterparty.
+    pub received: Decimal,
+    pub volume: Decimal,
+    pub transfers: i64,
+}
+
+/// A counterparty of `of`, one of several addresses looked up together.
+#[derive(FromRow, Debug)]
+pub struct CounterpartyEntry {
+    pub of: String,
+    #[sqlx(flatten)]
+    pub counterparty: Counterparty,
+}
+
+/// Transfers from one address to another, summed over a window of `transfer_edges_hourly`.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct GraphEdge {
+    pub from_address: String,
+    pub to_address: String,
+    pub volume: Decimal,
+    pub transfers: i64,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct AddressLabel {
+    pub address: String,
+    pub label: String,
+    pub category: String,
+}
+
+/// Labeled addresses (see `address_labels`) to leave out of leaderboards.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum LabelExclusion {
+    #[default]
+    AllLabeled,
+    Categories(Vec<String>),
+    Nothing,
+}
+
+impl LabelExclusion {
+    /// Binds for the `NOT ($n AND label matches AND ($m IS NULL OR category = ANY($m)))` filter.
+    fn binds(&self) -> (bool, Option<Vec<String>>) {
+        match self {
+            LabelExclusion::AllLabeled => (true, None),
+            LabelExclusion::Categories(categories) => (true, Some(categories.clone())),
+            LabelExclusion::Nothing => (false, None),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum MoverRank {
+    Sent,
+    Received,
+    Net,
+}
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct Holder {
+    pub address: String,
+    pub balance: Decimal,
+    pub label: Option<String>,
+}
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct Mover {
+    pub address: String,
+    pub sent: Decimal,
+    pub received: Decimal,
+    pub net: Decimal,
+    pub label: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeInterval {
+    Hour,
+    Day,
+    Week,
+}
+
+impl VolumeInterval {
+    /// Granularity name as stored in `volume_rollups` and understood by `date_trunc`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            VolumeInterval::Hour => "hour",
+            VolumeInterval::Day => "day",
+            VolumeInterval::Week => "week",
+        }
+    }
+
+    pub fn duration(self) -> chrono::Duration {
+        match self {
+            VolumeInterval::Hour => chrono::Duration::hours(1),
+            VolumeInterval::Day => chrono::Duration::days(1),
+            VolumeInterval::Week => chrono::Duration::weeks(1),
+        }
+    }
+}
+
+/// One bucket of `volume_rollups`. Unique senders and receivers exclude the zero address,
+/// whose activity is reported as mints and burns.
+#[derive(Serialize, FromRow, Debug)]
+pub struct VolumeBucket {
+    pub bucket_start: DateTime<Utc>,
+    pub transfers: i64,
+    pub volume: Decimal,
+    pub unique_senders: i64,
+    pub unique_receivers: i64,
+    pub mints: i64,
+    pub minted: Decimal,
+    pub burns: i64,
+    pub burned: Decimal,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TransferSort {
+    /// Chain order, i.e. `(block_number, log_index)`.
+    #[default]
+    Time,
+    Amount,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// Which side of a transfer `list_transfers_of_addresses` matches each address on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TransferSide {
+    #[default]
+    Either,
+    From,
+    To,
+}
+
+/// A transfer of `owner`, one of several addresses whose transfers are listed together.
+#[derive(FromRow, Debug)]
+pub struct AddressTransfer {
+    pub owner: String,
+    #[sqlx(flatten)]
+    pub transfer: UsdcTransfer,
+}
+
+/// Position of a transfer in the sort order used for keyset paging. `amount` is only set
+/// when sorting by amount.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TransferCursor {
+    pub amount: Option<Decimal>,
+    pub block_number: i64,
+    pub log_index: i64,
+}
+
+/// Filters for `list_transfers`. Address lists match any of their entries and all filters
+/// are combined with AND.
+#[derive(Debug, Default)]
+pub struct TransferQuery {
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    /// Matches transfers where either side is one of these addresses.
+    pub address: Vec<String>,
+    pub exclude_zero_address: bool,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub from_block: Option<i64>,
+    pub to_block: Option<i64>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub sort: TransferSort,
+    pub order: SortOrder,
+    /// Only rows strictly past this position in the requested order are returned.
+    pub after: Option<TransferCursor>,
+    pub limit: u32,
+}
+
+impl TransferQuery {
+    /// Whether `transfer` passes the filters; sort, cursor and limit are ignored.
+    pub fn matches(&self, transfer: &UsdcTransfer) -> bool {
+        (self.from.is_empty() || self.from.contains(&transfer.from_address))
+            && (self.to.is_empty() || self.to.contains(&transfer.to_address))
+            && (self.address.is_empty()
+                || self.address.contains(&transfer.from_address)
+                || self.address.contains(&transfer.to_address))
+            && !(self.exclude_zero_address
+                && (transfer.from_address == ZERO_ADDRESS || transfer.to_address == ZERO_ADDRESS))
+            && self.min_amount.is_none_or(|min| transfer.amount >= min)
+            && self.max_amount.is_none_or(|max| transfer.amount <= max)
+            && self.from_block.is_none_or(|block| transfer.block_number >= block)
+            && self.to_block.is_none_or(|block| transfer.block_number <= block)
+            && self.created_before.is_none_or(|before| transfer.block_time < before)
+            && self.created_after.is_none_or(|after| transfer.block_time > after)
+    }
+}
+
+#[derive(Debug)]
+pub struct NewVerificationRun {
+    pub from_block: u64,
+    pub to_block: u64,
+    pub missing: u64,
+    pub extra: u64,
+    pub mismatched: u64,
+    pub repaired: bool,
+    pub details: serde_json::Value,
+    pub started_at: DateTime<Utc>,
+}
+
+/// What an API key may call; `admin` implies every other scope.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "api_scope", rename_all = "lowercase")]
+pub enum ApiScope {
+    Read,
+    Stream,
+    Export,
+    Admin,
+}
+
+impl ApiScope {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiScope::Read => "read",
+            ApiScope::Stream => "stream",
+            ApiScope::Export => "export",
+            ApiScope::Admin => "admin",
+        }
+    }
+}
+
+impl std::str::FromStr for ApiScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(ApiScope::Read),
+            "stream" => Ok(ApiScope::Stream),
+            "export" => Ok(ApiScope::Export),
+            "admin" => Ok(ApiScope::Admin),
+            other => Err(format!("unknown scope '{}'; expected read, stream, export or admin", other)),
+        }
+    }
+}
+
+/// An API key without its secret; only the SHA-256 of the key is stored.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct ApiKey {
+    pub id: i64,
+    pub name: String,
+    /// Leading characters of the key, enough to tell keys apart.
+    pub prefix: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit_per_minute: i32,
+    pub daily_quota: i64,
     pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKey {
+    pub fn allows(&self, scope: ApiScope) -> bool {
+        self.scopes.iter().any(|s| *s == scope || *s == ApiScope::Admin)
+    }
+}
+
+#[derive(Debug)]
+pub struct NewApiKey {
+    pub name: String,
+    pub prefix: String,
+    pub key_hash: String,
+    pub scopes: Vec<ApiScope>,
+    pub rate_limit_per_minute: i32,
+    pub daily_quota: i64,
+}
+
+/// Requests one key made on one UTC day.
+#[derive(Serialize, FromRow, Debug)]
+pub struct ApiKeyUsage {
+    pub key_id: i64,
+    pub name: String,
+    pub day: NaiveDate,
+    /// Requests that were served.
+    pub requests: i64,
+    /// Requests refused by the rate limit or the daily quota.
+    pub rejected: i64,
+}
+
+/// Requests counted in memory since the last write to `api_key_usage`.
+#[derive(Clone, Copy, Debug)]
+pub struct UsageDelta {
+    pub key_id: i64,
+    pub day: NaiveDate,
+    pub requests: i64,
+    pub rejected: i64,
+}
+
+/// Administrative events of the USDC proxy and its FiatToken implementation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "admin_event_kind", rename_all = "snake_case")]
+pub enum AdminEventKind {
+    Blacklisted,
+    Unblacklisted,
+    Pause,
+    Unpause,
+    MinterConfigured,
+    MinterRemoved,
+    MasterMinterChanged,
+    PauserChanged,
+    BlacklisterChanged,
+    RescuerChanged,
+    OwnershipTransferred,
+    /// The proxy admin changed.
+    AdminChanged,
+    /// The proxy now points at a new implementation.
+    Upgraded,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewAdminEvent {
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub block_time: DateTime<Utc>,
+    pub kind: AdminEventKind,
+    pub account: Option<String>,
+    pub previous_account: Option<String>,
+    pub amount: Option<Decimal>,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct AdminEvent {
+    pub id: i64,
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: i64,
+    pub block_time: DateTime<Utc>,
+    pub kind: AdminEventKind,
+    /// The (un)blacklisted account, the minter, the new owner, admin or role holder, or the
+    /// new implementation; `None` for pause and unpause.
+    pub account: Option<String>,
+    /// The previous owner or admin.
+    pub previous_account: Option<String>,
+    /// The allowance of `MinterConfigured`.
+    pub amount: Option<Decimal>,
+}
+
+/// Filters for `list_admin_events`, which returns the latest events first.
+#[derive(Debug, Default)]
+pub struct AdminEventQuery {
+    /// Any of these kinds; all kinds when empty.
+    pub kinds: Vec<AdminEventKind>,
+    pub account: Option<String>,
+    pub from_block: Option<i64>,
+    pub to_block: Option<i64>,
+    /// Only events strictly before this `(block_number, log_index)`.
+    pub before: Option<(i64, i64)>,
+    pub limit: u32,
+}
+
+/// An address whose latest minter event is `MinterConfigured`.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct Minter {
+    pub address: String,
+    /// The allowance `MinterConfigured` set; mints since then have used up part of it on chain.
+    pub configured_allowance: Decimal,
+    pub block_number: i64,
+    pub block_time: DateTime<Utc>,
+    pub tx_hash: String,
+}
+
+/// The latest `Blacklisted` or `UnBlacklisted` event of an address.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct BlacklistEvent {
+    pub id: i64,
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: i64,
+    pub block_time: DateTime<Utc>,
+    pub address: String,
+    pub blacklisted: bool,
+}
+
+/// A currently blacklisted address and the block its freeze started in.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct BlacklistedAddress {
+    pub address: String,
+    pub block_number: i64,
+}
+
+/// A currently blacklisted address and the USDC frozen in it.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct FrozenAddress {
+    pub address: String,
+    pub balance: Decimal,
+    /// The `Blacklisted` event that started the current freeze.
+    pub block_number: i64,
+    pub blacklisted_at: DateTime<Utc>,
+    pub tx_hash: String,
+}
+
+#[derive(Serialize, FromRow, Debug)]
+pub struct Supply {
+    /// Minted minus burned.
+    pub total_supply: Decimal,
+    /// Held by currently blacklisted addresses.
+    pub frozen: Decimal,
+    pub frozen_addresses: i64,
+}
+
+/// Supply and frozen balance as of the end of one bucket.
+#[derive(Serialize, FromRow, Debug)]
+pub struct SupplyBucket {
+    pub bucket_start: DateTime<Utc>,
+    pub total_supply: Decimal,
+    pub frozen: Decimal,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCctpBurn {
+    pub chain: String,
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub block_time: DateTime<Utc>,
+    pub burn_token: String,
+    pub depositor: String,
+    pub amount: Decimal,
+    /// 0x-prefixed bytes32.
+    pub mint_recipient: String,
+    pub destination_domain: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct NewCctpReceive {
+    pub chain: String,
+    pub destination_domain: u32,
+    pub source_domain: u32,
+    pub nonce: u64,
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub block_time: DateTime<Utc>,
+    pub caller: String,
+    /// From the message body; `None` when the message is not a token burn.
+    pub amount: Option<Decimal>,
+    pub mint_recipient: Option<String>,
+}
+
+/// Whether the mint of a CCTP burn has been seen on the destination chain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BridgeStatus {
+    Matched,
+    Pending,
+}
+
+/// A CCTP burn and, once received, the matching `MessageReceived` on the destination chain.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct BridgeTransfer {
+    pub id: i64,
+    pub nonce: i64,
+    pub amount: Decimal,
+    pub depositor: String,
+    pub mint_recipient: String,
+    pub burn_token: String,
+    pub source_chain: String,
+    pub source_domain: i64,
+    pub source_tx_hash: String,
+    pub source_block_number: i64,
+    pub source_block_time: DateTime<Utc>,
+    pub destination_domain: i64,
+    pub destination_chain: Option<String>,
+    pub destination_tx_hash: Option<String>,
+    pub destination_block_number: Option<i64>,
+    pub destination_block_time: Option<DateTime<Utc>>,
+}
+
+/// Filters for `list_bridge_transfers`, which returns the latest burns first.
+#[derive(Debug, Default)]
+pub struct BridgeTransferQuery {
+    pub status: Option<BridgeStatus>,
+    pub source_domain: Option<u32>,
+    pub destination_domain: Option<u32>,
+    pub depositor: Option<String>,
+    /// Only burns strictly before this `(block_time, id)`.
+    pub before: Option<(DateTime<Utc>, i64)>,
+    pub limit: u32,
+}
+
+/// How taint is split when an address holding both tainted and clean funds sends some.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Deserialize, Serialize, sqlx::Type, ToSchema)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "risk_model", rename_all = "lowercase")]
+pub enum RiskModel {
+    /// Every outgoing transfer carries the sender's tainted share of its balance.
+    #[default]
+    Haircut,
+    /// Outgoing transfers spend the oldest received funds first.
+    Fifo,
+}
+
+impl RiskModel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            RiskModel::Haircut => "haircut",
+            RiskModel::Fifo => "fifo",
+        }
+    }
+}
+
+impl std::str::FromStr for RiskModel {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "haircut" => Ok(RiskModel::Haircut),
+            "fifo" => Ok(RiskModel::Fifo),
+            other => Err(format!("unknown risk model '{}'; expected haircut or fifo", other)),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct NewRiskRun {
+    pub model: RiskModel,
+    pub through_block: u64,
+    pub sources: u32,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct RiskRun {
+    pub id: i64,
+    pub model: RiskModel,
+    pub through_block: i64,
+    pub sources: i32,
+    pub scored: i64,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+}
+
+#[derive(Debug)]
+pub struct NewAddressRisk {
+    pub address: String,
+    pub score: Decimal,
+    pub tainted_received: Decimal,
+    pub hops: u32,
+    pub paths: Vec<RiskPath>,
+}
+
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct AddressRisk {
+    pub address: String,
+    pub run_id: i64,
+    pub score: Decimal,
+    pub tainted_received: Decimal,
+    pub hops: i32,
+}
+
+/// Taint an address received from one blacklisted source, and the shortest route it took.
+#[derive(Clone, Serialize, FromRow, Debug)]
+pub struct RiskPath {
+    pub source: String,
+    pub amount: Decimal,
+    pub hops: i32,
+    /// From the source to the scored address, both included.
+    pub path: Vec<String>,
 }
 
 #[async_trait]
 pub trait WriteData: Send + Sync {
-    async fn insert_transfer_if_not_exists(
-        &self,
-        tx_hash: &str,
-        log_index: u64,
-        block_number: u64,
-        from: &str,
-        to: &str,
-        amount: &Decimal,
-        block_time: &DateTime<Utc>,
-    ) -> Result<()>;
+    /// Returns `false` when the `(tx_hash, log_index)` pair was already stored.
+    async fn insert_transfer_if_not_exists(&self, transfer: &NewTransfer) -> Result<bool>;
+    async fn upsert_transfer(&self, transfer: &NewTransfer) -> Result<()>;
+    async fn delete_transfer(&self, tx_hash: &str, log_index: u64) -> Result<()>;
+    /// Stores a block header, replacing the one stored for that number (e.g. after a reorg).
+    async fn upsert_block(&self, block: &NewBlock) -> Result<()>;
 
     async fn update_sync_state(&self, last_block: u64) -> Result<()>;
     async fn update_sync_state_if_needs(&self, start_block: u64) -> Result<()>;
+
+    async fn insert_verification_run(&self, run: &NewVerificationRun) -> Result<i64>;
+
+    async fn insert_api_key(&self, key: &NewApiKey) -> Result<ApiKey>;
+    /// Returns `false` when no unrevoked key has this id.
+    async fn revoke_api_key(&self, id: i64) -> Result<bool>;
+    /// Adds each delta to its `(key_id, day)` row and returns the resulting request totals.
+    async fn add_api_key_usage(&self, deltas: &[UsageDelta]) -> Result<Vec<(i64, NaiveDate, i64)>>;
+    /// Returns `false` when the `(tx_hash, log_index)` pair was already stored.
+    async fn insert_admin_event(&self, event: &NewAdminEvent) -> Result<bool>;
+    async fn delete_admin_event(&self, tx_hash: &str, log_index: u64) -> Result<()>;
+    /// Labels an address, replacing its current label.
+    async fn upsert_address_label(&self, label: &AddressLabel) -> Result<()>;
+    /// Returns `false` when the address had no label.
+    async fn delete_address_label(&self, address: &str) -> Result<bool>;
+    /// Records a scoring run and replaces every score of its model in one transaction.
+    async fn replace_address_risk(&self, run: &NewRiskRun, scores: &[NewAddressRisk]) -> Result<i64>;
+    /// Returns `false` when the `(source_domain, nonce)` pair was already stored.
+    async fn insert_cctp_burn(&self, burn: &NewCctpBurn) -> Result<bool>;
+    /// Returns `false` when the `(source_domain, nonce)` pair was already stored.
+    async fn insert_cctp_receive(&self, receive: &NewCctpReceive) -> Result<bool>;
+    async fn update_cctp_sync_state(&self, chain: &str, last_block: u64) -> Result<()>;
 }
 
 #[async_trait]
 pub trait ReadData: Send + Sync {
+    async fn ping(&self) -> Result<()>;
     async fn get_last_block(&self) -> Result<u64>;
     async fn get_transfer_by_id(&self, id: i64) -> Result<Option<UsdcTransfer>>;
-    async fn list_transfers(
+    async fn list_transfers_in_blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<UsdcTransfer>>;
+    async fn get_transfers_by_tx_hash(&self, tx_hash: &str) -> Result<Vec<UsdcTransfer>>;
+    async fn get_transfer_by_tx_hash_and_log_index(&self, tx_hash: &str, log_index: i64) -> Result<Option<UsdcTransfer>>;
+    async fn list_transfers_by_tx_hashes(&self, tx_hashes: &[String]) -> Result<Vec<UsdcTransfer>>;
+    async fn list_transfers_by_block_numbers(&self, block_numbers: &[i64]) -> Result<Vec<UsdcTransfer>>;
+    async fn get_block(&self, number: i64) -> Result<Option<Block>>;
+    /// Stored blocks among `numbers`; the others are missing from the result.
+    async fn list_blocks(&self, numbers: &[i64]) -> Result<Vec<Block>>;
+    /// Latest stored block with `block_time <= at`.
+    async fn get_block_at_or_before(&self, at: DateTime<Utc>) -> Result<Option<Block>>;
+    /// Earliest stored block with `block_time >= at`.
+    async fn get_block_at_or_after(&self, at: DateTime<Utc>) -> Result<Option<Block>>;
+    async fn get_address_stats(&self, address: &str) -> Result<Option<AddressStats>>;
+    /// Addresses without transfers are missing from the result.
+    async fn list_address_stats(&self, addresses: &[String]) -> Result<Vec<AddressStats>>;
+    async fn list_address_labels(&self, addresses: &[String]) -> Result<Vec<AddressLabel>>;
+    /// Every labeled address, or those of one category, ordered by category and address.
+    async fn list_all_address_labels(&self, category: Option<&str>) -> Result<Vec<AddressLabel>>;
+    /// Sums hourly flows; `since` is rounded down to the hour and `until` is exclusive.
+    async fn get_address_flows(
+        &self,
+        address: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<AddressFlows>;
+    /// Other addresses by combined volume in both directions, with the same windowing as flows.
+    async fn list_top_counterparties(
+        &self,
+        address: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<Counterparty>>;
+    /// `get_address_flows` of each of `addresses` in one query; every address gets an entry.
+    async fn list_address_flows(
+        &self,
+        addresses: &[String],
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AddressFlowsEntry>>;
+    /// `list_top_counterparties` of each of `addresses` in one query, grouped by `of` and in
+    /// rank order within each group.
+    async fn list_top_counterparties_of(
+        &self,
+        addresses: &[String],
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<CounterpartyEntry>>;
+    /// Edges in either direction between each of `addresses` and its `fan_out` largest
+    /// counterparties by combined volume since `since`. Edges below `min_volume` and self-transfers
+    /// are left out before ranking.
+    async fn list_graph_edges(
+        &self,
+        addresses: &[String],
+        since: DateTime<Utc>,
+        min_volume: Decimal,
+        fan_out: u32,
+    ) -> Result<Vec<GraphEdge>>;
+    async fn list_top_holders(&self, limit: u32, exclusion: &LabelExclusion) -> Result<Vec<Holder>>;
+    /// Ranks by flows in the hourly buckets from `since` (rounded down) onwards, descending.
+    async fn list_top_movers(
         &self,
-        from: Option<String>,
-        to: Option<String>,
-        created_before: Option<DateTime<Utc>>,
-        created_after: Option<DateTime<Utc>>,
-        page: Option<u32>,
-        limit: Option<u32>,
-    ) -> Result<Vec<UsdcTransfer>>;
+        since: DateTime<Utc>,
+        rank_by: MoverRank,
+        limit: u32,
+        exclusion: &LabelExclusion,
+    ) -> Result<Vec<Mover>>;
+    /// Every bucket starting in `[from, to)` after rounding `from` down, empty ones included.
+    async fn list_volume_buckets(
+        &self,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<VolumeBucket>>;
+    async fn get_max_stream_seq(&self) -> Result<i64>;
+    /// Rows matching the filters of `query` with `stream_seq > after_seq`, in commit order.
+    async fn list_transfers_after_seq(
+        &self,
+        query: &TransferQuery,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<SequencedTransfer>>;
+    /// Every row matching `query` in its order, ignoring `query.limit`. The stream holds a pool
+    /// connection until it is dropped.
+    fn stream_transfers(&self, query: TransferQuery) -> BoxStream<'static, Result<UsdcTransfer>>;
+    /// Ordered by `query.sort` with `(block_number, log_index)` as tie-breaker, resuming after `query.after`.
+    async fn list_transfers(&self, query: &TransferQuery) -> Result<Vec<UsdcTransfer>>;
+    /// `list_transfers` for each of `addresses` on its `side`, up to `query.limit` rows each, in
+    /// one query. Rows are grouped by `owner` in the order of `addresses`.
+    async fn list_transfers_of_addresses(
+        &self,
+        addresses: &[String],
+        side: TransferSide,
+        query: &TransferQuery,
+    ) -> Result<Vec<AddressTransfer>>;
+
+    /// The unrevoked key whose SHA-256 hex digest is `key_hash`.
+    async fn get_active_api_key(&self, key_hash: &str) -> Result<Option<ApiKey>>;
+    /// Every key, revoked ones included, by id.
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>>;
+    async fn get_api_key_requests(&self, key_id: i64, day: NaiveDate) -> Result<i64>;
+    /// Usage rows for days in `[from, to]`, optionally of one key, ordered by day and key.
+    async fn list_api_key_usage(&self, from: NaiveDate, to: NaiveDate, key_id: Option<i64>) -> Result<Vec<ApiKeyUsage>>;
+    /// Addresses whose latest blacklist event is `Blacklisted`, by address.
+    async fn list_blacklisted_addresses(&self) -> Result<Vec<BlacklistedAddress>>;
+    async fn get_latest_blacklist_event(&self, address: &str) -> Result<Option<BlacklistEvent>>;
+    async fn list_admin_events(&self, query: &AdminEventQuery) -> Result<Vec<AdminEvent>>;
+    /// Largest frozen balance first.
+    async fn list_frozen_addresses(&self, limit: u32) -> Result<Vec<FrozenAddress>>;
+    async fn get_supply(&self) -> Result<Supply>;
+    /// Every bucket in `[from, to)`, with the frozen set as of each bucket's end.
+    async fn list_supply_buckets(
+        &self,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<SupplyBucket>>;
+    /// Every `Pause` and `Unpause`, in chain order.
+    async fn list_pause_events(&self) -> Result<Vec<AdminEvent>>;
+    /// Largest configured allowance first.
+    async fn list_current_minters(&self) -> Result<Vec<Minter>>;
+    /// Balance of `address` from the transfers strictly before `(block_number, log_index)`.
+    async fn get_balance_before(&self, address: &str, block_number: i64, log_index: i64) -> Result<Decimal>;
+    async fn get_latest_risk_run(&self, model: RiskModel) -> Result<Option<RiskRun>>;
+    async fn get_address_risk(&self, model: RiskModel, address: &str) -> Result<Option<AddressRisk>>;
+    /// Largest sources first.
+    async fn list_address_risk_paths(&self, model: RiskModel, address: &str) -> Result<Vec<RiskPath>>;
+    /// Last block indexed on `chain`; `None` before its first poll.
+    async fn get_cctp_sync_state(&self, chain: &str) -> Result<Option<u64>>;
+    async fn list_bridge_transfers(&self, query: &BridgeTransferQuery) -> Result<Vec<BridgeTransfer>>;
 }
 
 pub struct PostgresRepo {
@@ -73,17 +874,9 @@ impl PostgresRepo {
 
 #[async_trait]
 impl WriteData for PostgresRepo {
-    async fn insert_transfer_if_not_exists(
-        &self,
-        tx_hash: &str,
-        log_index: u64,
-        block_number: u64,
-        from: &str,
-        to: &str,
-        amount: &Decimal,
-        block_time: &DateTime<Utc>,
-    ) -> Result<()> {
-        sqlx::query(
+    #[instrument(name = "db.insert_transfer", skip_all, fields(tx_hash = %transfer.tx_hash, log_index = transfer.log_index), err)]
+    async fn insert_transfer_if_not_exists(&self, transfer: &NewTransfer) -> Result<bool> {
+        let result = sqlx::query(
             r#"
             INSERT INTO usdc_transfers
             (tx_hash, log_index, block_number, from_address, to_address, amount, block_time)
@@ -91,18 +884,78 @@ impl WriteData for PostgresRepo {
             ON CONFLICT (tx_hash, log_index) DO NOTHING
             "#
         )
+            .bind(&transfer.tx_hash)
+            .bind(transfer.log_index as i64)
+            .bind(transfer.block_number as i64)
+            .bind(&transfer.from_address)
+            .bind(&transfer.to_address)
+            .bind(transfer.amount)
+            .bind(transfer.block_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.upsert_transfer", skip_all, fields(tx_hash = %transfer.tx_hash, log_index = transfer.log_index), err)]
+    async fn upsert_transfer(&self, transfer: &NewTransfer) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO usdc_transfers
+            (tx_hash, log_index, block_number, from_address, to_address, amount, block_time)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (tx_hash, log_index) DO UPDATE
+            SET block_number = EXCLUDED.block_number,
+                from_address = EXCLUDED.from_address,
+                to_address = EXCLUDED.to_address,
+                amount = EXCLUDED.amount,
+                block_time = EXCLUDED.block_time
+            "#
+        )
+            .bind(&transfer.tx_hash)
+            .bind(transfer.log_index as i64)
+            .bind(transfer.block_number as i64)
+            .bind(&transfer.from_address)
+            .bind(&transfer.to_address)
+            .bind(transfer.amount)
+            .bind(transfer.block_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "db.delete_transfer", skip(self), err)]
+    async fn delete_transfer(&self, tx_hash: &str, log_index: u64) -> Result<()> {
+        sqlx::query(r#"DELETE FROM usdc_transfers WHERE tx_hash = $1::bpchar AND log_index = $2"#)
             .bind(tx_hash)
             .bind(log_index as i64)
-            .bind(block_number as i64)
-            .bind(from)
-            .bind(to)
-            .bind(amount)
-            .bind(block_time)
             .execute(&self.pool)
             .await?;
         Ok(())
     }
 
+    #[instrument(name = "db.upsert_block", skip_all, fields(number = block.number), err)]
+    async fn upsert_block(&self, block: &NewBlock) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO blocks (number, hash, parent_hash, block_time)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (number) DO UPDATE
+            SET hash = EXCLUDED.hash,
+                parent_hash = EXCLUDED.parent_hash,
+                block_time = EXCLUDED.block_time
+            WHERE blocks.hash IS DISTINCT FROM EXCLUDED.hash
+            "#
+        )
+            .bind(block.number as i64)
+            .bind(&block.hash)
+            .bind(&block.parent_hash)
+            .bind(block.block_time)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "db.update_sync_state", skip(self), err)]
     async fn update_sync_state(&self, last_block: u64) -> Result<()> {
         sqlx::query(
             r#"
@@ -118,6 +971,7 @@ impl WriteData for PostgresRepo {
         Ok(())
     }
 
+    #[instrument(name = "db.update_sync_state_if_needs", skip(self), err)]
     async fn update_sync_state_if_needs(&self, start_block: u64) -> Result<()> {
         let last_block = self.get_last_block().await?;
         if start_block > last_block {
@@ -125,92 +979,1396 @@ impl WriteData for PostgresRepo {
         }
         Ok(())
     }
-}
 
-#[async_trait]
-impl ReadData for PostgresRepo {
-    async fn get_last_block(&self) -> Result<u64> {
-        let val_opt: Option<i64> = sqlx::query_scalar(r#"SELECT last_block FROM sync_state WHERE id = 1"#,)
-            .fetch_optional(&self.pool)
+    #[instrument(name = "db.insert_verification_run", skip_all, fields(from_block = run.from_block, to_block = run.to_block), err)]
+    async fn insert_verification_run(&self, run: &NewVerificationRun) -> Result<i64> {
+        let id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO verification_runs
+            (from_block, to_block, missing, extra, mismatched, repaired, details, started_at, finished_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, now())
+            RETURNING id
+            "#
+        )
+            .bind(run.from_block as i64)
+            .bind(run.to_block as i64)
+            .bind(run.missing as i64)
+            .bind(run.extra as i64)
+            .bind(run.mismatched as i64)
+            .bind(run.repaired)
+            .bind(&run.details)
+            .bind(run.started_at)
+            .fetch_one(&self.pool)
             .await?;
-        Ok(val_opt.map(|v| v as u64).unwrap_or(0))
+        Ok(id)
     }
 
-    async fn get_transfer_by_id(&self, id: i64) -> Result<Option<UsdcTransfer>> {
-        let record = sqlx::query_as::<_, UsdcTransfer>(
+    #[instrument(name = "db.insert_api_key", skip_all, fields(name = %key.name), err)]
+    async fn insert_api_key(&self, key: &NewApiKey) -> Result<ApiKey> {
+        let key = sqlx::query_as::<_, ApiKey>(
             r#"
-            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
-            FROM usdc_transfers
-            WHERE id = $1
-            "#,
+            INSERT INTO api_keys (name, prefix, key_hash, scopes, rate_limit_per_minute, daily_quota)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            RETURNING id, name, prefix, scopes, rate_limit_per_minute, daily_quota, created_at, revoked_at
+            "#
         )
+            .bind(&key.name)
+            .bind(&key.prefix)
+            .bind(&key.key_hash)
+            .bind(&key.scopes)
+            .bind(key.rate_limit_per_minute)
+            .bind(key.daily_quota)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(key)
+    }
+
+    #[instrument(name = "db.revoke_api_key", skip(self), err)]
+    async fn revoke_api_key(&self, id: i64) -> Result<bool> {
+        let result = sqlx::query(r#"UPDATE api_keys SET revoked_at = now() WHERE id = $1 AND revoked_at IS NULL"#)
             .bind(id)
-            .fetch_optional(&self.pool)
+            .execute(&self.pool)
             .await?;
-        Ok(record)
+        Ok(result.rows_affected() > 0)
     }
 
-    async fn list_transfers(
-        &self,
-        from: Option<String>,
-        to: Option<String>,
-        created_before: Option<DateTime<Utc>>,
-        created_after: Option<DateTime<Utc>>,
-        page: Option<u32>,
-        limit: Option<u32>,
-    ) -> Result<Vec<UsdcTransfer>> {
-        let limit = limit.unwrap_or(20).min(100);
-        let offset = (page.unwrap_or(1).saturating_sub(1) * limit) as i64;
-        let mut conditions = Vec::new();
-        let mut binds: Vec<(usize, String)> = Vec::new();
-        let mut bind_index = 1;
-        if let Some(ref addr) = from {
-            conditions.push(format!("from_address = ${}", bind_index));
-            binds.push((bind_index, addr.clone()));
-            bind_index += 1;
-        }
-        if let Some(ref addr) = to {
-            conditions.push(format!("to_address = ${}", bind_index));
-            binds.push((bind_index, addr.clone()));
-            bind_index += 1;
-        }
-        if created_before.is_some() {
-            conditions.push(format!("block_time < ${}", bind_index));
-            bind_index += 1;
-        }
-        if created_after.is_some() {
-            conditions.push(format!("block_time > ${}", bind_index));
-            bind_index += 1;
-        }
-        let where_clause = if conditions.is_empty() {
-            String::from("")
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
-        let query = format!(
+    #[instrument(name = "db.add_api_key_usage", skip_all, fields(count = deltas.len()), err)]
+    async fn add_api_key_usage(&self, deltas: &[UsageDelta]) -> Result<Vec<(i64, NaiveDate, i64)>> {
+        let key_ids: Vec<i64> = deltas.iter().map(|d| d.key_id).collect();
+        let days: Vec<NaiveDate> = deltas.iter().map(|d| d.day).collect();
+        let requests: Vec<i64> = deltas.iter().map(|d| d.requests).collect();
+        let rejected: Vec<i64> = deltas.iter().map(|d| d.rejected).collect();
+        let totals = sqlx::query_as::<_, (i64, NaiveDate, i64)>(
             r#"
-            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
-            FROM usdc_transfers
-            {}
-            ORDER BY block_time DESC
-            LIMIT ${} OFFSET ${}
-            "#,
-            where_clause,
-            bind_index,
-            bind_index + 1
-        );
-        let mut q = sqlx::query_as::<_, UsdcTransfer>(&query);
-        for (_, val) in binds {
-            q = q.bind(val);
-        }
-        if let Some(v) = created_before {
-            q = q.bind(v);
-        }
-        if let Some(v) = created_after {
-            q = q.bind(v);
+            INSERT INTO api_key_usage (key_id, day, requests, rejected)
+            SELECT * FROM UNNEST($1::bigint[], $2::date[], $3::bigint[], $4::bigint[])
+            ON CONFLICT (key_id, day) DO UPDATE
+            SET requests = api_key_usage.requests + EXCLUDED.requests,
+                rejected = api_key_usage.rejected + EXCLUDED.rejected
+            RETURNING key_id, day, requests
+            "#
+        )
+            .bind(&key_ids)
+            .bind(&days)
+            .bind(&requests)
+            .bind(&rejected)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(totals)
+    }
+
+    #[instrument(name = "db.insert_admin_event", skip_all, fields(tx_hash = %event.tx_hash, log_index = event.log_index), err)]
+    async fn insert_admin_event(&self, event: &NewAdminEvent) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO admin_events (tx_hash, log_index, block_number, block_time, kind, account, previous_account, amount)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (tx_hash, log_index) DO NOTHING
+            "#
+        )
+            .bind(&event.tx_hash)
+            .bind(event.log_index as i64)
+            .bind(event.block_number as i64)
+            .bind(event.block_time)
+            .bind(event.kind)
+            .bind(&event.account)
+            .bind(&event.previous_account)
+            .bind(event.amount)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.delete_admin_event", skip(self), err)]
+    async fn delete_admin_event(&self, tx_hash: &str, log_index: u64) -> Result<()> {
+        sqlx::query(r#"DELETE FROM admin_events WHERE tx_hash = $1::bpchar AND log_index = $2"#)
+            .bind(tx_hash)
+            .bind(log_index as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "db.upsert_address_label", skip(self), err)]
+    async fn upsert_address_label(&self, label: &AddressLabel) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO address_labels (address, label, category)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (address) DO UPDATE
+            SET label = EXCLUDED.label, category = EXCLUDED.category
+            "#
+        )
+            .bind(&label.address)
+            .bind(&label.label)
+            .bind(&label.category)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    #[instrument(name = "db.delete_address_label", skip(self), err)]
+    async fn delete_address_label(&self, address: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM address_labels WHERE address = $1::bpchar")
+            .bind(address)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.replace_address_risk", skip_all, fields(model = run.model.as_str(), count = scores.len()), err)]
+    async fn replace_address_risk(&self, run: &NewRiskRun, scores: &[NewAddressRisk]) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        let run_id: i64 = sqlx::query_scalar(
+            r#"
+            INSERT INTO risk_runs (model, through_block, sources, scored, started_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING id
+            "#
+        )
+            .bind(run.model)
+            .bind(run.through_block as i64)
+            .bind(run.sources as i32)
+            .bind(scores.len() as i64)
+            .bind(run.started_at)
+            .fetch_one(&mut *tx)
+            .await?;
+
+        sqlx::query("DELETE FROM address_risk WHERE model = $1")
+            .bind(run.model)
+            .execute(&mut *tx)
+            .await?;
+
+        let addresses: Vec<&str> = scores.iter().map(|s| s.address.as_str()).collect();
+        let values: Vec<Decimal> = scores.iter().map(|s| s.score).collect();
+        let tainted: Vec<Decimal> = scores.iter().map(|s| s.tainted_received).collect();
+        let hops: Vec<i32> = scores.iter().map(|s| s.hops as i32).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO address_risk (model, address, run_id, score, tainted_received, hops)
+            SELECT $1, address, $2, score, tainted, hops
+            FROM UNNEST($3::text[], $4::numeric[], $5::numeric[], $6::int[]) AS t (address, score, tainted, hops)
+            "#
+        )
+            .bind(run.model)
+            .bind(run_id)
+            .bind(&addresses)
+            .bind(&values)
+            .bind(&tainted)
+            .bind(&hops)
+            .execute(&mut *tx)
+            .await?;
+
+        // Paths are passed comma-joined because UNNEST flattens nested arrays.
+        let paths: Vec<(&str, &RiskPath)> = scores
+            .iter()
+            .flat_map(|s| s.paths.iter().map(move |p| (s.address.as_str(), p)))
+            .collect();
+        let addresses: Vec<&str> = paths.iter().map(|(address, _)| *address).collect();
+        let sources: Vec<&str> = paths.iter().map(|(_, p)| p.source.as_str()).collect();
+        let amounts: Vec<Decimal> = paths.iter().map(|(_, p)| p.amount).collect();
+        let hops: Vec<i32> = paths.iter().map(|(_, p)| p.hops).collect();
+        let routes: Vec<String> = paths.iter().map(|(_, p)| p.path.join(",")).collect();
+        sqlx::query(
+            r#"
+            INSERT INTO address_risk_paths (model, address, source, amount, hops, path)
+            SELECT $1, address, source, amount, hops, string_to_array(route, ',')
+            FROM UNNEST($2::text[], $3::text[], $4::numeric[], $5::int[], $6::text[])
+                AS t (address, source, amount, hops, route)
+            "#
+        )
+            .bind(run.model)
+            .bind(&addresses)
+            .bind(&sources)
+            .bind(&amounts)
+            .bind(&hops)
+            .bind(&routes)
+            .execute(&mut *tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(run_id)
+    }
+
+    #[instrument(name = "db.insert_cctp_burn", skip_all, fields(source_domain = burn.source_domain, nonce = burn.nonce), err)]
+    async fn insert_cctp_burn(&self, burn: &NewCctpBurn) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO cctp_burns (chain, source_domain, nonce, tx_hash, log_index, block_number, block_time,
+                                    burn_token, depositor, amount, mint_recipient, destination_domain)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+            ON CONFLICT (source_domain, nonce) DO NOTHING
+            "#
+        )
+            .bind(&burn.chain)
+            .bind(burn.source_domain as i64)
+            .bind(burn.nonce as i64)
+            .bind(&burn.tx_hash)
+            .bind(burn.log_index as i64)
+            .bind(burn.block_number as i64)
+            .bind(burn.block_time)
+            .bind(&burn.burn_token)
+            .bind(&burn.depositor)
+            .bind(burn.amount)
+            .bind(&burn.mint_recipient)
+            .bind(burn.destination_domain as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.insert_cctp_receive", skip_all, fields(source_domain = receive.source_domain, nonce = receive.nonce), err)]
+    async fn insert_cctp_receive(&self, receive: &NewCctpReceive) -> Result<bool> {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO cctp_receives (chain, destination_domain, source_domain, nonce, tx_hash, log_index,
+                                       block_number, block_time, caller, amount, mint_recipient)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+            ON CONFLICT (source_domain, nonce) DO NOTHING
+            "#
+        )
+            .bind(&receive.chain)
+            .bind(receive.destination_domain as i64)
+            .bind(receive.source_domain as i64)
+            .bind(receive.nonce as i64)
+            .bind(&receive.tx_hash)
+            .bind(receive.log_index as i64)
+            .bind(receive.block_number as i64)
+            .bind(receive.block_time)
+            .bind(&receive.caller)
+            .bind(receive.amount)
+            .bind(&receive.mint_recipient)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    #[instrument(name = "db.update_cctp_sync_state", skip(self), err)]
+    async fn update_cctp_sync_state(&self, chain: &str, last_block: u64) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO cctp_sync_state (chain, last_block, updated_at)
+            VALUES ($1, $2, now())
+            ON CONFLICT (chain) DO UPDATE
+            SET last_block = EXCLUDED.last_block, updated_at = now()
+            "#
+        )
+            .bind(chain)
+            .bind(last_block as i64)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ReadData for PostgresRepo {
+    #[instrument(name = "db.ping", skip(self), err)]
+    async fn ping(&self) -> Result<()> {
+        sqlx::query("SELECT 1").execute(&self.pool).await?;
+        Ok(())
+    }
+
+    #[instrument(name = "db.get_last_block", skip(self), err)]
+    async fn get_last_block(&self) -> Result<u64> {
+        let val_opt: Option<i64> = sqlx::query_scalar(r#"SELECT last_block FROM sync_state WHERE id = 1"#,)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(val_opt.map(|v| v as u64).unwrap_or(0))
+    }
+
+    #[instrument(name = "db.get_transfer_by_id", skip(self), err)]
+    async fn get_transfer_by_id(&self, id: i64) -> Result<Option<UsdcTransfer>> {
+        let record = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE id = $1
+            "#,
+        )
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record)
+    }
+
+    #[instrument(name = "db.list_transfers_in_blocks", skip(self), err)]
+    async fn list_transfers_in_blocks(&self, from_block: u64, to_block: u64) -> Result<Vec<UsdcTransfer>> {
+        let records = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE block_number BETWEEN $1 AND $2
+            ORDER BY block_number, log_index
+            "#,
+        )
+            .bind(from_block as i64)
+            .bind(to_block as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(records)
+    }
+
+    // tx_hash is CHAR(66); binds are cast to bpchar so the (tx_hash, log_index) index is used.
+    #[instrument(name = "db.get_transfers_by_tx_hash", skip(self), err)]
+    async fn get_transfers_by_tx_hash(&self, tx_hash: &str) -> Result<Vec<UsdcTransfer>> {
+        let records = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE tx_hash = $1::bpchar
+            ORDER BY log_index
+            "#,
+        )
+            .bind(tx_hash)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(records)
+    }
+
+    #[instrument(name = "db.get_transfer_by_tx_hash_and_log_index", skip(self), err)]
+    async fn get_transfer_by_tx_hash_and_log_index(&self, tx_hash: &str, log_index: i64) -> Result<Option<UsdcTransfer>> {
+        let record = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE tx_hash = $1::bpchar AND log_index = $2
+            "#,
+        )
+            .bind(tx_hash)
+            .bind(log_index)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record)
+    }
+
+    #[instrument(name = "db.list_transfers_by_tx_hashes", skip_all, fields(count = tx_hashes.len()), err)]
+    async fn list_transfers_by_tx_hashes(&self, tx_hashes: &[String]) -> Result<Vec<UsdcTransfer>> {
+        let records = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE tx_hash = ANY($1::bpchar[])
+            ORDER BY block_number, log_index
+            "#,
+        )
+            .bind(tx_hashes)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(records)
+    }
+
+    #[instrument(name = "db.list_transfers_by_block_numbers", skip_all, fields(count = block_numbers.len()), err)]
+    async fn list_transfers_by_block_numbers(&self, block_numbers: &[i64]) -> Result<Vec<UsdcTransfer>> {
+        let records = sqlx::query_as::<_, UsdcTransfer>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at
+            FROM usdc_transfers
+            WHERE block_number = ANY($1)
+            ORDER BY block_number, log_index
+            "#,
+        )
+            .bind(block_numbers)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(records)
+    }
+
+    #[instrument(name = "db.get_block", skip(self), err)]
+    async fn get_block(&self, number: i64) -> Result<Option<Block>> {
+        let record = sqlx::query_as::<_, Block>(
+            r#"SELECT number, hash, parent_hash, block_time FROM blocks WHERE number = $1"#,
+        )
+            .bind(number)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record)
+    }
+
+    #[instrument(name = "db.list_blocks", skip_all, fields(count = numbers.len()), err)]
+    async fn list_blocks(&self, numbers: &[i64]) -> Result<Vec<Block>> {
+        let records = sqlx::query_as::<_, Block>(
+            r#"SELECT number, hash, parent_hash, block_time FROM blocks WHERE number = ANY($1)"#,
+        )
+            .bind(numbers)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(records)
+    }
+
+    #[instrument(name = "db.get_block_at_or_before", skip(self), err)]
+    async fn get_block_at_or_before(&self, at: DateTime<Utc>) -> Result<Option<Block>> {
+        let record = sqlx::query_as::<_, Block>(
+            r#"
+            SELECT number, hash, parent_hash, block_time
+            FROM blocks
+            WHERE block_time <= $1
+            ORDER BY block_time DESC, number DESC
+            LIMIT 1
+            "#,
+        )
+            .bind(at)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record)
+    }
+
+    #[instrument(name = "db.get_block_at_or_after", skip(self), err)]
+    async fn get_block_at_or_after(&self, at: DateTime<Utc>) -> Result<Option<Block>> {
+        let record = sqlx::query_as::<_, Block>(
+            r#"
+            SELECT number, hash, parent_hash, block_time
+            FROM blocks
+            WHERE block_time >= $1
+            ORDER BY block_time, number
+            LIMIT 1
+            "#,
+        )
+            .bind(at)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(record)
+    }
+
+    #[instrument(name = "db.get_address_stats", skip(self), err)]
+    async fn get_address_stats(&self, address: &str) -> Result<Option<AddressStats>> {
+        let stats = sqlx::query_as::<_, AddressStats>(
+            r#"
+            SELECT address, balance, total_in, total_out, transfers_in, transfers_out, first_block, last_block
+            FROM address_stats
+            WHERE address = $1::bpchar
+            "#,
+        )
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(stats)
+    }
+
+    #[instrument(name = "db.list_address_stats", skip_all, fields(count = addresses.len()), err)]
+    async fn list_address_stats(&self, addresses: &[String]) -> Result<Vec<AddressStats>> {
+        let stats = sqlx::query_as::<_, AddressStats>(
+            r#"
+            SELECT address, balance, total_in, total_out, transfers_in, transfers_out, first_block, last_block
+            FROM address_stats
+            WHERE address = ANY($1::bpchar[])
+            "#,
+        )
+            .bind(addresses)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(stats)
+    }
+
+    #[instrument(name = "db.list_address_labels", skip_all, fields(count = addresses.len()), err)]
+    async fn list_address_labels(&self, addresses: &[String]) -> Result<Vec<AddressLabel>> {
+        let labels = sqlx::query_as::<_, AddressLabel>(
+            r#"
+            SELECT address, label, category
+            FROM address_labels
+            WHERE address = ANY($1::bpchar[])
+            "#,
+        )
+            .bind(addresses)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(labels)
+    }
+
+    #[instrument(name = "db.list_all_address_labels", skip(self), err)]
+    async fn list_all_address_labels(&self, category: Option<&str>) -> Result<Vec<AddressLabel>> {
+        let labels = sqlx::query_as::<_, AddressLabel>(
+            r#"
+            SELECT address, label, category
+            FROM address_labels
+            WHERE $1::text IS NULL OR category = $1
+            ORDER BY category, address
+            "#,
+        )
+            .bind(category)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(labels)
+    }
+
+    #[instrument(name = "db.get_address_flows", skip(self), err)]
+    async fn get_address_flows(
+        &self,
+        address: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<AddressFlows> {
+        let flows = sqlx::query_as::<_, AddressFlows>(
+            r#"
+            SELECT COALESCE(sum(inflow), 0) AS inflow,
+                   COALESCE(sum(outflow), 0) AS outflow,
+                   COALESCE(sum(transfers_in), 0)::BIGINT AS transfers_in,
+                   COALESCE(sum(transfers_out), 0)::BIGINT AS transfers_out
+            FROM address_flows_hourly
+            WHERE address = $1::bpchar
+              AND ($2::timestamptz IS NULL OR hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+              AND ($3::timestamptz IS NULL OR hour < $3)
+            "#,
+        )
+            .bind(address)
+            .bind(since)
+            .bind(until)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(flows)
+    }
+
+    #[instrument(name = "db.list_top_counterparties", skip(self), err)]
+    async fn list_top_counterparties(
+        &self,
+        address: &str,
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<Counterparty>> {
+        let counterparties = sqlx::query_as::<_, Counterparty>(
+            r#"
+            WITH edges AS (
+                SELECT to_address AS address, volume AS sent, 0::NUMERIC AS received, transfers
+                FROM transfer_edges_hourly
+                WHERE from_address = $1::bpchar AND to_address <> $1::bpchar
+                  AND ($2::timestamptz IS NULL OR hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+                  AND ($3::timestamptz IS NULL OR hour < $3)
+                UNION ALL
+                SELECT from_address, 0, volume, transfers
+                FROM transfer_edges_hourly
+                WHERE to_address = $1::bpchar AND from_address <> $1::bpchar
+                  AND ($2::timestamptz IS NULL OR hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+                  AND ($3::timestamptz IS NULL OR hour < $3)
+            )
+            SELECT address, sum(sent) AS sent, sum(received) AS received,
+                   sum(sent) + sum(received) AS volume, sum(transfers)::BIGINT AS transfers
+            FROM edges
+            GROUP BY address
+            ORDER BY volume DESC, address
+            LIMIT $4
+            "#,
+        )
+            .bind(address)
+            .bind(since)
+            .bind(until)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(counterparties)
+    }
+
+    #[instrument(name = "db.list_address_flows", skip(self, addresses), fields(count = addresses.len()), err)]
+    async fn list_address_flows(
+        &self,
+        addresses: &[String],
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+    ) -> Result<Vec<AddressFlowsEntry>> {
+        let flows = sqlx::query_as::<_, AddressFlowsEntry>(
+            r#"
+            SELECT a.address,
+                   COALESCE(sum(f.inflow), 0) AS inflow,
+                   COALESCE(sum(f.outflow), 0) AS outflow,
+                   COALESCE(sum(f.transfers_in), 0)::BIGINT AS transfers_in,
+                   COALESCE(sum(f.transfers_out), 0)::BIGINT AS transfers_out
+            FROM unnest($1::bpchar[]) AS a (address)
+            LEFT JOIN address_flows_hourly f
+              ON f.address = a.address
+             AND ($2::timestamptz IS NULL OR f.hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+             AND ($3::timestamptz IS NULL OR f.hour < $3)
+            GROUP BY a.address
+            "#,
+        )
+            .bind(addresses)
+            .bind(since)
+            .bind(until)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(flows)
+    }
+
+    #[instrument(name = "db.list_top_counterparties_of", skip(self, addresses), fields(count = addresses.len()), err)]
+    async fn list_top_counterparties_of(
+        &self,
+        addresses: &[String],
+        since: Option<DateTime<Utc>>,
+        until: Option<DateTime<Utc>>,
+        limit: u32,
+    ) -> Result<Vec<CounterpartyEntry>> {
+        let counterparties = sqlx::query_as::<_, CounterpartyEntry>(
+            r#"
+            WITH edges AS (
+                SELECT from_address AS of, to_address AS address, volume AS sent, 0::NUMERIC AS received, transfers
+                FROM transfer_edges_hourly
+                WHERE from_address = ANY($1::bpchar[]) AND to_address <> from_address
+                  AND ($2::timestamptz IS NULL OR hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+                  AND ($3::timestamptz IS NULL OR hour < $3)
+                UNION ALL
+                SELECT to_address, from_address, 0, volume, transfers
+                FROM transfer_edges_hourly
+                WHERE to_address = ANY($1::bpchar[]) AND from_address <> to_address
+                  AND ($2::timestamptz IS NULL OR hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+                  AND ($3::timestamptz IS NULL OR hour < $3)
+            ),
+            ranked AS (
+                SELECT of, address, sum(sent) AS sent, sum(received) AS received,
+                       sum(sent) + sum(received) AS volume, sum(transfers)::BIGINT AS transfers,
+                       row_number() OVER (PARTITION BY of ORDER BY sum(sent) + sum(received) DESC, address) AS rank
+                FROM edges
+                GROUP BY of, address
+            )
+            SELECT of, address, sent, received, volume, transfers
+            FROM ranked
+            WHERE rank <= $4
+            ORDER BY of, rank
+            "#,
+        )
+            .bind(addresses)
+            .bind(since)
+            .bind(until)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(counterparties)
+    }
+
+    #[instrument(name = "db.list_graph_edges", skip(self, addresses), fields(count = addresses.len()), err)]
+    async fn list_graph_edges(
+        &self,
+        addresses: &[String],
+        since: DateTime<Utc>,
+        min_volume: Decimal,
+        fan_out: u32,
+    ) -> Result<Vec<GraphEdge>> {
+        let edges = sqlx::query_as::<_, GraphEdge>(
+            r#"
+            WITH edges AS (
+                SELECT from_address, to_address, sum(volume) AS volume, sum(transfers)::BIGINT AS transfers
+                FROM transfer_edges_hourly
+                WHERE (from_address = ANY($1::bpchar[]) OR to_address = ANY($1::bpchar[]))
+                  AND from_address <> to_address
+                  AND hour >= date_trunc('hour', $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                GROUP BY from_address, to_address
+                HAVING sum(volume) >= $3
+            ),
+            sides AS (
+                SELECT from_address AS node, to_address AS neighbor, volume
+                FROM edges
+                WHERE from_address = ANY($1::bpchar[])
+                UNION ALL
+                SELECT to_address, from_address, volume
+                FROM edges
+                WHERE to_address = ANY($1::bpchar[])
+            ),
+            ranked AS (
+                SELECT node, neighbor,
+                       row_number() OVER (PARTITION BY node ORDER BY sum(volume) DESC, neighbor) AS rank
+                FROM sides
+                GROUP BY node, neighbor
+            )
+            SELECT e.from_address, e.to_address, e.volume, e.transfers
+            FROM edges e
+            WHERE EXISTS (
+                SELECT 1 FROM ranked r
+                WHERE r.rank <= $4
+                  AND ((r.node = e.from_address AND r.neighbor = e.to_address)
+                    OR (r.node = e.to_address AND r.neighbor = e.from_address))
+            )
+            ORDER BY e.volume DESC, e.from_address, e.to_address
+            "#,
+        )
+            .bind(addresses)
+            .bind(since)
+            .bind(min_volume)
+            .bind(fan_out as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(edges)
+    }
+
+    #[instrument(name = "db.list_top_holders", skip(self), err)]
+    async fn list_top_holders(&self, limit: u32, exclusion: &LabelExclusion) -> Result<Vec<Holder>> {
+        let (exclude, categories) = exclusion.binds();
+        let holders = sqlx::query_as::<_, Holder>(
+            r#"
+            SELECT s.address, s.balance, l.label
+            FROM address_stats s
+            LEFT JOIN address_labels l ON l.address = s.address
+            WHERE NOT ($1 AND l.address IS NOT NULL AND ($2::text[] IS NULL OR l.category = ANY($2)))
+            ORDER BY s.balance DESC, s.address
+            LIMIT $3
+            "#,
+        )
+            .bind(exclude)
+            .bind(categories)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(holders)
+    }
+
+    #[instrument(name = "db.list_top_movers", skip(self), err)]
+    async fn list_top_movers(
+        &self,
+        since: DateTime<Utc>,
+        rank_by: MoverRank,
+        limit: u32,
+        exclusion: &LabelExclusion,
+    ) -> Result<Vec<Mover>> {
+        let (exclude, categories) = exclusion.binds();
+        let order_by = match rank_by {
+            MoverRank::Sent => "sent",
+            MoverRank::Received => "received",
+            MoverRank::Net => "net",
+        };
+        let query = format!(
+            r#"
+            SELECT f.address, sum(f.outflow) AS sent, sum(f.inflow) AS received,
+                   sum(f.inflow) - sum(f.outflow) AS net, l.label
+            FROM address_flows_hourly f
+            LEFT JOIN address_labels l ON l.address = f.address
+            WHERE f.hour >= date_trunc('hour', $1 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+              AND NOT ($2 AND l.address IS NOT NULL AND ($3::text[] IS NULL OR l.category = ANY($3)))
+            GROUP BY f.address, l.label
+            ORDER BY {} DESC, f.address
+            LIMIT $4
+            "#,
+            order_by
+        );
+        let movers = sqlx::query_as::<_, Mover>(&query)
+            .bind(since)
+            .bind(exclude)
+            .bind(categories)
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(movers)
+    }
+
+    #[instrument(name = "db.list_volume_buckets", skip(self), err)]
+    async fn list_volume_buckets(
+        &self,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<VolumeBucket>> {
+        let buckets = sqlx::query_as::<_, VolumeBucket>(
+            r#"
+            SELECT b.bucket AT TIME ZONE 'UTC' AS bucket_start,
+                   COALESCE(r.transfers, 0) AS transfers,
+                   COALESCE(r.volume, 0) AS volume,
+                   COALESCE(r.unique_senders, 0) AS unique_senders,
+                   COALESCE(r.unique_receivers, 0) AS unique_receivers,
+                   COALESCE(r.mints, 0) AS mints,
+                   COALESCE(r.minted, 0) AS minted,
+                   COALESCE(r.burns, 0) AS burns,
+                   COALESCE(r.burned, 0) AS burned
+            FROM generate_series(
+                     date_trunc($1, $2 AT TIME ZONE 'UTC'),
+                     $3 AT TIME ZONE 'UTC',
+                     ('1 ' || $1)::interval
+                 ) AS b (bucket)
+            LEFT JOIN volume_rollups r
+                   ON r.granularity = $1 AND r.bucket = b.bucket AT TIME ZONE 'UTC'
+            WHERE b.bucket < $3 AT TIME ZONE 'UTC'
+            ORDER BY b.bucket
+            "#,
+        )
+            .bind(interval.as_str())
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(buckets)
+    }
+
+    #[instrument(name = "db.get_max_stream_seq", skip(self), err)]
+    async fn get_max_stream_seq(&self) -> Result<i64> {
+        let seq: i64 = sqlx::query_scalar("SELECT COALESCE(max(stream_seq), 0) FROM usdc_transfers")
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(seq)
+    }
+
+    #[instrument(name = "db.list_transfers_after_seq", skip(self, query), err)]
+    async fn list_transfers_after_seq(
+        &self,
+        query: &TransferQuery,
+        after_seq: i64,
+        limit: u32,
+    ) -> Result<Vec<SequencedTransfer>> {
+        let mut builder = QueryBuilder::<Postgres>::new(SEQUENCED_TRANSFER_SELECT);
+        push_transfer_filters(&mut builder, query);
+        builder
+            .push(" AND stream_seq > ")
+            .push_bind(after_seq)
+            .push(" ORDER BY stream_seq LIMIT ")
+            .push_bind(limit as i64);
+
+        let transfers = builder
+            .build_query_as::<SequencedTransfer>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(transfers)
+    }
+
+    fn stream_transfers(&self, query: TransferQuery) -> BoxStream<'static, Result<UsdcTransfer>> {
+        let pool = self.pool.clone();
+        Box::pin(try_stream! {
+            let mut builder = transfer_query_builder(&query);
+            let mut rows = builder.build_query_as::<UsdcTransfer>().fetch(&pool);
+            while let Some(row) = rows.try_next().await? {
+                yield row;
+            }
+        })
+    }
+
+    #[instrument(name = "db.list_transfers", skip(self), err)]
+    async fn list_transfers(&self, query: &TransferQuery) -> Result<Vec<UsdcTransfer>> {
+        let mut builder = transfer_query_builder(query);
+        builder.push(" LIMIT ").push_bind(query.limit as i64);
+
+        let transfers = builder
+            .build_query_as::<UsdcTransfer>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(transfers)
+    }
+
+    #[instrument(name = "db.list_transfers_of_addresses", skip(self, addresses, query), fields(count = addresses.len()), err)]
+    async fn list_transfers_of_addresses(
+        &self,
+        addresses: &[String],
+        side: TransferSide,
+        query: &TransferQuery,
+    ) -> Result<Vec<AddressTransfer>> {
+        let mut builder = QueryBuilder::<Postgres>::new("SELECT a.address AS owner, t.* FROM unnest(");
+        builder
+            .push_bind(addresses)
+            .push("::bpchar[]) WITH ORDINALITY AS a (address, ord) CROSS JOIN LATERAL (")
+            .push(TRANSFER_SELECT)
+            .push(match side {
+                TransferSide::Either => " AND (from_address = a.address OR to_address = a.address)",
+                TransferSide::From => " AND from_address = a.address",
+                TransferSide::To => " AND to_address = a.address",
+            });
+        push_transfer_filters(&mut builder, query);
+        push_transfer_position(&mut builder, query);
+        builder
+            .push(" LIMIT ")
+            .push_bind(query.limit as i64)
+            .push(format!(") t ORDER BY a.ord, {}", transfer_order(query)));
+
+        let transfers = builder
+            .build_query_as::<AddressTransfer>()
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(transfers)
+    }
+
+    #[instrument(name = "db.get_active_api_key", skip_all, err)]
+    async fn get_active_api_key(&self, key_hash: &str) -> Result<Option<ApiKey>> {
+        let key = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, name, prefix, scopes, rate_limit_per_minute, daily_quota, created_at, revoked_at
+            FROM api_keys
+            WHERE key_hash = $1::bpchar AND revoked_at IS NULL
+            "#,
+        )
+            .bind(key_hash)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(key)
+    }
+
+    #[instrument(name = "db.list_api_keys", skip(self), err)]
+    async fn list_api_keys(&self) -> Result<Vec<ApiKey>> {
+        let keys = sqlx::query_as::<_, ApiKey>(
+            r#"
+            SELECT id, name, prefix, scopes, rate_limit_per_minute, daily_quota, created_at, revoked_at
+            FROM api_keys
+            ORDER BY id
+            "#,
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(keys)
+    }
+
+    #[instrument(name = "db.get_api_key_requests", skip(self), err)]
+    async fn get_api_key_requests(&self, key_id: i64, day: NaiveDate) -> Result<i64> {
+        let requests: Option<i64> = sqlx::query_scalar(
+            r#"SELECT requests FROM api_key_usage WHERE key_id = $1 AND day = $2"#,
+        )
+            .bind(key_id)
+            .bind(day)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(requests.unwrap_or(0))
+    }
+
+    #[instrument(name = "db.list_api_key_usage", skip(self), err)]
+    async fn list_api_key_usage(&self, from: NaiveDate, to: NaiveDate, key_id: Option<i64>) -> Result<Vec<ApiKeyUsage>> {
+        let usage = sqlx::query_as::<_, ApiKeyUsage>(
+            r#"
+            SELECT u.key_id, k.name, u.day, u.requests, u.rejected
+            FROM api_key_usage u
+            JOIN api_keys k ON k.id = u.key_id
+            WHERE u.day BETWEEN $1 AND $2
+              AND ($3::bigint IS NULL OR u.key_id = $3)
+            ORDER BY u.day, u.key_id
+            "#,
+        )
+            .bind(from)
+            .bind(to)
+            .bind(key_id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(usage)
+    }
+
+    #[instrument(name = "db.list_blacklisted_addresses", skip(self), err)]
+    async fn list_blacklisted_addresses(&self) -> Result<Vec<BlacklistedAddress>> {
+        let addresses = sqlx::query_as::<_, BlacklistedAddress>(
+            r#"SELECT address, block_number FROM frozen_addresses ORDER BY address"#,
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(addresses)
+    }
+
+    #[instrument(name = "db.get_latest_blacklist_event", skip(self), err)]
+    async fn get_latest_blacklist_event(&self, address: &str) -> Result<Option<BlacklistEvent>> {
+        let event = sqlx::query_as::<_, BlacklistEvent>(
+            r#"
+            SELECT id, tx_hash, log_index, block_number, block_time, account AS address,
+                   kind = 'blacklisted' AS blacklisted
+            FROM admin_events
+            WHERE account = $1::bpchar AND kind IN ('blacklisted', 'unblacklisted')
+            ORDER BY block_number DESC, log_index DESC
+            LIMIT 1
+            "#,
+        )
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(event)
+    }
+
+    #[instrument(name = "db.list_admin_events", skip(self), err)]
+    async fn list_admin_events(&self, query: &AdminEventQuery) -> Result<Vec<AdminEvent>> {
+        let mut builder = QueryBuilder::<Postgres>::new(ADMIN_EVENT_SELECT);
+        if !query.kinds.is_empty() {
+            builder.push(" AND kind = ANY(").push_bind(&query.kinds).push(")");
+        }
+        if let Some(account) = &query.account {
+            builder
+                .push(" AND (account = ")
+                .push_bind(account)
+                .push("::bpchar OR previous_account = ")
+                .push_bind(account)
+                .push("::bpchar)");
+        }
+        if let Some(from_block) = query.from_block {
+            builder.push(" AND block_number >= ").push_bind(from_block);
+        }
+        if let Some(to_block) = query.to_block {
+            builder.push(" AND block_number <= ").push_bind(to_block);
+        }
+        if let Some((block_number, log_index)) = query.before {
+            builder
+                .push(" AND (block_number, log_index) < (")
+                .push_bind(block_number)
+                .push(", ")
+                .push_bind(log_index)
+                .push(")");
+        }
+        builder.push(" ORDER BY block_number DESC, log_index DESC LIMIT ").push_bind(query.limit as i64);
+
+        let events = builder.build_query_as::<AdminEvent>().fetch_all(&self.pool).await?;
+        Ok(events)
+    }
+
+    #[instrument(name = "db.list_frozen_addresses", skip(self), err)]
+    async fn list_frozen_addresses(&self, limit: u32) -> Result<Vec<FrozenAddress>> {
+        let frozen = sqlx::query_as::<_, FrozenAddress>(
+            r#"
+            SELECT f.address, COALESCE(s.balance, 0) AS balance, f.block_number, f.blacklisted_at, f.tx_hash
+            FROM frozen_addresses f
+            LEFT JOIN address_stats s ON s.address = f.address
+            ORDER BY balance DESC, f.address
+            LIMIT $1
+            "#,
+        )
+            .bind(limit as i64)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(frozen)
+    }
+
+    #[instrument(name = "db.get_supply", skip(self), err)]
+    async fn get_supply(&self) -> Result<Supply> {
+        let supply = sqlx::query_as::<_, Supply>(
+            r#"
+            SELECT COALESCE((SELECT total_out - total_in FROM address_stats WHERE address = $1::bpchar), 0) AS total_supply,
+                   COALESCE((SELECT sum(s.balance)
+                             FROM frozen_addresses f
+                             JOIN address_stats s ON s.address = f.address), 0) AS frozen,
+                   (SELECT count(*) FROM frozen_addresses) AS frozen_addresses
+            "#,
+        )
+            .bind(ZERO_ADDRESS)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(supply)
+    }
+
+    #[instrument(name = "db.list_supply_buckets", skip(self), err)]
+    async fn list_supply_buckets(
+        &self,
+        interval: VolumeInterval,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<Vec<SupplyBucket>> {
+        // Supply is the hourly net mint before the first bucket plus a running sum over the
+        // buckets. Freezes are spans from a `Blacklisted` event to the next blacklist event of
+        // the address, computed once; an address is frozen at a bucket's end when a span covers
+        // it, and its balance there is its balance before the series plus a running sum of its
+        // net flow per bucket.
+        let buckets = sqlx::query_as::<_, SupplyBucket>(
+            r#"
+            WITH buckets AS (
+                SELECT b.bucket, b.bucket + ('1 ' || $1)::interval AS bucket_end
+                FROM generate_series(
+                         date_trunc($1, $2 AT TIME ZONE 'UTC'),
+                         $3 AT TIME ZONE 'UTC',
+                         ('1 ' || $1)::interval
+                     ) AS b (bucket)
+                WHERE b.bucket < $3 AT TIME ZONE 'UTC'
+            ),
+            series AS (
+                SELECT min(bucket) AT TIME ZONE 'UTC' AS starts, max(bucket_end) AT TIME ZONE 'UTC' AS ends
+                FROM buckets
+            ),
+            blacklist_changes AS (
+                SELECT account, kind, block_time AS starts,
+                       lead(block_time) OVER (PARTITION BY account ORDER BY block_number, log_index) AS ends
+                FROM admin_events
+                WHERE kind IN ('blacklisted', 'unblacklisted')
+            ),
+            freezes AS (
+                SELECT e.account, e.starts, e.ends
+                FROM blacklist_changes e, series s
+                WHERE e.kind = 'blacklisted'
+                  AND e.starts < s.ends
+                  AND (e.ends IS NULL OR e.ends > s.starts)
+            ),
+            opening AS (
+                SELECT a.account, COALESCE(sum(f.inflow - f.outflow), 0) AS balance
+                FROM (SELECT DISTINCT account FROM freezes) a
+                CROSS JOIN series s
+                LEFT JOIN address_flows_hourly f ON f.address = a.account AND f.hour < s.starts
+                GROUP BY a.account
+            ),
+            moves AS (
+                SELECT f.address AS account, date_trunc($1, f.hour AT TIME ZONE 'UTC') AS bucket,
+                       sum(f.inflow - f.outflow) AS net
+                FROM address_flows_hourly f
+                JOIN opening o ON o.account = f.address
+                CROSS JOIN series s
+                WHERE f.hour >= s.starts AND f.hour < s.ends
+                GROUP BY 1, 2
+            ),
+            balances AS (
+                SELECT bk.bucket, bk.bucket_end, o.account,
+                       o.balance + sum(COALESCE(m.net, 0)) OVER (PARTITION BY o.account ORDER BY bk.bucket) AS balance
+                FROM buckets bk
+                CROSS JOIN opening o
+                LEFT JOIN moves m ON m.account = o.account AND m.bucket = bk.bucket
+            ),
+            frozen AS (
+                SELECT b.bucket, sum(b.balance) AS frozen
+                FROM balances b
+                WHERE EXISTS (
+                    SELECT 1 FROM freezes z
+                    WHERE z.account = b.account
+                      AND z.starts < b.bucket_end AT TIME ZONE 'UTC'
+                      AND (z.ends IS NULL OR z.ends >= b.bucket_end AT TIME ZONE 'UTC')
+                )
+                GROUP BY b.bucket
+            )
+            SELECT bk.bucket AT TIME ZONE 'UTC' AS bucket_start,
+                   (SELECT COALESCE(sum(minted - burned), 0)
+                    FROM volume_rollups
+                    WHERE granularity = 'hour' AND bucket < date_trunc($1, $2 AT TIME ZONE 'UTC') AT TIME ZONE 'UTC')
+                   + sum(COALESCE(r.minted - r.burned, 0)) OVER (ORDER BY bk.bucket) AS total_supply,
+                   COALESCE(fz.frozen, 0) AS frozen
+            FROM buckets bk
+            LEFT JOIN volume_rollups r
+                   ON r.granularity = $1 AND r.bucket = bk.bucket AT TIME ZONE 'UTC'
+            LEFT JOIN frozen fz ON fz.bucket = bk.bucket
+            ORDER BY bk.bucket
+            "#,
+        )
+            .bind(interval.as_str())
+            .bind(from)
+            .bind(to)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(buckets)
+    }
+
+    #[instrument(name = "db.list_pause_events", skip(self), err)]
+    async fn list_pause_events(&self) -> Result<Vec<AdminEvent>> {
+        let mut builder = QueryBuilder::<Postgres>::new(ADMIN_EVENT_SELECT);
+        builder.push(" AND kind IN ('pause', 'unpause') ORDER BY block_number, log_index");
+        let events = builder.build_query_as::<AdminEvent>().fetch_all(&self.pool).await?;
+        Ok(events)
+    }
+
+    #[instrument(name = "db.list_current_minters", skip(self), err)]
+    async fn list_current_minters(&self) -> Result<Vec<Minter>> {
+        let minters = sqlx::query_as::<_, Minter>(
+            r#"
+            SELECT account AS address, amount AS configured_allowance, block_number, block_time, tx_hash
+            FROM (
+                SELECT DISTINCT ON (account) account, kind, amount, block_number, block_time, tx_hash
+                FROM admin_events
+                WHERE kind IN ('minter_configured', 'minter_removed')
+                ORDER BY account, block_number DESC, log_index DESC
+            ) latest
+            WHERE kind = 'minter_configured'
+            ORDER BY configured_allowance DESC, address
+            "#,
+        )
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(minters)
+    }
+
+    #[instrument(name = "db.get_balance_before", skip(self), err)]
+    async fn get_balance_before(&self, address: &str, block_number: i64, log_index: i64) -> Result<Decimal> {
+        let balance: Decimal = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE((SELECT sum(amount) FROM usdc_transfers
+                             WHERE to_address = $1::bpchar AND (block_number, log_index) < ($2, $3)), 0)
+                 - COALESCE((SELECT sum(amount) FROM usdc_transfers
+                             WHERE from_address = $1::bpchar AND (block_number, log_index) < ($2, $3)), 0)
+            "#,
+        )
+            .bind(address)
+            .bind(block_number)
+            .bind(log_index)
+            .fetch_one(&self.pool)
+            .await?;
+        Ok(balance)
+    }
+
+    #[instrument(name = "db.get_latest_risk_run", skip(self), err)]
+    async fn get_latest_risk_run(&self, model: RiskModel) -> Result<Option<RiskRun>> {
+        let run = sqlx::query_as::<_, RiskRun>(
+            r#"
+            SELECT id, model, through_block, sources, scored, started_at, finished_at
+            FROM risk_runs
+            WHERE model = $1
+            ORDER BY id DESC
+            LIMIT 1
+            "#,
+        )
+            .bind(model)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(run)
+    }
+
+    #[instrument(name = "db.get_address_risk", skip(self), err)]
+    async fn get_address_risk(&self, model: RiskModel, address: &str) -> Result<Option<AddressRisk>> {
+        let risk = sqlx::query_as::<_, AddressRisk>(
+            r#"
+            SELECT address, run_id, score, tainted_received, hops
+            FROM address_risk
+            WHERE model = $1 AND address = $2::bpchar
+            "#,
+        )
+            .bind(model)
+            .bind(address)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(risk)
+    }
+
+    #[instrument(name = "db.list_address_risk_paths", skip(self), err)]
+    async fn list_address_risk_paths(&self, model: RiskModel, address: &str) -> Result<Vec<RiskPath>> {
+        let paths = sqlx::query_as::<_, RiskPath>(
+            r#"
+            SELECT source, amount, hops, path
+            FROM address_risk_paths
+            WHERE model = $1 AND address = $2::bpchar
+            ORDER BY amount DESC, source
+            "#,
+        )
+            .bind(model)
+            .bind(address)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(paths)
+    }
+
+    #[instrument(name = "db.get_cctp_sync_state", skip(self), err)]
+    async fn get_cctp_sync_state(&self, chain: &str) -> Result<Option<u64>> {
+        let last_block: Option<i64> = sqlx::query_scalar("SELECT last_block FROM cctp_sync_state WHERE chain = $1")
+            .bind(chain)
+            .fetch_optional(&self.pool)
+            .await?;
+        Ok(last_block.map(|b| b as u64))
+    }
+
+    #[instrument(name = "db.list_bridge_transfers", skip(self), err)]
+    async fn list_bridge_transfers(&self, query: &BridgeTransferQuery) -> Result<Vec<BridgeTransfer>> {
+        let mut builder = QueryBuilder::<Postgres>::new(BRIDGE_TRANSFER_SELECT);
+        match query.status {
+            Some(BridgeStatus::Matched) => {
+                builder.push(" AND r.id IS NOT NULL");
+            }
+            Some(BridgeStatus::Pending) => {
+                builder.push(" AND r.id IS NULL");
+            }
+            None => {}
+        }
+        if let Some(source_domain) = query.source_domain {
+            builder.push(" AND b.source_domain = ").push_bind(source_domain as i64);
+        }
+        if let Some(destination_domain) = query.destination_domain {
+            builder.push(" AND b.destination_domain = ").push_bind(destination_domain as i64);
+        }
+        if let Some(depositor) = &query.depositor {
+            builder.push(" AND b.depositor = ").push_bind(depositor).push("::bpchar");
+        }
+        if let Some((block_time, id)) = query.before {
+            builder
+                .push(" AND (b.block_time, b.id) < (")
+                .push_bind(block_time)
+                .push(", ")
+                .push_bind(id)
+                .push(")");
         }
-        q = q.bind(limit as i64).bind(offset);
-        let transfers = q.fetch_all(&self.pool).await?;
+        builder.push(" ORDER BY b.block_time DESC, b.id DESC LIMIT ").push_bind(query.limit as i64);
+
+        let transfers = builder.build_query_as::<BridgeTransfer>().fetch_all(&self.pool).await?;
         Ok(transfers)
     }
 }
+
+const BRIDGE_TRANSFER_SELECT: &str =
+    "SELECT b.id, b.nonce, b.amount, b.depositor, b.mint_recipient, b.burn_token, \
+            b.chain AS source_chain, b.source_domain, b.tx_hash AS source_tx_hash, \
+            b.block_number AS source_block_number, b.block_time AS source_block_time, \
+            b.destination_domain, r.chain AS destination_chain, r.tx_hash AS destination_tx_hash, \
+            r.block_number AS destination_block_number, r.block_time AS destination_block_time \
+     FROM cctp_burns b \
+     LEFT JOIN cctp_receives r ON r.source_domain = b.source_domain AND r.nonce = b.nonce \
+     WHERE TRUE";
+
+const ADMIN_EVENT_SELECT: &str =
+    "SELECT id, tx_hash, log_index, block_number, block_time, kind, account, previous_account, amount \
+     FROM admin_events WHERE TRUE";
+
+const TRANSFER_SELECT: &str =
+    "SELECT id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at \
+     FROM usdc_transfers WHERE TRUE";
+
+const SEQUENCED_TRANSFER_SELECT: &str =
+    "SELECT stream_seq, id, tx_hash, log_index, block_number, from_address, to_address, amount, block_time, created_at \
+     FROM usdc_transfers WHERE TRUE";
+
+/// `SELECT` over `usdc_transfers` with every filter, the keyset position and the ORDER BY of
+/// `query` applied; callers append their own LIMIT.
+fn transfer_query_builder(query: &TransferQuery) -> QueryBuilder<'_, Postgres> {
+    let mut builder = QueryBuilder::<Postgres>::new(TRANSFER_SELECT);
+    push_transfer_filters(&mut builder, query);
+    push_transfer_position(&mut builder, query);
+    builder
+}
+
+/// Appends the keyset condition and the ORDER BY of `query`.
+fn push_transfer_position<'a>(builder: &mut QueryBuilder<'a, Postgres>, query: &'a TransferQuery) {
+    let op = match query.order {
+        SortOrder::Asc => ">",
+        SortOrder::Desc => "<",
+    };
+    if let Some(cursor) = query.after {
+        match (query.sort, cursor.amount) {
+            (TransferSort::Amount, Some(amount)) => {
+                builder
+                    .push(format!(" AND (amount, block_number, log_index) {} (", op))
+                    .push_bind(amount)
+                    .push(", ");
+            }
+            _ => {
+                builder.push(format!(" AND (block_number, log_index) {} (", op));
+            }
+        }
+        builder
+            .push_bind(cursor.block_number)
+            .push(", ")
+            .push_bind(cursor.log_index)
+            .push(")");
+    }
+    builder.push(format!(" ORDER BY {}", transfer_order(query)));
+}
+
+fn transfer_order(query: &TransferQuery) -> String {
+    let direction = match query.order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    match query.sort {
+        TransferSort::Amount => format!("amount {0}, block_number {0}, log_index {0}", direction),
+        TransferSort::Time => format!("block_number {0}, log_index {0}", direction),
+    }
+}
+
+/// Appends the WHERE conditions of `query`, without keyset position or ordering.
+fn push_transfer_filters<'a>(builder: &mut QueryBuilder<'a, Postgres>, query: &'a TransferQuery) {
+    if !query.from.is_empty() {
+        builder.push(" AND from_address = ANY(").push_bind(&query.from).push("::bpchar[])");
+    }
+    if !query.to.is_empty() {
+        builder.push(" AND to_address = ANY(").push_bind(&query.to).push("::bpchar[])");
+    }
+    if !query.address.is_empty() {
+        builder
+            .push(" AND (from_address = ANY(")
+            .push_bind(&query.address)
+            .push("::bpchar[]) OR to_address = ANY(")
+            .push_bind(&query.address)
+            .push("::bpchar[]))");
+    }
+    if query.exclude_zero_address {
+        builder
+            .push(" AND from_address <> ")
+            .push_bind(ZERO_ADDRESS)
+            .push("::bpchar AND to_address <> ")
+            .push_bind(ZERO_ADDRESS)
+            .push("::bpchar");
+    }
+    if let Some(min) = query.min_amount {
+        builder.push(" AND amount >= ").push_bind(min);
+    }
+    if let Some(max) = query.max_amount {
+        builder.push(" AND amount <= ").push_bind(max);
+    }
+    if let Some(from_block) = query.from_block {
+        builder.push(" AND block_number >= ").push_bind(from_block);
+    }
+    if let Some(to_block) = query.to_block {
+        builder.push(" AND block_number <= ").push_bind(to_block);
+    }
+    if let Some(before) = query.created_before {
+        builder.push(" AND block_time < ").push_bind(before);
+    }
+    if let Some(after) = query.created_after {
+        builder.push(" AND block_time > ").push_bind(after);
+    }
+}
@@ -0,0 +1,106 @@
+//! Freezes follow blacklist events: a repeated `Blacklisted` does not move the start of a freeze,
+//! and the supply series counts a balance as frozen only while a freeze covers a bucket's end.
+//! Needs a Postgres server; run with
+//! `DATABASE_URL=postgres://... cargo test -p db --test frozen -- --ignored`.
+
+use chrono::{DateTime, Utc};
+use db::{AdminEventKind, NewAdminEvent, NewTransfer, PgPool, PostgresRepo, ReadData, VolumeInterval, WriteData, ZERO_ADDRESS};
+use rust_decimal::Decimal;
+
+const HOLDER: &str = "0x28c6c06298d514db089934071355e5743bf21d60";
+const SENDER: &str = "0x7f367cc41522ce07553e823bf3be79a889debe1b";
+const DORMANT: &str = "0xa9d1e08c7793af67e9d92fe308d5697fb81d3e43";
+
+fn at(time: &str) -> DateTime<Utc> {
+    format!("2025-11-20T{}:00Z", time).parse().unwrap()
+}
+
+fn tx(block_number: u64) -> String {
+    format!("0x{:064x}", block_number)
+}
+
+async fn blacklist(repo: &PostgresRepo, kind: AdminEventKind, account: &str, block_number: u64, time: &str) {
+    repo.insert_admin_event(&NewAdminEvent {
+        tx_hash: tx(block_number),
+        log_index: 0,
+        block_number,
+        block_time: at(time),
+        kind,
+        account: Some(account.to_string()),
+        previous_account: None,
+        amount: None,
+    }).await.unwrap();
+}
+
+async fn transfer(repo: &PostgresRepo, from: &str, to: &str, amount: i64, block_number: u64, time: &str) {
+    repo.insert_transfer_if_not_exists(&NewTransfer {
+        tx_hash: tx(block_number),
+        log_index: 1,
+        block_number,
+        from_address: from.to_string(),
+        to_address: to.to_string(),
+        amount: Decimal::from(amount),
+        block_time: at(time),
+    }).await.unwrap();
+}
+
+async fn freeze_start(pool: &PgPool) -> Option<i64> {
+    sqlx::query_scalar(r#"SELECT block_number FROM frozen_addresses WHERE address = $1"#)
+        .bind(HOLDER)
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+}
+
+#[ignore = "needs DATABASE_URL"]
+#[sqlx::test(migrations = "./migrations")]
+async fn repeated_blacklisting_keeps_the_start_of_the_freeze(pool: PgPool) {
+    let repo = PostgresRepo::new(pool.clone());
+
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 220, "10:00").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 300, "11:00").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 310, "12:00").await;
+    assert_eq!(freeze_start(&pool).await, Some(220));
+
+    blacklist(&repo, AdminEventKind::Unblacklisted, HOLDER, 400, "13:00").await;
+    assert_eq!(freeze_start(&pool).await, None);
+
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 500, "14:00").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 510, "15:00").await;
+    assert_eq!(freeze_start(&pool).await, Some(500));
+
+    // Indexed out of order, an earlier event of the same freeze still becomes its start.
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 450, "13:30").await;
+    assert_eq!(freeze_start(&pool).await, Some(450));
+}
+
+#[ignore = "needs DATABASE_URL"]
+#[sqlx::test(migrations = "./migrations")]
+async fn supply_history_counts_balances_while_frozen(pool: PgPool) {
+    let repo = PostgresRepo::new(pool.clone());
+
+    // Frozen before the series starts, so only its opening balance counts.
+    transfer(&repo, ZERO_ADDRESS, DORMANT, 7, 90, "08:10").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, DORMANT, 91, "08:20").await;
+
+    transfer(&repo, ZERO_ADDRESS, SENDER, 50, 99, "10:00").await;
+    transfer(&repo, ZERO_ADDRESS, HOLDER, 100, 100, "10:15").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 101, "10:30").await;
+    transfer(&repo, SENDER, HOLDER, 50, 102, "11:10").await;
+    blacklist(&repo, AdminEventKind::Blacklisted, HOLDER, 103, "12:20").await;
+    blacklist(&repo, AdminEventKind::Unblacklisted, HOLDER, 104, "13:05").await;
+
+    let buckets = repo.list_supply_buckets(VolumeInterval::Hour, at("09:00"), at("15:00")).await.unwrap();
+    let series: Vec<(u32, i64, i64)> = buckets
+        .iter()
+        .map(|b| {
+            let hour = b.bucket_start.format("%H").to_string().parse().unwrap();
+            (hour, b.total_supply.try_into().unwrap(), b.frozen.try_into().unwrap())
+        })
+        .collect();
+
+    assert_eq!(
+        series,
+        [(9, 7, 7), (10, 157, 107), (11, 157, 157), (12, 157, 157), (13, 157, 7), (14, 157, 7)]
+    );
+}
@@ -0,0 +1,59 @@
+//! Deleting a transfer, as the fetcher does for a log a reorg removed, has to reverse what its
+//! insert added to the trigger-maintained aggregates. Needs a Postgres server; run with
+//! `DATABASE_URL=postgres://... cargo test -p db --test reorg -- --ignored`.
+
+use chrono::{TimeZone, Utc};
+use db::{NewTransfer, PgPool, PostgresRepo, WriteData};
+use rust_decimal::Decimal;
+
+const FROM: &str = "0x28c6c06298d514db089934071355e5743bf21d60";
+const TO: &str = "0x7f367cc41522ce07553e823bf3be79a889debe1b";
+
+fn transfer(log_index: u64, amount: i64) -> NewTransfer {
+    NewTransfer {
+        tx_hash: format!("0x{}", "ab".repeat(32)),
+        log_index,
+        block_number: 100,
+        from_address: FROM.to_string(),
+        to_address: TO.to_string(),
+        amount: Decimal::new(amount, 6),
+        block_time: Utc.with_ymd_and_hms(2025, 11, 20, 10, 15, 0).unwrap(),
+    }
+}
+
+async fn hourly_rollup(pool: &PgPool) -> (i64, Decimal, i64) {
+    sqlx::query_as(
+        r#"
+        SELECT transfers, volume, unique_senders
+        FROM volume_rollups
+        WHERE granularity = 'hour' AND bucket = '2025-11-20T10:00:00Z'
+        "#,
+    )
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn sender_out(pool: &PgPool) -> (Decimal, i64) {
+    sqlx::query_as(r#"SELECT total_out, transfers_out FROM address_stats WHERE address = $1"#)
+        .bind(FROM)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+#[ignore = "needs DATABASE_URL"]
+#[sqlx::test(migrations = "./migrations")]
+async fn deleting_a_removed_transfer_reverses_its_rollups(pool: PgPool) {
+    let repo = PostgresRepo::new(pool.clone());
+    repo.insert_transfer_if_not_exists(&transfer(0, 1_500_000)).await.unwrap();
+    repo.insert_transfer_if_not_exists(&transfer(1, 2_000_000)).await.unwrap();
+
+    assert_eq!(hourly_rollup(&pool).await, (2, Decimal::new(3_500_000, 6), 1));
+    assert_eq!(sender_out(&pool).await, (Decimal::new(3_500_000, 6), 2));
+
+    repo.delete_transfer(&transfer(1, 0).tx_hash, 1).await.unwrap();
+
+    assert_eq!(hourly_rollup(&pool).await, (1, Decimal::new(1_500_000, 6), 1));
+    assert_eq!(sender_out(&pool).await, (Decimal::new(1_500_000, 6), 1));
+}
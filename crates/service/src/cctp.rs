@@ -0,0 +1,311 @@
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use config::{AppConfig, CctpChainConfig};
+use db::{NewCctpBurn, NewCctpReceive, PgPool, PostgresRepo, ReadData, WriteData};
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use rust_decimal::Decimal;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, instrument};
+
+use crate::fetchers::{fetch_logs, record_rpc_call, usdc_amount};
+
+const DEPOSIT_FOR_BURN_SIG: &str = "DepositForBurn(uint64,address,uint256,address,bytes32,uint32,bytes32,bytes32)";
+const MESSAGE_RECEIVED_SIG: &str = "MessageReceived(address,uint32,uint64,bytes32,bytes)";
+/// Most blocks indexed before the checkpoint moves, so a restart repeats little work.
+const MAX_BLOCKS_PER_STEP: u64 = 10_000;
+/// Length of a v1 BurnMessage: version, burn token, mint recipient, amount, sender.
+const BURN_MESSAGE_LEN: usize = 132;
+
+enum CctpEvent {
+    Burn {
+        nonce: u64,
+        burn_token: Address,
+        depositor: Address,
+        amount: Decimal,
+        mint_recipient: H256,
+        destination_domain: u32,
+    },
+    /// `burn` is the amount and mint recipient when the message is a token burn.
+    Receive { caller: Address, source_domain: u32, nonce: u64, burn: Option<(Decimal, H256)> },
+}
+
+/// Indexes the CCTP events of `chain` from `start_block` (or its checkpoint) on, then keeps
+/// polling every `cctp.poll_interval_secs`. A failed poll is retried on the next one.
+pub async fn run_cctp_indexer(pool: Arc<PgPool>, cfg: &AppConfig, chain: &CctpChainConfig) -> anyhow::Result<()> {
+    let provider = Provider::<Http>::try_from(chain.rpc_http.as_str())?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+
+    loop {
+        if let Err(e) = index_new_blocks(&provider, &repo, cfg, chain).await {
+            error!(chain = %chain.name, error = %e, "cctp indexing failed");
+        }
+        sleep(Duration::from_secs(cfg.cctp.poll_interval_secs)).await;
+    }
+}
+
+/// Indexes every block from the checkpoint to `cctp.confirmations` blocks behind the chain head
+/// and returns how many events were newly stored.
+#[instrument(skip_all, fields(chain = %chain.name), err)]
+async fn index_new_blocks(
+    provider: &Provider<Http>,
+    repo: &PostgresRepo,
+    cfg: &AppConfig,
+    chain: &CctpChainConfig,
+) -> anyhow::Result<u64> {
+    let addresses: [Address; 2] = [chain.token_messenger.parse()?, chain.message_transmitter.parse()?];
+    let topics = [DEPOSIT_FOR_BURN_SIG, MESSAGE_RECEIVED_SIG].map(|sig| H256::from_slice(&keccak256(sig)));
+
+    let response = provider.get_block_number().await;
+    record_rpc_call("eth_blockNumber", &response);
+    let head = response?.as_u64().saturating_sub(cfg.cctp.confirmations);
+    let mut from_block = match repo.get_cctp_sync_state(&chain.name).await? {
+        Some(last_block) => last_block + 1,
+        None => chain.start_block,
+    };
+    let mut stored = 0;
+
+    while from_block <= head {
+        let to_block = head.min(from_block + MAX_BLOCKS_PER_STEP - 1);
+        let logs = fetch_logs(provider, &addresses, &topics, from_block, to_block, &cfg.ingest).await?;
+        let mut block_time: Option<(U64, DateTime<Utc>)> = None;
+
+        for log in logs {
+            let (Some(event), Some(block_number), Some(tx_hash), Some(log_index)) =
+                (decode_cctp_event(&log, &addresses), log.block_number, log.transaction_hash, log.log_index)
+            else {
+                continue;
+            };
+            let time = match block_time {
+                Some((number, time)) if number == block_number => time,
+                _ => {
+                    let time = get_block_timestamp(provider, block_number).await?;
+                    block_time = Some((block_number, time));
+                    time
+                }
+            };
+
+            let inserted = match event {
+                CctpEvent::Burn { nonce, burn_token, depositor, amount, mint_recipient, destination_domain } => {
+                    repo.insert_cctp_burn(&NewCctpBurn {
+                        chain: chain.name.clone(),
+                        source_domain: chain.domain,
+                        nonce,
+                        tx_hash: format!("{:?}", tx_hash),
+                        log_index: log_index.as_u64(),
+                        block_number: block_number.as_u64(),
+                        block_time: time,
+                        burn_token: format!("{:?}", burn_token),
+                        depositor: format!("{:?}", depositor),
+                        amount,
+                        mint_recipient: format!("{:?}", mint_recipient),
+                        destination_domain,
+                    }).await?
+                }
+                CctpEvent::Receive { caller, source_domain, nonce, burn } => {
+                    repo.insert_cctp_receive(&NewCctpReceive {
+                        chain: chain.name.clone(),
+                        destination_domain: chain.domain,
+                        source_domain,
+                        nonce,
+                        tx_hash: format!("{:?}", tx_hash),
+                        log_index: log_index.as_u64(),
+                        block_number: block_number.as_u64(),
+                        block_time: time,
+                        caller: format!("{:?}", caller),
+                        amount: burn.map(|(amount, _)| amount),
+                        mint_recipient: burn.map(|(_, recipient)| format!("{:?}", recipient)),
+                    }).await?
+                }
+            };
+            stored += inserted as u64;
+        }
+
+        repo.update_cctp_sync_state(&chain.name, to_block).await?;
+        from_block = to_block + 1;
+    }
+
+    if stored > 0 {
+        info!(chain = %chain.name, through_block = head, stored, "cctp events indexed");
+    }
+    Ok(stored)
+}
+
+/// `addresses` is `[token_messenger, message_transmitter]`; each event only counts when it
+/// comes from the contract that defines it.
+fn decode_cctp_event(log: &Log, addresses: &[Address; 2]) -> Option<CctpEvent> {
+    let topic0 = *log.topics.first()?;
+    let word = |i: usize| log.data.get(i * 32..(i + 1) * 32);
+    let topic_address = |i: usize| log.topics.get(i).map(|t| Address::from_slice(&t.as_bytes()[12..]));
+    let topic_u64 = |i: usize| log.topics.get(i).map(|t| U256::from_big_endian(t.as_bytes())).and_then(to_u64);
+
+    if topic0 == H256::from_slice(&keccak256(DEPOSIT_FOR_BURN_SIG)) && log.address == addresses[0] {
+        if log.topics.len() != 4 {
+            return None;
+        }
+        return Some(CctpEvent::Burn {
+            nonce: topic_u64(1)?,
+            burn_token: topic_address(2)?,
+            depositor: topic_address(3)?,
+            amount: usdc_amount(U256::from_big_endian(word(0)?))?,
+            mint_recipient: H256::from_slice(word(1)?),
+            destination_domain: u32::try_from(to_u64(U256::from_big_endian(word(2)?))?).ok()?,
+        });
+    }
+    if topic0 == H256::from_slice(&keccak256(MESSAGE_RECEIVED_SIG)) && log.address == addresses[1] {
+        if log.topics.len() != 3 {
+            return None;
+        }
+        // The body is the dynamic `bytes` argument: an offset into the data, then its length.
+        let start = (to_u64(U256::from_big_endian(word(2)?))? as usize).checked_add(32)?;
+        let len = to_u64(U256::from_big_endian(log.data.get(start - 32..start)?))? as usize;
+        let body = log.data.get(start..start.checked_add(len)?)?;
+        let burn = if body.len() == BURN_MESSAGE_LEN {
+            usdc_amount(U256::from_big_endian(&body[68..100])).map(|amount| (amount, H256::from_slice(&body[36..68])))
+        } else {
+            None
+        };
+        return Some(CctpEvent::Receive {
+            caller: topic_address(1)?,
+            source_domain: u32::try_from(to_u64(U256::from_big_endian(word(0)?))?).ok()?,
+            nonce: topic_u64(2)?,
+            burn,
+        });
+    }
+    None
+}
+
+fn to_u64(value: U256) -> Option<u64> {
+    (value <= U256::from(u64::MAX)).then(|| value.as_u64())
+}
+
+/// Unlike `get_block_time`, leaves the `blocks` table alone: it holds the USDC chain's headers.
+async fn get_block_timestamp(provider: &Provider<Http>, block_number: U64) -> anyhow::Result<DateTime<Utc>> {
+    let response = provider.get_block(block_number).await;
+    record_rpc_call("eth_getBlockByNumber", &response);
+    let block = response?.ok_or_else(|| anyhow::anyhow!("block {} not found", block_number))?;
+    DateTime::from_timestamp(block.timestamp.as_u64() as i64, 0)
+        .ok_or_else(|| anyhow::anyhow!("block {} has an invalid timestamp", block_number))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const DEPOSITOR: &str = "0x28c6c06298d514db089934071355e5743bf21d60";
+    const ETHEREUM: [&str; 2] = ["0xbd3fa81b58ba92a82136038b25adec7066af3155", "0x0a992d191deec32afe36203ad87d7d289a738f81"];
+    const ARBITRUM: [&str; 2] = ["0x19330d10d9cc8751218eaf51e8885d058642e08a", "0xc30362313fbba5cf9163f0bb16a0e01f01a896ca"];
+
+    // 25,000 USDC burned on Ethereum (domain 0) for the same address on Arbitrum (domain 3).
+    const BURN_DATA: &str = concat!(
+        "00000000000000000000000000000000000000000000000000000005d21dba00", // amount
+        "00000000000000000000000028c6c06298d514db089934071355e5743bf21d60", // mintRecipient
+        "0000000000000000000000000000000000000000000000000000000000000003", // destinationDomain
+        "00000000000000000000000019330d10d9cc8751218eaf51e8885d058642e08a", // destinationTokenMessenger
+        "0000000000000000000000000000000000000000000000000000000000000000", // destinationCaller
+    );
+
+    // The mint of that burn on Arbitrum.
+    const RECEIVE_DATA: &str = concat!(
+        "0000000000000000000000000000000000000000000000000000000000000000", // sourceDomain
+        "000000000000000000000000bd3fa81b58ba92a82136038b25adec7066af3155", // sender
+        "0000000000000000000000000000000000000000000000000000000000000060", // offset of messageBody
+        "0000000000000000000000000000000000000000000000000000000000000084", // its length, 132
+        "00000000",                                                         // BurnMessage version
+        "000000000000000000000000a0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", // burnToken
+        "00000000000000000000000028c6c06298d514db089934071355e5743bf21d60", // mintRecipient
+        "00000000000000000000000000000000000000000000000000000005d21dba00", // amount
+        "00000000000000000000000028c6c06298d514db089934071355e5743bf21d60", // messageSender
+        "00000000000000000000000000000000000000000000000000000000",         // padding to 32 bytes
+    );
+
+    fn addresses(contracts: [&str; 2]) -> [Address; 2] {
+        contracts.map(|a| a.parse().unwrap())
+    }
+
+    fn padded(address: &str) -> H256 {
+        H256::from(address.parse::<Address>().unwrap())
+    }
+
+    fn log(address: &str, sig: &str, topics: &[H256], data: &str) -> Log {
+        Log {
+            address: address.parse().unwrap(),
+            topics: std::iter::once(H256::from_slice(&keccak256(sig))).chain(topics.iter().copied()).collect(),
+            data: data.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn burn_log() -> Log {
+        log(ETHEREUM[0], DEPOSIT_FOR_BURN_SIG, &[H256::from_low_u64_be(254_001), padded(USDC), padded(DEPOSITOR)], BURN_DATA)
+    }
+
+    fn receive_log(data: &str) -> Log {
+        log(ARBITRUM[1], MESSAGE_RECEIVED_SIG, &[padded("0xb2f38107a18f8599331677c14374fd3a952fb2c8"), H256::from_low_u64_be(254_001)], data)
+    }
+
+    #[test]
+    fn decodes_deposit_for_burn() {
+        let Some(CctpEvent::Burn { nonce, burn_token, depositor, amount, mint_recipient, destination_domain }) =
+            decode_cctp_event(&burn_log(), &addresses(ETHEREUM))
+        else {
+            panic!("not decoded as a burn");
+        };
+        assert_eq!(nonce, 254_001);
+        assert_eq!(burn_token, USDC.parse::<Address>().unwrap());
+        assert_eq!(depositor, DEPOSITOR.parse::<Address>().unwrap());
+        assert_eq!(amount, Decimal::new(25_000_000_000, 6));
+        assert_eq!(mint_recipient, padded(DEPOSITOR));
+        assert_eq!(destination_domain, 3);
+    }
+
+    #[test]
+    fn decodes_the_burn_message_of_message_received() {
+        let Some(CctpEvent::Receive { caller, source_domain, nonce, burn }) =
+            decode_cctp_event(&receive_log(RECEIVE_DATA), &addresses(ARBITRUM))
+        else {
+            panic!("not decoded as a receive");
+        };
+        assert_eq!(caller, "0xb2f38107a18f8599331677c14374fd3a952fb2c8".parse::<Address>().unwrap());
+        assert_eq!(source_domain, 0);
+        assert_eq!(nonce, 254_001);
+        assert_eq!(burn, Some((Decimal::new(25_000_000_000, 6), padded(DEPOSITOR))));
+    }
+
+    #[test]
+    fn message_received_without_a_burn_message_has_no_amount() {
+        // A 5-byte body, e.g. a message of another application.
+        let data = concat!(
+            "0000000000000000000000000000000000000000000000000000000000000000",
+            "000000000000000000000000bd3fa81b58ba92a82136038b25adec7066af3155",
+            "0000000000000000000000000000000000000000000000000000000000000060",
+            "0000000000000000000000000000000000000000000000000000000000000005",
+            "68656c6c6f000000000000000000000000000000000000000000000000000000",
+        );
+        let Some(CctpEvent::Receive { burn, .. }) = decode_cctp_event(&receive_log(data), &addresses(ARBITRUM)) else {
+            panic!("not decoded as a receive");
+        };
+        assert_eq!(burn, None);
+    }
+
+    #[test]
+    fn rejects_a_body_past_the_end_of_the_data() {
+        // The declared length is one byte more than the data holds.
+        let data = RECEIVE_DATA.replacen(
+            "0000000000000000000000000000000000000000000000000000000000000084",
+            "00000000000000000000000000000000000000000000000000000000000000a1",
+            1,
+        );
+        assert!(decode_cctp_event(&receive_log(&data), &addresses(ARBITRUM)).is_none());
+    }
+
+    #[test]
+    fn ignores_events_from_other_contracts() {
+        // The messenger and transmitter swapped, and a burn seen on another chain's contracts.
+        assert!(decode_cctp_event(&burn_log(), &[ETHEREUM[1], ETHEREUM[0]].map(|a| a.parse().unwrap())).is_none());
+        assert!(decode_cctp_event(&burn_log(), &addresses(ARBITRUM)).is_none());
+        assert!(decode_cctp_event(&receive_log(RECEIVE_DATA), &addresses(ETHEREUM)).is_none());
+    }
+}
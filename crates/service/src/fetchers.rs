@@ -1,22 +1,53 @@
-use std::env;
 use std::str::FromStr;
 
 use chrono::{DateTime, Utc};
-use dotenv::dotenv;
+use config::{AppConfig, IngestConfig};
 use ethers::prelude::*;
 use ethers::types::U256;
 use ethers::utils::keccak256;
 use rust_decimal::Decimal;
+use tokio::task::JoinHandle;
 use tokio::time::{sleep, Duration};
-use db::{PostgresRepo, ReadData, WriteData, PgPool};
+use db::{AdminEventKind, NewAdminEvent, NewBlock, NewTransfer, PostgresRepo, ReadData, WriteData, PgPool};
 use std::sync::{Arc, atomic::{AtomicBool, Ordering}};
-
-
-const TRANSFER_EVENT_SIG: &str = "Transfer(address,address,uint256)";
-const LOGS_BATCH_SIZE: u64 = 100;
-const HISTORICAL_SLEEP_MS: u64 = 200;
-const RATE_LIMIT_WAIT_SECS: u64 = 10;
-const RETRY_TIMES: u64 = 3;
+use telemetry::{ingest, metrics};
+use tracing::{error, info, info_span, warn, Instrument};
+
+
+pub(crate) const TRANSFER_EVENT_SIG: &str = "Transfer(address,address,uint256)";
+
+/// Upper bound of the doubling wait between failed historical syncs or live subscriptions.
+const MAX_RECONNECT_BACKOFF_SECS: u64 = 60;
+/// Blocks of admin events backfilled, then stored, at a time.
+const BACKFILL_BLOCKS_PER_STEP: u64 = 10_000;
+
+/// Administrative events of the proxy and the FiatToken implementation behind it.
+const ADMIN_EVENT_SIGS: [(AdminEventKind, &str); 13] = [
+    (AdminEventKind::Blacklisted, "Blacklisted(address)"),
+    (AdminEventKind::Unblacklisted, "UnBlacklisted(address)"),
+    (AdminEventKind::Pause, "Pause()"),
+    (AdminEventKind::Unpause, "Unpause()"),
+    (AdminEventKind::MinterConfigured, "MinterConfigured(address,uint256)"),
+    (AdminEventKind::MinterRemoved, "MinterRemoved(address)"),
+    (AdminEventKind::MasterMinterChanged, "MasterMinterChanged(address)"),
+    (AdminEventKind::PauserChanged, "PauserChanged(address)"),
+    (AdminEventKind::BlacklisterChanged, "BlacklisterChanged(address)"),
+    (AdminEventKind::RescuerChanged, "RescuerChanged(address)"),
+    (AdminEventKind::OwnershipTransferred, "OwnershipTransferred(address,address)"),
+    (AdminEventKind::AdminChanged, "AdminChanged(address,address)"),
+    (AdminEventKind::Upgraded, "Upgraded(address)"),
+];
+
+/// A decoded log of one of the indexed events.
+pub(crate) enum ContractEvent {
+    Transfer { from: Address, to: Address, amount: Decimal },
+    Admin {
+        kind: AdminEventKind,
+        account: Option<Address>,
+        previous_account: Option<Address>,
+        amount: Option<Decimal>,
+    },
+}
 
 enum RpcErrorKind {
     RateLimited,
@@ -25,6 +56,17 @@ enum RpcErrorKind {
     Fatal,
 }
 
+impl RpcErrorKind {
+    fn label(&self) -> &'static str {
+        match self {
+            RpcErrorKind::RateLimited => "rate_limited",
+            RpcErrorKind::TooManyLogs => "too_many_logs",
+            RpcErrorKind::Temporary => "temporary",
+            RpcErrorKind::Fatal => "error",
+        }
+    }
+}
+
 struct BatchSizer {
     original: u64,
     current: u64,
@@ -49,28 +91,68 @@ impl BatchSizer {
 }
 
 
-pub async fn take_and_push_transactions(pool: Arc<PgPool>) -> anyhow::Result<()> {
-    dotenv().ok();
-
+pub async fn take_and_push_transactions(pool: Arc<PgPool>, cfg: &AppConfig) -> anyhow::Result<()> {
     let repo = PostgresRepo::new(pool.as_ref().clone());
 
-    let rpc_http = env::var("RPC_HTTP")?;
-    let rpc_ws = env::var("RPC_WS")?;
-    let usdc_address: Address = env::var("USDC_CONTRACT")?.parse()?;
-    let start_block = repo.get_last_block().await?;
-
-
-    let provider_http = Provider::<Http>::try_from(rpc_http.clone())?;
-    let provider_ws = Provider::<Ws>::connect(rpc_ws.clone()).await?;
-    let provider_ws = Arc::new(provider_ws);
+    let usdc_address: Address = cfg.chain.usdc_contract.parse()?;
+
+    let provider_http = Provider::<Http>::try_from(cfg.rpc.http.as_str())?;
+    tokio::spawn(poll_chain_head(provider_http.clone(), cfg.ingest.head_poll_secs));
+
+    // Failing here would stop the API with it, health and metrics included, so an unreachable
+    // node or database is retried until it comes back.
+    let mut backoff_secs = 1;
+    loop {
+        let synced = match repo.get_last_block().await {
+            Ok(start_block) => process_historical_transactions(&provider_http, usdc_address, start_block, &pool, &cfg.ingest).await,
+            Err(e) => Err(e),
+        };
+        match synced {
+            Ok(_) => break,
+            Err(e) => {
+                metrics::HISTORICAL_SYNC_FAILURES.inc();
+                error!(error = %e, retry_in_secs = backoff_secs, "historical sync failed, retrying");
+            }
+        }
+        sleep(Duration::from_secs(backoff_secs)).await;
+        backoff_secs = (backoff_secs * 2).min(MAX_RECONNECT_BACKOFF_SECS);
+    }
 
-    let transfer_topic = H256::from_slice(&keccak256(TRANSFER_EVENT_SIG));
+    let mut backfill: Option<JoinHandle<()>> = None;
+    let mut backoff_secs = 1;
+    loop {
+        let session = match Provider::<Ws>::connect(cfg.rpc.ws.as_str()).await {
+            Ok(provider_ws) => {
+                process_live_transactions(&provider_http, Arc::new(provider_ws), usdc_address, &pool, cfg.ingest, &mut backfill).await
+            }
+            Err(e) => Err(e.into()),
+        };
+        match session {
+            Ok(()) => {
+                warn!("live subscription ended, reconnecting");
+                backoff_secs = 1;
+            }
+            Err(e) => {
+                error!(error = %e, retry_in_secs = backoff_secs, "live ingestion failed, reconnecting");
+            }
+        }
 
-    let _latest_block = process_historical_transactions(&provider_http, usdc_address, start_block, transfer_topic, &pool).await?;
+        metrics::WS_RECONNECTS.inc();
+        sleep(Duration::from_secs(backoff_secs)).await;
+        backoff_secs = (backoff_secs * 2).min(MAX_RECONNECT_BACKOFF_SECS);
+    }
+}
 
-    process_live_transactions(&provider_http, provider_ws, usdc_address, transfer_topic, &pool).await?;
 
-    Ok(())
+/// Reads `eth_blockNumber` every `interval_secs`, so the chain head gauge and the lag derived
+/// from it follow the chain rather than what has been indexed.
+async fn poll_chain_head(provider_http: Provider<Http>, interval_secs: u64) {
+    loop {
+        if let Err(e) = get_block_number(&provider_http).await {
+            warn!(error = %e, "chain head poll failed");
+        }
+        sleep(Duration::from_secs(interval_secs)).await;
+    }
 }
 
 
@@ -78,63 +160,59 @@ async fn process_historical_transactions(
     provider_http: &Provider<Http>,
     usdc_address: Address,
     start_block: u64,
-    transfer_topic: H256,
     pool: &sqlx::PgPool,
+    ingest: &IngestConfig,
 ) -> anyhow::Result<u64> {
-    let latest_block = provider_http.get_block_number().await?.as_u64();
+    let latest_block = get_block_number(provider_http).await?;
     let repo = PostgresRepo::new(pool.clone());
 
 
     let mut current = start_block;
-    let mut batch = BatchSizer::new(LOGS_BATCH_SIZE);
+    let mut batch = BatchSizer::new(ingest.logs_batch_size);
 
     while current <= latest_block {
 
+        let batch_start = current;
         let mut attempt = 0;
         let mut success = false;
 
-        while attempt < RETRY_TIMES {
+        while attempt < ingest.retry_times {
+            metrics::BATCH_SIZE.set(batch.current as i64);
             let end = std::cmp::min(current + batch.current - 1, latest_block);
             let filter = Filter::new()
                 .address(usdc_address)
                 .from_block(current)
                 .to_block(end)
-                .topic0(transfer_topic);
-
-            let response = provider_http.get_logs(&filter).await;
+                .topic0(event_topics());
+
+            let span = info_span!(
+                "logs_batch",
+                from_block = current,
+                to_block = end,
+                provider = provider_host(provider_http),
+                attempt,
+            );
+
+            let response = async {
+                let response = provider_http.get_logs(&filter).await;
+                record_rpc_call("eth_getLogs", &response);
+                response
+            }
+                .instrument(span.clone())
+                .await;
 
             match response {
                 Ok(logs) => {
-                    let mut last_block: Option<U64> = None;
-                    let mut last_block_time: Option<DateTime<Utc>> = None;
-
-                    for log in logs {
-                        if let Some((from, to, amount)) = decode_transfer(&log) {
-                            if log.block_number != last_block {
-                                last_block = log.block_number;
-                                last_block_time = get_block_time(&provider_http, log.block_number).await;
-                            }
-
-                            if let Some(datetime) = last_block_time {
-                                if let (Some(tx_hash), Some(li)) = (log.transaction_hash, log.log_index) {
-                                    repo.insert_transfer_if_not_exists(
-                                        &format!("{:?}", tx_hash),
-                                        li.as_u64(),
-                                        log.block_number.unwrap().as_u64(),
-                                        &format!("{:?}", from),
-                                        &format!("{:?}", to),
-                                        &amount,
-                                        &datetime,
-                                    ).await?;
-                                }
-                            }
-                        }
-                    }
+                    store_logs(provider_http, &repo, logs)
+                        .instrument(span.clone())
+                        .await?;
 
-                    repo.update_sync_state(end).await?;
+                    repo.update_sync_state(end).instrument(span).await?;
+                    metrics::INDEXED_BLOCK.set(end as i64);
+                    ingest::mark_block_processed();
                     current = end + 1;
                     batch.reset();
-                    sleep(Duration::from_millis(HISTORICAL_SLEEP_MS)).await;
+                    sleep(Duration::from_millis(ingest.historical_sleep_ms)).await;
                     success = true;
                     break;
                 }
@@ -142,7 +220,8 @@ async fn process_historical_transactions(
                 Err(err) => {
                     let msg = err.to_string();
                     let kind = classify_rpc_error(&msg);
-                    if !handle_rpc_error(kind, &mut current, &mut batch, &mut attempt).await {
+                    span.in_scope(|| warn!(error = %msg, kind = kind.label(), "eth_getLogs failed"));
+                    if !handle_rpc_error(kind, &mut current, &mut batch, &mut attempt, ingest.rate_limit_wait_secs).await {
                         break;
                     }
                 }
@@ -152,98 +231,349 @@ async fn process_historical_transactions(
         if !success {
             current = current.saturating_add(1);
             batch.reset();
+            metrics::SKIPPED_BLOCKS.inc_by(current - batch_start);
+            warn!(from_block = batch_start, to_block = current - 1, "skipped blocks after exhausting retries");
         }
 
     }
 
+    info!(latest_block, "historical sync finished");
     Ok(latest_block)
 }
 
 
+async fn store_logs(
+    provider_http: &Provider<Http>,
+    repo: &PostgresRepo,
+    logs: Vec<Log>,
+) -> anyhow::Result<()> {
+    let mut last_block: Option<U64> = None;
+    let mut last_block_time: Option<DateTime<Utc>> = None;
+
+    for log in logs {
+        if let Some(event) = decode_event(&log) {
+            if log.block_number != last_block {
+                last_block = log.block_number;
+                last_block_time = get_block_time(provider_http, repo, log.block_number).await?;
+            }
+
+            if let Some(datetime) = last_block_time {
+                store_event(repo, &log, event, datetime).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+
+/// Writes one decoded log; logs without a transaction hash or log index are ignored.
+async fn store_event(repo: &PostgresRepo, log: &Log, event: ContractEvent, block_time: DateTime<Utc>) -> anyhow::Result<()> {
+    let (Some(tx_hash), Some(li), Some(block_number)) = (log.transaction_hash, log.log_index, log.block_number) else {
+        return Ok(());
+    };
+    match event {
+        ContractEvent::Transfer { from, to, amount } => {
+            let inserted = repo.insert_transfer_if_not_exists(&NewTransfer {
+                tx_hash: format!("{:?}", tx_hash),
+                log_index: li.as_u64(),
+                block_number: block_number.as_u64(),
+                from_address: format!("{:?}", from),
+                to_address: format!("{:?}", to),
+                amount,
+                block_time,
+            }).await?;
+            record_insert(inserted, block_number.as_u64(), &block_time);
+        }
+        ContractEvent::Admin { kind, account, previous_account, amount } => {
+            let inserted = repo.insert_admin_event(&NewAdminEvent {
+                tx_hash: format!("{:?}", tx_hash),
+                log_index: li.as_u64(),
+                block_number: block_number.as_u64(),
+                block_time,
+                kind,
+                account: account.map(|a| format!("{:?}", a)),
+                previous_account: previous_account.map(|a| format!("{:?}", a)),
+                amount,
+            }).await?;
+            if inserted {
+                info!(?kind, account = ?account, block_number = block_number.as_u64(), "admin event indexed");
+            }
+        }
+    }
+    Ok(())
+}
+
+
+/// Deletes the row stored for a log the node reports as removed by a reorg. The aggregate
+/// and frozen-balance triggers reverse its contribution on delete.
+async fn retract_event(repo: &PostgresRepo, log: &Log, event: &ContractEvent) -> anyhow::Result<()> {
+    let (Some(tx_hash), Some(li)) = (log.transaction_hash, log.log_index) else {
+        return Ok(());
+    };
+    let tx_hash = format!("{:?}", tx_hash);
+    match event {
+        ContractEvent::Transfer { .. } => repo.delete_transfer(&tx_hash, li.as_u64()).await?,
+        ContractEvent::Admin { .. } => repo.delete_admin_event(&tx_hash, li.as_u64()).await?,
+    }
+    metrics::REMOVED_LOGS.inc();
+    warn!(tx_hash, log_index = li.as_u64(), "log removed by a reorg, deleted its row");
+    Ok(())
+}
+
+
 async fn process_live_transactions(
     provider_http: &Provider<Http>,
     provider_ws: Arc<Provider<Ws>>,
     usdc_address: Address,
-    transfer_topic: H256,
     pool: &sqlx::PgPool,
+    ingest: IngestConfig,
+    backfill: &mut Option<JoinHandle<()>>,
 ) -> anyhow::Result<()> {
     let repo = PostgresRepo::new(pool.clone());
 
     let filter_live = Filter::new()
         .address(usdc_address)
-        .topic0(transfer_topic);
+        .topic0(event_topics());
 
     let can_update_sync_state = Arc::new(AtomicBool::new(false));
 
-    let mut sub = provider_ws.subscribe_logs(&filter_live).await?;
+    let sub = provider_ws.subscribe_logs(&filter_live).await;
+    record_rpc_call("eth_subscribe", &sub);
+    let mut sub = sub?;
+    ingest::set_ws_connected(true);
+    info!(provider = provider_host(provider_http), "subscribed to live contract logs");
 
     let pool_clone = pool.clone();
     let provider_http_clone = provider_http.clone();
     let usdc_address_clone = usdc_address;
-    let transfer_topic_clone = transfer_topic;
 
     let can_update_clone = can_update_sync_state.clone();
 
-    let _historical_handle = tokio::spawn(async move {
+    // A backfill left over from the previous subscription would race this one over the same gap.
+    if let Some(previous) = backfill.take() {
+        previous.abort();
+    }
+    *backfill = Some(tokio::spawn(async move {
         let repo_clone = PostgresRepo::new(pool_clone.clone());
-        if let Ok(last_stored_block) = repo_clone.get_last_block().await {
-            if let Ok(current_block) = provider_http_clone.get_block_number().await {
-                let current_block = current_block.as_u64();
-                if current_block > last_stored_block {
-                    if let Err(_e) = process_historical_transactions(
-                        &provider_http_clone,
-                        usdc_address_clone,
-                        last_stored_block,
-                        transfer_topic_clone,
-                        &pool_clone,
-                    ).await {
-                    }
-                    else {
-                        can_update_clone.store(true, Ordering::SeqCst);
-                    }
-                }
-                else {
-                    can_update_clone.store(false, Ordering::SeqCst);
-                }
+        let last_stored_block = match repo_clone.get_last_block().await {
+            Ok(block) => block,
+            Err(e) => {
+                error!(error = %e, "gap backfill could not read the last indexed block; sync_state stays frozen");
+                return;
+            }
+        };
+        let current_block = match get_block_number(&provider_http_clone).await {
+            Ok(block) => block,
+            Err(e) => {
+                error!(error = %e, "gap backfill could not read the chain head; sync_state stays frozen");
+                return;
             }
+        };
+        if current_block > last_stored_block {
+            if let Err(e) = process_historical_transactions(
+                &provider_http_clone,
+                usdc_address_clone,
+                last_stored_block,
+                &pool_clone,
+                &ingest,
+            ).await {
+                error!(error = %e, from_block = last_stored_block, "gap backfill failed; sync_state stays frozen");
+            }
+            else {
+                can_update_clone.store(true, Ordering::SeqCst);
+            }
+        }
+        else {
+            can_update_clone.store(true, Ordering::SeqCst);
         }
-    });
+    }));
 
 
     let mut last_block: Option<U64> = None;
     let mut last_block_time: Option<DateTime<Utc>> = None;
 
     while let Some(log) = sub.next().await {
-        if let Some((from, to, amount)) = decode_transfer(&log) {
-            if log.block_number != last_block {
-                last_block = log.block_number;
-                last_block_time = get_block_time(&provider_http, log.block_number).await;
-            }
-            if let (Some(_block_number), Some(datetime)) = (log.block_number, last_block_time) {
-                if let (Some(tx_hash), Some(li)) = (log.transaction_hash, log.log_index) {
-                    repo.insert_transfer_if_not_exists(
-                        &format!("{:?}", tx_hash),
-                        li.as_u64(),
-                        log.block_number.unwrap().as_u64(),
-                        &format!("{:?}", from),
-                        &format!("{:?}", to),
-                        &amount,
-                        &datetime,
-                    ).await?;
+        let span = info_span!(
+            "live_log",
+            block_number = log.block_number.map(|b| b.as_u64()),
+            log_index = log.log_index.map(|li| li.as_u64()),
+        );
+        async {
+            if let Some(event) = decode_event(&log) {
+                if log.removed == Some(true) {
+                    return retract_event(&repo, &log, &event).await;
+                }
+                if log.block_number != last_block {
+                    last_block = log.block_number;
+                    last_block_time = get_block_time(provider_http, &repo, log.block_number).await?;
+                }
+                if let (Some(block_number), Some(datetime)) = (log.block_number, last_block_time) {
+                    store_event(&repo, &log, event, datetime).await?;
 
                     if can_update_sync_state.load(Ordering::SeqCst) {
-                        repo.update_sync_state(log.block_number.unwrap().as_u64()).await?;
+                        repo.update_sync_state(block_number.as_u64()).await?;
                     }
                 }
             }
+            Ok::<(), anyhow::Error>(())
         }
+            .instrument(span)
+            .await
+            .inspect_err(|_| ingest::set_ws_connected(false))?;
     }
 
+    ingest::set_ws_connected(false);
     Ok(())
 }
 
 
-fn decode_transfer(log: &Log) -> Option<(Address, Address, Decimal)> {
+/// Fetches every log of `addresses` with one of `topics` in `[from_block, to_block]`. Unlike
+/// the ingestion loop, a window that still fails after the retries is an error instead of
+/// being skipped.
+pub(crate) async fn fetch_logs(
+    provider_http: &Provider<Http>,
+    addresses: &[Address],
+    topics: &[H256],
+    from_block: u64,
+    to_block: u64,
+    ingest: &IngestConfig,
+) -> anyhow::Result<Vec<Log>> {
+    let mut logs = Vec::new();
+    let mut current = from_block;
+    let mut batch = BatchSizer::new(ingest.logs_batch_size);
+    let mut attempt = 0;
+
+    while current <= to_block {
+        let end = std::cmp::min(current + batch.current - 1, to_block);
+        let filter = Filter::new()
+            .address(addresses.to_vec())
+            .from_block(current)
+            .to_block(end)
+            .topic0(topics.to_vec());
+
+        let response = provider_http.get_logs(&filter).await;
+        record_rpc_call("eth_getLogs", &response);
+
+        match response {
+            Ok(mut chunk) => {
+                logs.append(&mut chunk);
+                current = end + 1;
+                batch.reset();
+                attempt = 0;
+            }
+            Err(err) => {
+                let msg = err.to_string();
+                let kind = classify_rpc_error(&msg);
+                let mut skipped = current;
+                if attempt >= ingest.retry_times
+                    || !handle_rpc_error(kind, &mut skipped, &mut batch, &mut attempt, ingest.rate_limit_wait_secs).await
+                {
+                    anyhow::bail!("failed to fetch logs for blocks {}..={}: {}", current, end, msg);
+                }
+            }
+        }
+    }
+
+    Ok(logs)
+}
+
+
+/// Indexes admin events from `[from_block, to_block]`, for ranges the fetcher passed before
+/// it tracked them. Returns the number of events newly stored.
+pub async fn backfill_events(pool: Arc<PgPool>, cfg: &AppConfig, from_block: u64, to_block: u64) -> anyhow::Result<u64> {
+    if from_block > to_block {
+        anyhow::bail!("invalid block range {}..={}", from_block, to_block);
+    }
+    let usdc_address: Address = cfg.chain.usdc_contract.parse()?;
+    let provider_http = Provider::<Http>::try_from(cfg.rpc.http.as_str())?;
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+
+    let topics: Vec<H256> = ADMIN_EVENT_SIGS.iter().map(|(_, sig)| H256::from_slice(&keccak256(sig))).collect();
+    let mut stored = 0;
+    let mut last_block: Option<U64> = None;
+    let mut last_block_time: Option<DateTime<Utc>> = None;
+    let mut window_start = from_block;
+    // Each window is stored before the next is fetched, so a failure keeps what came before it.
+    while window_start <= to_block {
+        let window_end = to_block.min(window_start + BACKFILL_BLOCKS_PER_STEP - 1);
+        let logs = fetch_logs(&provider_http, &[usdc_address], &topics, window_start, window_end, &cfg.ingest).await?;
+        for log in logs {
+            let Some(event) = decode_event(&log) else {
+                continue;
+            };
+            if log.block_number != last_block {
+                last_block = log.block_number;
+                last_block_time = get_block_time(&provider_http, &repo, log.block_number).await?;
+            }
+            let Some(block_time) = last_block_time else {
+                anyhow::bail!("block {:?} has no timestamp", log.block_number);
+            };
+            if let ContractEvent::Admin { kind, account, previous_account, amount } = event
+                && let (Some(tx_hash), Some(li), Some(block_number)) = (log.transaction_hash, log.log_index, log.block_number) {
+                let inserted = repo.insert_admin_event(&NewAdminEvent {
+                    tx_hash: format!("{:?}", tx_hash),
+                    log_index: li.as_u64(),
+                    block_number: block_number.as_u64(),
+                    block_time,
+                    kind,
+                    account: account.map(|a| format!("{:?}", a)),
+                    previous_account: previous_account.map(|a| format!("{:?}", a)),
+                    amount,
+                }).await?;
+                stored += inserted as u64;
+            }
+        }
+        info!(through_block = window_end, to_block, stored, "event backfill progress");
+        window_start = window_end + 1;
+    }
+    info!(from_block, to_block, stored, "event backfill finished");
+    Ok(stored)
+}
+
+
+/// topic0 of every event the fetcher indexes.
+fn event_topics() -> Vec<H256> {
+    std::iter::once(TRANSFER_EVENT_SIG)
+        .chain(ADMIN_EVENT_SIGS.iter().map(|(_, sig)| *sig))
+        .map(|sig| H256::from_slice(&keccak256(sig)))
+        .collect()
+}
+
+
+pub(crate) fn decode_event(log: &Log) -> Option<ContractEvent> {
+    let topic0 = *log.topics.first()?;
+    if topic0 == H256::from_slice(&keccak256(TRANSFER_EVENT_SIG)) {
+        let (from, to, amount) = decode_transfer(log)?;
+        return Some(ContractEvent::Transfer { from, to, amount });
+    }
+    let kind = ADMIN_EVENT_SIGS
+        .iter()
+        .find(|(_, sig)| H256::from_slice(&keccak256(sig)) == topic0)?
+        .0;
+
+    // None of these events has an indexed argument after a non-indexed one, so the indexed
+    // topics followed by the data words are the arguments in declaration order. Which ones
+    // are indexed differs between proxy versions.
+    let args: Vec<&[u8]> = log.topics[1..]
+        .iter()
+        .map(|topic| topic.as_bytes())
+        .chain(log.data.chunks(32))
+        .collect();
+    let word = |i: usize| args.get(i).copied().filter(|w| w.len() == 32);
+    let address = |i: usize| word(i).map(|w| Address::from_slice(&w[12..]));
+
+    let (account, previous_account, amount) = match kind {
+        AdminEventKind::Pause | AdminEventKind::Unpause => (None, None, None),
+        AdminEventKind::MinterConfigured => (Some(address(0)?), None, Some(usdc_amount(U256::from_big_endian(word(1)?))?)),
+        AdminEventKind::OwnershipTransferred | AdminEventKind::AdminChanged => (Some(address(1)?), Some(address(0)?), None),
+        _ => (Some(address(0)?), None, None),
+    };
+    Some(ContractEvent::Admin { kind, account, previous_account, amount })
+}
+
+
+pub(crate) fn decode_transfer(log: &Log) -> Option<(Address, Address, Decimal)> {
     if log.topics.len() != 3 {
         return None;
     }
@@ -252,23 +582,79 @@ fn decode_transfer(log: &Log) -> Option<(Address, Address, Decimal)> {
     let to = Address::from_slice(&log.topics[2].as_bytes()[12..]);
     let amount = U256::from_big_endian(&log.data.0);
 
-    let raw_str = amount.to_string();
-    let raw_dec = Decimal::from_str(&raw_str).ok()?;
+    Some((from, to, usdc_amount(amount)?))
+}
+
+
+/// Converts raw token units to USDC; `None` past what `Decimal` can hold.
+pub(crate) fn usdc_amount(raw: U256) -> Option<Decimal> {
+    let raw_dec = Decimal::from_str(&raw.to_string()).ok()?;
     let divisor = Decimal::new(1, 6);
-    let value = raw_dec * divisor;
+    Some(raw_dec * divisor)
+}
+
 
-    Some((from, to, value))
+/// Reads the header of `block_number`, stores it in `blocks` and returns its timestamp; `None`
+/// only for a log without a block number, i.e. a pending one. A failed read or a block the
+/// node does not have yet is an error, so the caller retries instead of dropping its logs.
+pub(crate) async fn get_block_time(
+    provider: &Provider<Http>,
+    repo: &PostgresRepo,
+    block_number: Option<U64>,
+) -> anyhow::Result<Option<DateTime<Utc>>> {
+    let Some(block_number) = block_number else {
+        return Ok(None);
+    };
+    let response = provider.get_block(block_number).await;
+    record_rpc_call("eth_getBlockByNumber", &response);
+    let block = response?.ok_or_else(|| anyhow::anyhow!("block {} not found", block_number))?;
+    let block_time = DateTime::from_timestamp(block.timestamp.as_u64() as i64, 0)
+        .ok_or_else(|| anyhow::anyhow!("block {} has an invalid timestamp", block_number))?;
+
+    if let Some(hash) = block.hash {
+        repo.upsert_block(&NewBlock {
+            number: block_number.as_u64(),
+            hash: format!("{:?}", hash),
+            parent_hash: format!("{:?}", block.parent_hash),
+            block_time,
+        }).await?;
+    }
+    Ok(Some(block_time))
 }
 
 
-async fn get_block_time(provider: &Provider<Http>, block_number: Option<U64>) -> Option<DateTime<Utc>> {
-    if let Some(block_number) = block_number {
-        if let Ok(Some(block)) = provider.get_block(block_number).await {
-            let ts = block.timestamp.as_u64() as i64;
-            return DateTime::from_timestamp(ts, 0);
-        }
+async fn get_block_number(provider: &Provider<Http>) -> anyhow::Result<u64> {
+    let response = provider.get_block_number().await;
+    record_rpc_call("eth_blockNumber", &response);
+    let block_number = response?.as_u64();
+    metrics::observe_chain_head(block_number);
+    ingest::mark_head_read();
+    Ok(block_number)
+}
+
+
+fn provider_host(provider: &Provider<Http>) -> String {
+    provider.url().host_str().unwrap_or_default().to_string()
+}
+
+
+pub(crate) fn record_rpc_call<T>(method: &str, response: &Result<T, ProviderError>) {
+    let outcome = match response {
+        Ok(_) => "ok",
+        Err(err) => classify_rpc_error(&err.to_string()).label(),
+    };
+    metrics::observe_rpc_call(method, outcome);
+}
+
+
+fn record_insert(inserted: bool, block_number: u64, block_time: &DateTime<Utc>) {
+    if inserted {
+        metrics::LOGS_INGESTED.inc();
+    } else {
+        metrics::INSERT_DUPLICATES.inc();
     }
-    None
+    metrics::observe_indexed_block(block_number, block_time.timestamp());
+    ingest::mark_block_processed();
 }
 
 
@@ -291,6 +677,7 @@ async fn handle_rpc_error(
     current: &mut u64,
     batch: &mut BatchSizer,
     attempt: &mut u64,
+    rate_limit_wait_secs: u64,
 ) -> bool {
     use RpcErrorKind::*;
 
@@ -304,10 +691,11 @@ async fn handle_rpc_error(
                     LAST_WAS_BATCH_REDUCTION = false;
                 }
                 else {
-                    sleep(Duration::from_secs(RATE_LIMIT_WAIT_SECS)).await;
+                    sleep(Duration::from_secs(rate_limit_wait_secs)).await;
                 }
             }
             *attempt += 1;
+            metrics::RPC_RETRIES.inc();
             true
         }
 
@@ -316,6 +704,7 @@ async fn handle_rpc_error(
                 batch.halve();
                 unsafe { LAST_WAS_BATCH_REDUCTION = true; }
                 sleep(Duration::from_millis(300)).await;
+                metrics::RPC_RETRIES.inc();
                 true
             } else {
                 *current += 1;
@@ -329,6 +718,7 @@ async fn handle_rpc_error(
             sleep(Duration::from_millis(500)).await;
             *attempt += 1;
             unsafe { LAST_WAS_BATCH_REDUCTION = false; }
+            metrics::RPC_RETRIES.inc();
             true
         }
 
@@ -338,3 +728,106 @@ async fn handle_rpc_error(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USDC: &str = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+    const MINTER: &str = "0x5b6122c109b78c6755486966148c1d70a50a47d7";
+    const OLD_OWNER: &str = "0xfcb19e6a322b27c06842a71e8c725399f049ae3a";
+    const NEW_OWNER: &str = "0xc4922d64a24675e16e1586e3e3aa56c06fabe907";
+
+    fn padded(address: &str) -> H256 {
+        H256::from(address.parse::<Address>().unwrap())
+    }
+
+    fn word(address: &str) -> String {
+        format!("{:x}", padded(address))
+    }
+
+    fn log(sig: &str, topics: &[H256], data: &str) -> Log {
+        Log {
+            address: USDC.parse().unwrap(),
+            topics: std::iter::once(H256::from_slice(&keccak256(sig))).chain(topics.iter().copied()).collect(),
+            data: data.parse().unwrap(),
+            ..Default::default()
+        }
+    }
+
+    fn admin(log: &Log) -> (AdminEventKind, Option<Address>, Option<Address>, Option<Decimal>) {
+        match decode_event(log) {
+            Some(ContractEvent::Admin { kind, account, previous_account, amount }) => (kind, account, previous_account, amount),
+            Some(ContractEvent::Transfer { .. }) => panic!("decoded as a transfer"),
+            None => panic!("not decoded"),
+        }
+    }
+
+    fn address(address: &str) -> Option<Address> {
+        Some(address.parse().unwrap())
+    }
+
+    #[test]
+    fn decodes_minter_configured_with_its_unindexed_allowance() {
+        // 1,000,000 USDC.
+        let allowance = "000000000000000000000000000000000000000000000000000000e8d4a51000";
+        let decoded = admin(&log("MinterConfigured(address,uint256)", &[padded(MINTER)], allowance));
+        assert_eq!(decoded, (AdminEventKind::MinterConfigured, address(MINTER), None, Some(Decimal::new(1_000_000_000_000, 6))));
+    }
+
+    #[test]
+    fn decodes_ownership_transferred_unindexed_and_indexed() {
+        let sig = "OwnershipTransferred(address,address)";
+        let expected = (AdminEventKind::OwnershipTransferred, address(NEW_OWNER), address(OLD_OWNER), None);
+
+        // FiatToken's Ownable declares neither argument indexed.
+        let data = format!("{}{}", word(OLD_OWNER), word(NEW_OWNER));
+        assert_eq!(admin(&log(sig, &[], &data)), expected);
+
+        // OpenZeppelin's Ownable indexes both.
+        assert_eq!(admin(&log(sig, &[padded(OLD_OWNER), padded(NEW_OWNER)], "")), expected);
+    }
+
+    #[test]
+    fn decodes_admin_changed_unindexed_and_indexed() {
+        let sig = "AdminChanged(address,address)";
+        let expected = (AdminEventKind::AdminChanged, address(NEW_OWNER), address(OLD_OWNER), None);
+
+        let data = format!("{}{}", word(OLD_OWNER), word(NEW_OWNER));
+        assert_eq!(admin(&log(sig, &[], &data)), expected);
+        assert_eq!(admin(&log(sig, &[padded(OLD_OWNER), padded(NEW_OWNER)], "")), expected);
+    }
+
+    #[test]
+    fn decodes_single_address_events() {
+        assert_eq!(
+            admin(&log("Blacklisted(address)", &[padded(MINTER)], "")),
+            (AdminEventKind::Blacklisted, address(MINTER), None, None)
+        );
+        assert_eq!(
+            admin(&log("Upgraded(address)", &[], &word(NEW_OWNER))),
+            (AdminEventKind::Upgraded, address(NEW_OWNER), None, None)
+        );
+    }
+
+    #[test]
+    fn decodes_pause_without_arguments() {
+        assert_eq!(admin(&log("Pause()", &[], "")), (AdminEventKind::Pause, None, None, None));
+        assert_eq!(admin(&log("Unpause()", &[], "")), (AdminEventKind::Unpause, None, None, None));
+    }
+
+    #[test]
+    fn rejects_logs_with_too_few_words() {
+        assert!(decode_event(&log("MinterConfigured(address,uint256)", &[padded(MINTER)], "")).is_none());
+        assert!(decode_event(&log("OwnershipTransferred(address,address)", &[], &word(OLD_OWNER))).is_none());
+        assert!(decode_event(&log("Blacklisted(address)", &[], "")).is_none());
+        // A trailing partial word is not an argument.
+        assert!(decode_event(&log("Upgraded(address)", &[], &word(NEW_OWNER)[..62])).is_none());
+    }
+
+    #[test]
+    fn ignores_unknown_events() {
+        assert!(decode_event(&log("Approval(address,address,uint256)", &[padded(MINTER), padded(NEW_OWNER)], "")).is_none());
+        assert!(decode_event(&Log::default()).is_none());
+    }
+}
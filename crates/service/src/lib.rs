@@ -1,12 +1,16 @@
 
+pub mod cctp;
 pub mod fetchers;
+pub mod risk;
+pub mod verify;
 
 pub use common::errors::*;
 
 use std::sync::Arc;
 use anyhow::Result;
+use config::AppConfig;
 use db::PgPool;
 
-pub async fn start_tracker(pool: Arc<PgPool>) -> Result<()> {
-    fetchers::take_and_push_transactions(pool).await
+pub async fn start_tracker(pool: Arc<PgPool>, cfg: &AppConfig) -> Result<()> {
+    fetchers::take_and_push_transactions(pool, cfg).await
 }
@@ -0,0 +1,549 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use chrono::Utc;
+use config::AppConfig;
+use db::{
+    BlacklistedAddress, NewAddressRisk, NewRiskRun, PgPool, PostgresRepo, ReadData, RiskModel, RiskPath, SortOrder, TransferCursor,
+    TransferQuery, TransferSort, UsdcTransfer, WriteData, ZERO_ADDRESS,
+};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, instrument};
+
+const PAGE_SIZE: u32 = 5000;
+const USDC_DECIMALS: u32 = 6;
+const SCORE_DECIMALS: u32 = 8;
+const STATS_CHUNK: usize = 1000;
+
+#[derive(Serialize, Debug)]
+pub struct RiskReport {
+    pub run_id: i64,
+    pub model: RiskModel,
+    pub through_block: u64,
+    pub sources: usize,
+    pub transfers_scanned: u64,
+    pub scored: usize,
+}
+
+/// Taint moving with a transfer, from one source, `hops` transfers away from it.
+#[derive(Clone, Copy, Debug)]
+struct Taint {
+    source: usize,
+    amount: Decimal,
+    hops: u32,
+}
+
+/// Funds of an address that has received enough taint to be followed.
+enum Holdings {
+    /// Tainted amount and fewest hops per source; every transfer out carries the same share of each.
+    Haircut { balance: Decimal, taint: HashMap<usize, (Decimal, u32)> },
+    /// Received lots, oldest first, spent in order; clean lots have no source.
+    Fifo { lots: VecDeque<(Decimal, Option<(usize, u32)>)> },
+}
+
+impl Holdings {
+    /// `balance` is what the address held before it was first tainted, all of it clean.
+    fn new(model: RiskModel, balance: Decimal) -> Self {
+        let balance = balance.max(Decimal::ZERO);
+        match model {
+            RiskModel::Haircut => Holdings::Haircut { balance, taint: HashMap::new() },
+            RiskModel::Fifo => {
+                let mut lots = VecDeque::new();
+                if balance > Decimal::ZERO {
+                    lots.push_back((balance, None));
+                }
+                Holdings::Fifo { lots }
+            }
+        }
+    }
+
+    fn receive(&mut self, amount: Decimal, taint: &[Taint]) {
+        match self {
+            Holdings::Haircut { balance, taint: held } => {
+                *balance += amount;
+                for t in taint {
+                    let (held_amount, hops) = held.entry(t.source).or_insert((Decimal::ZERO, t.hops));
+                    *held_amount += t.amount;
+                    *hops = (*hops).min(t.hops);
+                }
+            }
+            Holdings::Fifo { lots } => {
+                let mut clean = amount;
+                for t in taint {
+                    lots.push_back((t.amount, Some((t.source, t.hops))));
+                    clean -= t.amount;
+                }
+                if clean > Decimal::ZERO {
+                    lots.push_back((clean, None));
+                }
+            }
+        }
+    }
+
+    /// Removes `amount` and returns the taint it carries, one entry per source.
+    fn send(&mut self, amount: Decimal) -> Vec<Taint> {
+        match self {
+            Holdings::Haircut { balance, taint } => {
+                let share = if *balance > amount { amount / *balance } else { Decimal::ONE };
+                *balance = (*balance - amount).max(Decimal::ZERO);
+                let mut sent = Vec::new();
+                for (&source, (held, hops)) in taint.iter_mut() {
+                    let moved = (*held * share).round_dp(USDC_DECIMALS).min(*held);
+                    *held -= moved;
+                    if moved > Decimal::ZERO {
+                        sent.push(Taint { source, amount: moved, hops: *hops });
+                    }
+                }
+                taint.retain(|_, (held, _)| *held > Decimal::ZERO);
+                sent
+            }
+            Holdings::Fifo { lots } => {
+                let mut remaining = amount;
+                let mut sent: Vec<Taint> = Vec::new();
+                while remaining > Decimal::ZERO
+                    && let Some((lot, source)) = lots.front_mut()
+                {
+                    let taken = (*lot).min(remaining);
+                    *lot -= taken;
+                    remaining -= taken;
+                    if let Some((source, hops)) = *source {
+                        match sent.iter_mut().find(|t| t.source == source) {
+                            Some(t) => {
+                                t.amount += taken;
+                                t.hops = t.hops.min(hops);
+                            }
+                            None => sent.push(Taint { source, amount: taken, hops }),
+                        }
+                    }
+                    if lot.is_zero() {
+                        lots.pop_front();
+                    }
+                }
+                sent
+            }
+        }
+    }
+}
+
+/// Taint an address received from one source; `via` sent it the taint that arrived in the fewest hops.
+struct Exposure {
+    amount: Decimal,
+    hops: u32,
+    via: String,
+}
+
+/// Moves taint along transfers applied in chain order.
+struct Tracer {
+    model: RiskModel,
+    max_hops: u32,
+    min_taint: Decimal,
+    /// Index of each source and the block it was blacklisted in.
+    source_index: HashMap<String, (usize, i64)>,
+    holders: HashMap<String, Holdings>,
+    exposures: HashMap<String, HashMap<usize, Exposure>>,
+}
+
+impl Tracer {
+    fn new(model: RiskModel, max_hops: u32, min_taint: Decimal, sources: &[BlacklistedAddress]) -> Self {
+        Self {
+            model,
+            max_hops,
+            min_taint,
+            source_index: sources.iter().enumerate().map(|(i, s)| (s.address.clone(), (i, s.block_number))).collect(),
+            holders: HashMap::new(),
+            exposures: HashMap::new(),
+        }
+    }
+
+    /// Applies `transfer`. When it brings an address that is not followed yet enough taint to
+    /// start, returns that taint; the caller passes it to `follow` with the receiver's balance
+    /// before the transfer.
+    fn apply(&mut self, transfer: &UsdcTransfer) -> Option<Vec<Taint>> {
+        let (from, to) = (&transfer.from_address, &transfer.to_address);
+        if from == to {
+            return None;
+        }
+        let sent = match self.source_index.get(from) {
+            Some(&(source, since)) if transfer.block_number >= since => {
+                vec![Taint { source, amount: transfer.amount, hops: 0 }]
+            }
+            // Sent before the source was blacklisted.
+            Some(_) => Vec::new(),
+            None => self.holders.get_mut(from).map(|h| h.send(transfer.amount)).unwrap_or_default(),
+        };
+        if to == ZERO_ADDRESS || self.source_index.contains_key(to) {
+            return None;
+        }
+        let arriving: Vec<Taint> = sent
+            .into_iter()
+            .filter(|t| t.hops < self.max_hops)
+            .map(|t| Taint { hops: t.hops + 1, ..t })
+            .collect();
+
+        let Some(holdings) = self.holders.get_mut(to) else {
+            let tainted: Decimal = arriving.iter().map(|t| t.amount).sum();
+            return (!arriving.is_empty() && tainted >= self.min_taint).then_some(arriving);
+        };
+        holdings.receive(transfer.amount, &arriving);
+        self.expose(transfer, &arriving);
+        None
+    }
+
+    /// Starts following the receiver of `transfer`, which held `balance` before it.
+    fn follow(&mut self, transfer: &UsdcTransfer, balance: Decimal, arriving: Vec<Taint>) {
+        let mut holdings = Holdings::new(self.model, balance);
+        holdings.receive(transfer.amount, &arriving);
+        self.holders.insert(transfer.to_address.clone(), holdings);
+        self.expose(transfer, &arriving);
+    }
+
+    fn expose(&mut self, transfer: &UsdcTransfer, arriving: &[Taint]) {
+        let from = &transfer.from_address;
+        let received = self.exposures.entry(transfer.to_address.clone()).or_default();
+        for t in arriving {
+            let exposure = received
+                .entry(t.source)
+                .or_insert_with(|| Exposure { amount: Decimal::ZERO, hops: t.hops, via: from.clone() });
+            exposure.amount += t.amount;
+            if t.hops < exposure.hops {
+                exposure.hops = t.hops;
+                exposure.via = from.clone();
+            }
+        }
+    }
+}
+
+/// Scores every address by the share of its received USDC that traces back to a currently
+/// blacklisted address, and replaces the stored scores of `model`.
+///
+/// Everything a blacklisted address sent from the block it was blacklisted in counts as tainted;
+/// what it sent before that is clean. Taint is followed for
+/// `risk.max_hops` transfers, and an address only starts being followed once one transfer
+/// brings it at least `risk.min_taint` USDC of taint; smaller amounts are dropped. Mints and
+/// burns end a path, and blacklisted addresses are scored 1 without paths.
+#[instrument(skip(pool, cfg), err)]
+pub async fn score_addresses(pool: Arc<PgPool>, cfg: &AppConfig, model: RiskModel) -> anyhow::Result<RiskReport> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let started_at = Utc::now();
+    let through_block = repo.get_last_block().await?;
+    let max_hops = cfg.risk.max_hops;
+    let min_taint = Decimal::from(cfg.risk.min_taint);
+
+    let sources = repo.list_blacklisted_addresses().await?;
+    let source_addresses: Vec<String> = sources.iter().map(|s| s.address.clone()).collect();
+    let source_stats = repo.list_address_stats(&source_addresses).await?;
+    let from_block = sources.iter().map(|s| s.block_number).min();
+
+    let mut tracer = Tracer::new(model, max_hops, min_taint, &sources);
+    let mut transfers_scanned = 0u64;
+    let mut after = None;
+
+    while let Some(from_block) = from_block {
+        let page = repo
+            .list_transfers(&TransferQuery {
+                from_block: Some(from_block),
+                to_block: Some(through_block as i64),
+                sort: TransferSort::Time,
+                order: SortOrder::Asc,
+                after,
+                limit: PAGE_SIZE,
+                ..Default::default()
+            })
+            .await?;
+        let Some(last) = page.last() else {
+            break;
+        };
+        after = Some(TransferCursor { amount: None, block_number: last.block_number, log_index: last.log_index });
+        transfers_scanned += page.len() as u64;
+
+        for transfer in &page {
+            if let Some(arriving) = tracer.apply(transfer) {
+                let balance = repo
+                    .get_balance_before(&transfer.to_address, transfer.block_number, transfer.log_index)
+                    .await?;
+                tracer.follow(transfer, balance, arriving);
+            }
+        }
+
+        if page.len() < PAGE_SIZE as usize {
+            break;
+        }
+    }
+
+    let mut scores: Vec<NewAddressRisk> = source_stats
+        .iter()
+        .map(|stats| NewAddressRisk {
+            address: stats.address.clone(),
+            score: Decimal::ONE,
+            tainted_received: stats.total_in,
+            hops: 0,
+            paths: Vec::new(),
+        })
+        .collect();
+
+    let exposures = tracer.exposures;
+    let exposed: Vec<String> = exposures.keys().cloned().collect();
+    for chunk in exposed.chunks(STATS_CHUNK) {
+        for stats in repo.list_address_stats(chunk).await? {
+            let received = &exposures[&stats.address];
+            let tainted_received: Decimal = received.values().map(|e| e.amount).sum();
+            if tainted_received.is_zero() || stats.total_in.is_zero() {
+                continue;
+            }
+            let score = (tainted_received / stats.total_in).min(Decimal::ONE).round_dp(SCORE_DECIMALS);
+            let hops = received.values().map(|e| e.hops).min().unwrap_or_default();
+
+            let mut heaviest: Vec<(&usize, &Exposure)> = received.iter().collect();
+            heaviest.sort_by(|a, b| b.1.amount.cmp(&a.1.amount).then(a.0.cmp(b.0)));
+            let paths = heaviest
+                .into_iter()
+                .take(cfg.risk.max_paths as usize)
+                .map(|(&source, exposure)| RiskPath {
+                    source: sources[source].address.clone(),
+                    amount: exposure.amount,
+                    hops: exposure.hops as i32,
+                    path: trace_path(&exposures, &stats.address, source),
+                })
+                .collect();
+
+            scores.push(NewAddressRisk { address: stats.address, score, tainted_received, hops, paths });
+        }
+    }
+
+    let run_id = repo
+        .replace_address_risk(
+            &NewRiskRun { model, through_block, sources: sources.len() as u32, started_at },
+            &scores,
+        )
+        .await?;
+
+    let report = RiskReport {
+        run_id,
+        model,
+        through_block,
+        sources: sources.len(),
+        transfers_scanned,
+        scored: scores.len(),
+    };
+    info!(
+        run_id,
+        model = model.as_str(),
+        through_block,
+        sources = report.sources,
+        scored = report.scored,
+        "risk scoring finished",
+    );
+    Ok(report)
+}
+
+/// Follows the fewest-hop senders back from `address` to the source. Each sender holds the
+/// source's taint in fewer hops than its receiver, so the walk always ends at the source.
+fn trace_path(exposures: &HashMap<String, HashMap<usize, Exposure>>, address: &str, source: usize) -> Vec<String> {
+    let mut path = vec![address.to_string()];
+    let mut current = address;
+    while let Some(exposure) = exposures.get(current).and_then(|received| received.get(&source)) {
+        path.push(exposure.via.clone());
+        current = &exposure.via;
+    }
+    path.reverse();
+    path
+}
+
+/// Re-scores every address with `risk.model` every `risk.interval_secs`.
+pub async fn run_scheduled_scoring(pool: Arc<PgPool>, cfg: &AppConfig) -> anyhow::Result<()> {
+    let model: RiskModel = cfg.risk.model.parse().map_err(anyhow::Error::msg)?;
+
+    loop {
+        sleep(Duration::from_secs(cfg.risk.interval_secs)).await;
+
+        if let Err(e) = score_addresses(pool.clone(), cfg, model).await {
+            error!(error = %e, model = model.as_str(), "scheduled risk scoring failed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usdc(units: i64) -> Decimal {
+        Decimal::from(units)
+    }
+
+    fn addr(n: u64) -> String {
+        format!("0x{:040x}", n)
+    }
+
+    fn transfer(log_index: i64, from: &str, to: &str, amount: i64) -> UsdcTransfer {
+        UsdcTransfer {
+            id: log_index,
+            tx_hash: format!("0x{:064x}", log_index),
+            log_index,
+            block_number: 100,
+            from_address: from.to_string(),
+            to_address: to.to_string(),
+            amount: usdc(amount),
+            block_time: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Blacklisted from the first block, before every transfer of a test.
+    fn sources(addresses: &[&str]) -> Vec<BlacklistedAddress> {
+        addresses.iter().map(|a| BlacklistedAddress { address: a.to_string(), block_number: 0 }).collect()
+    }
+
+    fn taint(source: usize, amount: Decimal, hops: u32) -> Taint {
+        Taint { source, amount, hops }
+    }
+
+    /// `(source, amount, hops)` of each entry, by source.
+    fn sorted(mut taint: Vec<Taint>) -> Vec<(usize, Decimal, u32)> {
+        taint.sort_by_key(|t| t.source);
+        taint.into_iter().map(|t| (t.source, t.amount, t.hops)).collect()
+    }
+
+    /// Applies `transfers` in order, following new receivers with a zero prior balance.
+    fn trace(tracer: &mut Tracer, transfers: &[UsdcTransfer]) {
+        for transfer in transfers {
+            if let Some(arriving) = tracer.apply(transfer) {
+                tracer.follow(transfer, Decimal::ZERO, arriving);
+            }
+        }
+    }
+
+    #[test]
+    fn haircut_sends_the_tainted_share_of_the_balance() {
+        // 100 clean, then 100 from source 0: half of every transfer out is tainted.
+        let mut holdings = Holdings::new(RiskModel::Haircut, usdc(100));
+        holdings.receive(usdc(100), &[taint(0, usdc(100), 1)]);
+
+        assert_eq!(sorted(holdings.send(usdc(50))), vec![(0, usdc(25), 1)]);
+        // 150 left with 75 tainted; sending everything takes all of it.
+        assert_eq!(sorted(holdings.send(usdc(150))), vec![(0, usdc(75), 1)]);
+        assert!(holdings.send(usdc(10)).is_empty());
+    }
+
+    #[test]
+    fn haircut_rounds_to_usdc_units_without_losing_taint() {
+        let mut holdings = Holdings::new(RiskModel::Haircut, Decimal::ZERO);
+        holdings.receive(usdc(3), &[taint(0, usdc(1), 1)]);
+
+        // A third of 1 USDC, rounded to six decimals.
+        assert_eq!(sorted(holdings.send(usdc(1))), vec![(0, Decimal::new(333_333, 6), 1)]);
+        assert_eq!(sorted(holdings.send(usdc(2))), vec![(0, Decimal::new(666_667, 6), 1)]);
+    }
+
+    #[test]
+    fn haircut_splits_between_sources_and_keeps_the_fewest_hops() {
+        let mut holdings = Holdings::new(RiskModel::Haircut, Decimal::ZERO);
+        holdings.receive(usdc(40), &[taint(0, usdc(30), 3), taint(1, usdc(10), 1)]);
+        holdings.receive(usdc(60), &[taint(0, usdc(10), 2)]);
+
+        // 40 of source 0 and 10 of source 1 in a balance of 100.
+        assert_eq!(sorted(holdings.send(usdc(20))), vec![(0, usdc(8), 2), (1, usdc(2), 1)]);
+    }
+
+    #[test]
+    fn fifo_spends_the_oldest_lots_first() {
+        let mut holdings = Holdings::new(RiskModel::Fifo, usdc(50));
+        holdings.receive(usdc(30), &[taint(0, usdc(30), 1)]);
+        // Only part of this transfer is tainted; the tainted lot comes first.
+        holdings.receive(usdc(40), &[taint(1, usdc(20), 2)]);
+
+        // The clean 50, then 10 of source 0.
+        assert_eq!(sorted(holdings.send(usdc(60))), vec![(0, usdc(10), 1)]);
+        // The other 20 of source 0 and 10 of source 1.
+        assert_eq!(sorted(holdings.send(usdc(30))), vec![(0, usdc(20), 1), (1, usdc(10), 2)]);
+        // 10 of source 1, then the clean 20.
+        assert_eq!(sorted(holdings.send(usdc(25))), vec![(1, usdc(10), 2)]);
+        // Sending more than is held only spends what is left.
+        assert!(holdings.send(usdc(100)).is_empty());
+    }
+
+    #[test]
+    fn fifo_merges_lots_of_one_source() {
+        let mut holdings = Holdings::new(RiskModel::Fifo, Decimal::ZERO);
+        holdings.receive(usdc(5), &[taint(0, usdc(5), 3)]);
+        holdings.receive(usdc(5), &[taint(0, usdc(5), 1)]);
+
+        assert_eq!(sorted(holdings.send(usdc(10))), vec![(0, usdc(10), 1)]);
+    }
+
+    #[test]
+    fn taint_below_min_taint_is_not_followed() {
+        let (source, small, large) = (addr(1), addr(2), addr(3));
+        let mut tracer = Tracer::new(RiskModel::Haircut, 5, usdc(10), &sources(&[&source]));
+
+        trace(&mut tracer, &[
+            transfer(0, &source, &small, 9),
+            transfer(1, &source, &large, 10),
+            // Passes nothing on: `small` is not followed.
+            transfer(2, &small, &addr(4), 9),
+        ]);
+
+        assert!(!tracer.exposures.contains_key(&small));
+        assert!(!tracer.exposures.contains_key(&addr(4)));
+        assert_eq!(tracer.exposures[&large][&0].amount, usdc(10));
+        // Once followed, an address takes any taint, however small.
+        trace(&mut tracer, &[transfer(3, &source, &large, 1)]);
+        assert_eq!(tracer.exposures[&large][&0].amount, usdc(11));
+    }
+
+    #[test]
+    fn transfers_before_the_blacklisting_are_clean() {
+        let (source, early, late) = (addr(1), addr(2), addr(3));
+        let blacklisted = [BlacklistedAddress { address: source.clone(), block_number: 100 }];
+        let mut tracer = Tracer::new(RiskModel::Haircut, 5, Decimal::ZERO, &blacklisted);
+
+        let mut before = transfer(0, &source, &early, 50);
+        before.block_number = 99;
+        trace(&mut tracer, &[before, transfer(1, &source, &late, 20)]);
+
+        assert!(!tracer.exposures.contains_key(&early));
+        assert_eq!(tracer.exposures[&late][&0].amount, usdc(20));
+    }
+
+    #[test]
+    fn taint_stops_after_max_hops_and_at_burns_and_sources() {
+        let (source, other_source, a, b, c) = (addr(1), addr(2), addr(3), addr(4), addr(5));
+        let mut tracer = Tracer::new(RiskModel::Fifo, 2, Decimal::ZERO, &sources(&[&source, &other_source]));
+
+        trace(&mut tracer, &[
+            transfer(0, &source, &a, 100),
+            transfer(1, &a, &b, 60),
+            // Three hops from the source.
+            transfer(2, &b, &c, 60),
+            transfer(3, &a, ZERO_ADDRESS, 20),
+            transfer(4, &a, &other_source, 20),
+        ]);
+
+        assert_eq!((tracer.exposures[&a][&0].amount, tracer.exposures[&a][&0].hops), (usdc(100), 1));
+        assert_eq!((tracer.exposures[&b][&0].amount, tracer.exposures[&b][&0].hops), (usdc(60), 2));
+        assert!(!tracer.exposures.contains_key(&c));
+        assert!(!tracer.exposures.contains_key(ZERO_ADDRESS));
+        assert!(!tracer.exposures.contains_key(&other_source));
+    }
+
+    #[test]
+    fn trace_path_follows_the_fewest_hops_back_to_the_source() {
+        let (source, a, b, c) = (addr(1), addr(2), addr(3), addr(4));
+        let mut tracer = Tracer::new(RiskModel::Haircut, 10, Decimal::ZERO, &sources(&[&source]));
+
+        trace(&mut tracer, &[
+            transfer(0, &source, &a, 100),
+            transfer(1, &a, &b, 50),
+            transfer(2, &b, &c, 50),
+            // A shorter route to `c` replaces the one through `b`.
+            transfer(3, &source, &c, 1),
+            // Taint cycling back to `a` does not make a loop.
+            transfer(4, &c, &a, 10),
+        ]);
+
+        assert_eq!(trace_path(&tracer.exposures, &b, 0), vec![source.clone(), a.clone(), b.clone()]);
+        assert_eq!(trace_path(&tracer.exposures, &c, 0), vec![source.clone(), c.clone()]);
+        assert_eq!(trace_path(&tracer.exposures, &a, 0), vec![source, a]);
+    }
+}
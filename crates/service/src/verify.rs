@@ -0,0 +1,338 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use chrono::Utc;
+use config::AppConfig;
+use ethers::prelude::*;
+use ethers::utils::keccak256;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::time::{sleep, Duration};
+use tracing::{error, info, instrument, warn};
+use db::{NewTransfer, NewVerificationRun, PgPool, PostgresRepo, ReadData, UsdcTransfer, WriteData};
+
+use crate::fetchers::{decode_transfer, fetch_logs, get_block_time, TRANSFER_EVENT_SIG};
+
+#[derive(Serialize, Debug)]
+pub struct TransferKey {
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct OnchainTransfer {
+    pub tx_hash: String,
+    pub log_index: u64,
+    pub block_number: u64,
+    pub from_address: String,
+    pub to_address: String,
+    pub amount: Decimal,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Mismatch {
+    pub onchain: OnchainTransfer,
+    pub stored: UsdcTransfer,
+}
+
+#[derive(Serialize, Debug)]
+pub struct VerifyReport {
+    pub run_id: Option<i64>,
+    pub from_block: u64,
+    pub to_block: u64,
+    pub onchain_count: usize,
+    pub stored_count: usize,
+    pub missing: Vec<OnchainTransfer>,
+    pub extra: Vec<TransferKey>,
+    pub mismatched: Vec<Mismatch>,
+    pub repaired: bool,
+}
+
+impl VerifyReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// Re-fetches the transfer logs of `[from_block, to_block]` from RPC and diffs them
+/// against `usdc_transfers` on `(tx_hash, log_index)`. With `repair` set, missing and
+/// mismatched rows are written from chain data and extra rows are deleted.
+#[instrument(skip(pool, cfg), err)]
+pub async fn verify_range(
+    pool: Arc<PgPool>,
+    cfg: &AppConfig,
+    from_block: u64,
+    to_block: u64,
+    repair: bool,
+) -> anyhow::Result<VerifyReport> {
+    let usdc_address: Address = cfg.chain.usdc_contract.parse()?;
+    let provider_http = Provider::<Http>::try_from(cfg.rpc.http.as_str())?;
+    let transfer_topic = H256::from_slice(&keccak256(TRANSFER_EVENT_SIG));
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+
+    if from_block > to_block {
+        anyhow::bail!("invalid block range {}..={}", from_block, to_block);
+    }
+
+    let started_at = Utc::now();
+
+    let logs = fetch_logs(&provider_http, &[usdc_address], &[transfer_topic], from_block, to_block, &cfg.ingest).await?;
+    let mut onchain: BTreeMap<(String, u64), OnchainTransfer> = BTreeMap::new();
+    for log in logs {
+        if let Some((from, to, amount)) = decode_transfer(&log)
+            && let (Some(tx_hash), Some(li), Some(block_number)) = (log.transaction_hash, log.log_index, log.block_number) {
+            let transfer = OnchainTransfer {
+                tx_hash: format!("{:?}", tx_hash),
+                log_index: li.as_u64(),
+                block_number: block_number.as_u64(),
+                from_address: format!("{:?}", from),
+                to_address: format!("{:?}", to),
+                amount,
+            };
+            onchain.insert((transfer.tx_hash.clone(), transfer.log_index), transfer);
+        }
+    }
+    let onchain_count = onchain.len();
+
+    let stored_rows = repo.list_transfers_in_blocks(from_block, to_block).await?;
+    let stored_count = stored_rows.len();
+    let (missing, extra, mismatched) = classify(onchain, stored_rows);
+
+    let mut report = VerifyReport {
+        run_id: None,
+        from_block,
+        to_block,
+        onchain_count,
+        stored_count,
+        missing,
+        extra,
+        mismatched,
+        repaired: false,
+    };
+
+    if repair && !report.is_clean() {
+        for transfer in report.missing.iter().chain(report.mismatched.iter().map(|m| &m.onchain)) {
+            let block_time = get_block_time(&provider_http, &repo, Some(U64::from(transfer.block_number)))
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("block {} has no timestamp", transfer.block_number))?;
+            repo.upsert_transfer(&NewTransfer {
+                tx_hash: transfer.tx_hash.clone(),
+                log_index: transfer.log_index,
+                block_number: transfer.block_number,
+                from_address: transfer.from_address.clone(),
+                to_address: transfer.to_address.clone(),
+                amount: transfer.amount,
+                block_time,
+            }).await?;
+        }
+        for key in &report.extra {
+            repo.delete_transfer(&key.tx_hash, key.log_index).await?;
+        }
+        report.repaired = true;
+    }
+
+    let run_id = repo.insert_verification_run(&NewVerificationRun {
+        from_block,
+        to_block,
+        missing: report.missing.len() as u64,
+        extra: report.extra.len() as u64,
+        mismatched: report.mismatched.len() as u64,
+        repaired: report.repaired,
+        details: serde_json::to_value(&report)?,
+        started_at,
+    }).await?;
+    report.run_id = Some(run_id);
+
+    if report.is_clean() {
+        info!(run_id, onchain_count, stored_count, "block range verified");
+    } else {
+        warn!(
+            run_id,
+            missing = report.missing.len(),
+            extra = report.extra.len(),
+            mismatched = report.mismatched.len(),
+            repaired = report.repaired,
+            "block range differs from chain",
+        );
+    }
+
+    Ok(report)
+}
+
+/// Re-verifies `verify.last_blocks` indexed blocks every `verify.interval_secs`, ending
+/// `verify.confirmations` blocks behind the indexed head.
+pub async fn run_rolling_verification(pool: Arc<PgPool>, cfg: &AppConfig) -> anyhow::Result<()> {
+    let repo = PostgresRepo::new(pool.as_ref().clone());
+    let (interval_secs, repair) = (cfg.verify.interval_secs, cfg.verify.repair);
+
+    loop {
+        sleep(Duration::from_secs(interval_secs)).await;
+
+        let indexed_head = match repo.get_last_block().await {
+            Ok(block) => block,
+            Err(e) => {
+                error!(error = %e, "rolling verification could not read the last indexed block");
+                continue;
+            }
+        };
+        let Some((from_block, to_block)) = rolling_window(indexed_head, cfg.verify.last_blocks, cfg.verify.confirmations) else {
+            continue;
+        };
+
+        if let Err(e) = verify_range(pool.clone(), cfg, from_block, to_block, repair).await {
+            error!(error = %e, from_block, to_block, "rolling verification failed");
+        }
+    }
+}
+
+/// The `last_blocks` blocks ending `confirmations` blocks behind `indexed_head`, or `None`
+/// while nothing that deep has been indexed. The head itself may not be served by the HTTP
+/// node yet, which would make its stored rows look extra.
+fn rolling_window(indexed_head: u64, last_blocks: u64, confirmations: u64) -> Option<(u64, u64)> {
+    let to_block = indexed_head.checked_sub(confirmations).filter(|&block| block > 0)?;
+    Some((to_block.saturating_sub(last_blocks.saturating_sub(1)), to_block))
+}
+
+/// Pairs stored rows with chain transfers on `(tx_hash, log_index)`: chain transfers left
+/// unpaired are missing, stored rows without one are extra, and pairs that differ are
+/// mismatched.
+fn classify(
+    mut onchain: BTreeMap<(String, u64), OnchainTransfer>,
+    stored_rows: Vec<UsdcTransfer>,
+) -> (Vec<OnchainTransfer>, Vec<TransferKey>, Vec<Mismatch>) {
+    let mut extra = Vec::new();
+    let mut mismatched = Vec::new();
+    for stored in stored_rows {
+        let key = (stored.tx_hash.trim().to_string(), stored.log_index as u64);
+        match onchain.remove(&key) {
+            Some(chain) if matches(&chain, &stored) => {}
+            Some(chain) => mismatched.push(Mismatch { onchain: chain, stored }),
+            None => extra.push(TransferKey {
+                tx_hash: key.0,
+                log_index: key.1,
+                block_number: stored.block_number as u64,
+            }),
+        }
+    }
+    (onchain.into_values().collect(), extra, mismatched)
+}
+
+fn matches(chain: &OnchainTransfer, stored: &UsdcTransfer) -> bool {
+    chain.block_number == stored.block_number as u64
+        && chain.amount == stored.amount
+        && chain.from_address.eq_ignore_ascii_case(stored.from_address.trim())
+        && chain.to_address.eq_ignore_ascii_case(stored.to_address.trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FROM: &str = "0x28c6c06298d514db089934071355e5743bf21d60";
+    const TO: &str = "0x7f367cc41522ce07553e823bf3be79a889debe1b";
+
+    fn tx(n: u8) -> String {
+        format!("0x{}", format!("{:02x}", n).repeat(32))
+    }
+
+    fn onchain(n: u8, log_index: u64, amount: i64) -> OnchainTransfer {
+        OnchainTransfer {
+            tx_hash: tx(n),
+            log_index,
+            block_number: 100,
+            from_address: FROM.to_string(),
+            to_address: TO.to_string(),
+            amount: Decimal::new(amount, 6),
+        }
+    }
+
+    fn stored(n: u8, log_index: i64, amount: i64) -> UsdcTransfer {
+        UsdcTransfer {
+            id: 1,
+            tx_hash: tx(n),
+            log_index,
+            block_number: 100,
+            from_address: FROM.to_string(),
+            to_address: TO.to_string(),
+            amount: Decimal::new(amount, 6),
+            block_time: Utc::now(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn by_key(transfers: Vec<OnchainTransfer>) -> BTreeMap<(String, u64), OnchainTransfer> {
+        transfers.into_iter().map(|t| ((t.tx_hash.clone(), t.log_index), t)).collect()
+    }
+
+    #[test]
+    fn matches_ignores_address_case_and_char_padding() {
+        let mut row = stored(1, 0, 1_500_000);
+        row.from_address = FROM.to_uppercase().replace("0X", "0x");
+        row.to_address = format!("{} ", TO);
+        assert!(matches(&onchain(1, 0, 1_500_000), &row));
+    }
+
+    #[test]
+    fn matches_compares_amount_block_and_both_sides() {
+        let chain = onchain(1, 0, 1_500_000);
+        assert!(!matches(&chain, &stored(1, 0, 1_500_001)));
+
+        let mut row = stored(1, 0, 1_500_000);
+        row.block_number = 101;
+        assert!(!matches(&chain, &row));
+
+        let mut row = stored(1, 0, 1_500_000);
+        row.from_address = TO.to_string();
+        assert!(!matches(&chain, &row));
+
+        let mut row = stored(1, 0, 1_500_000);
+        row.to_address = FROM.to_string();
+        assert!(!matches(&chain, &row));
+    }
+
+    #[test]
+    fn rolling_window_ends_confirmations_behind_the_indexed_head() {
+        assert_eq!(rolling_window(1_000, 100, 12), Some((889, 988)));
+        assert_eq!(rolling_window(1_000, 100, 0), Some((901, 1_000)));
+        assert_eq!(rolling_window(50, 100, 12), Some((0, 38)));
+        assert_eq!(rolling_window(12, 100, 12), None);
+        assert_eq!(rolling_window(5, 100, 12), None);
+        assert_eq!(rolling_window(0, 100, 0), None);
+    }
+
+    #[test]
+    fn classify_splits_missing_extra_and_mismatched() {
+        let chain = by_key(vec![onchain(1, 0, 10), onchain(1, 1, 20), onchain(2, 0, 30)]);
+        let rows = vec![stored(1, 0, 10), stored(1, 1, 21), stored(3, 4, 40)];
+
+        let (missing, extra, mismatched) = classify(chain, rows);
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!((missing[0].tx_hash.as_str(), missing[0].log_index), (tx(2).as_str(), 0));
+        assert_eq!(extra.len(), 1);
+        assert_eq!((extra[0].tx_hash.as_str(), extra[0].log_index, extra[0].block_number), (tx(3).as_str(), 4, 100));
+        assert_eq!(mismatched.len(), 1);
+        assert_eq!(mismatched[0].onchain.amount, Decimal::new(20, 6));
+        assert_eq!(mismatched[0].stored.amount, Decimal::new(21, 6));
+    }
+
+    #[test]
+    fn classify_pairs_padded_stored_hashes() {
+        let mut row = stored(1, 0, 10);
+        row.tx_hash = format!("{}  ", row.tx_hash);
+
+        let (missing, extra, mismatched) = classify(by_key(vec![onchain(1, 0, 10)]), vec![row]);
+
+        assert!(missing.is_empty() && extra.is_empty() && mismatched.is_empty());
+    }
+
+    #[test]
+    fn classify_same_tx_different_log_index_is_not_a_match() {
+        let (missing, extra, mismatched) = classify(by_key(vec![onchain(1, 0, 10)]), vec![stored(1, 1, 10)]);
+
+        assert_eq!(missing.len(), 1);
+        assert_eq!(extra.len(), 1);
+        assert!(mismatched.is_empty());
+    }
+}
@@ -0,0 +1,42 @@
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+
+use chrono::Utc;
+
+static WS_CONNECTED: AtomicBool = AtomicBool::new(false);
+static LAST_PROCESSED_AT: AtomicI64 = AtomicI64::new(0);
+static HEAD_READ_AT: AtomicI64 = AtomicI64::new(0);
+
+/// Whether the live log subscription is currently open in this process.
+pub fn set_ws_connected(connected: bool) {
+    WS_CONNECTED.store(connected, Ordering::SeqCst);
+}
+
+pub fn ws_connected() -> bool {
+    WS_CONNECTED.load(Ordering::SeqCst)
+}
+
+/// Records the wall-clock time at which the indexer last finished a block.
+pub fn mark_block_processed() {
+    LAST_PROCESSED_AT.store(Utc::now().timestamp(), Ordering::SeqCst);
+}
+
+/// Seconds since a block was last processed, or `None` if none was processed yet.
+pub fn seconds_since_last_block() -> Option<i64> {
+    match LAST_PROCESSED_AT.load(Ordering::SeqCst) {
+        0 => None,
+        at => Some((Utc::now().timestamp() - at).max(0)),
+    }
+}
+
+/// Records the wall-clock time at which the chain head was last read from the node.
+pub fn mark_head_read() {
+    HEAD_READ_AT.store(Utc::now().timestamp(), Ordering::SeqCst);
+}
+
+/// Seconds since the chain head was last read, or `None` if it was never read.
+pub fn seconds_since_head_read() -> Option<i64> {
+    match HEAD_READ_AT.load(Ordering::SeqCst) {
+        0 => None,
+        at => Some((Utc::now().timestamp() - at).max(0)),
+    }
+}
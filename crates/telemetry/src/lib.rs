@@ -0,0 +1,3 @@
+pub mod ingest;
+pub mod logging;
+pub mod metrics;
@@ -0,0 +1,140 @@
+use chrono::Utc;
+use once_cell::sync::Lazy;
+use prometheus::{
+    Encoder, IntCounter, IntCounterVec, IntGauge, Opts, Registry, TextEncoder,
+};
+
+pub static REGISTRY: Lazy<Registry> = Lazy::new(Registry::new);
+
+pub static CHAIN_HEAD: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("usdc_chain_head_block", "Latest block number seen on the chain").unwrap())
+});
+
+pub static INDEXED_BLOCK: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("usdc_indexed_block", "Latest block number written to the database").unwrap())
+});
+
+pub static INDEXED_BLOCK_TIMESTAMP: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new(
+        "usdc_indexed_block_timestamp_seconds",
+        "Unix timestamp of the latest block written to the database",
+    ).unwrap())
+});
+
+pub static LAG_BLOCKS: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("usdc_lag_blocks", "Chain head minus indexed block").unwrap())
+});
+
+pub static LAG_SECONDS: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("usdc_lag_seconds", "Seconds since the timestamp of the latest indexed block").unwrap())
+});
+
+pub static LOGS_INGESTED: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new(
+        "usdc_logs_ingested_total",
+        "Transfer logs inserted into the database; use rate() for logs per second",
+    ).unwrap())
+});
+
+pub static INSERT_DUPLICATES: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new(
+        "usdc_insert_duplicates_total",
+        "Transfer logs skipped because (tx_hash, log_index) was already stored",
+    ).unwrap())
+});
+
+pub static RPC_CALLS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("usdc_rpc_calls_total", "RPC calls by method and outcome"),
+        &["method", "outcome"],
+    ).unwrap())
+});
+
+pub static BATCH_SIZE: Lazy<IntGauge> = Lazy::new(|| {
+    register(IntGauge::new("usdc_batch_size_blocks", "Current BatchSizer window in blocks").unwrap())
+});
+
+pub static RPC_RETRIES: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("usdc_rpc_retries_total", "RPC requests retried after an error").unwrap())
+});
+
+pub static SKIPPED_BLOCKS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("usdc_skipped_blocks_total", "Blocks skipped after exhausting retries").unwrap())
+});
+
+pub static WS_RECONNECTS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("usdc_ws_reconnects_total", "WebSocket subscription reconnects").unwrap())
+});
+
+pub static HISTORICAL_SYNC_FAILURES: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("usdc_historical_sync_failures_total", "Startup historical syncs that failed and were retried").unwrap())
+});
+
+pub static REMOVED_LOGS: Lazy<IntCounter> = Lazy::new(|| {
+    register(IntCounter::new("usdc_removed_logs_total", "Stored logs deleted after the node reported them removed by a reorg").unwrap())
+});
+
+pub static API_KEY_REJECTIONS: Lazy<IntCounterVec> = Lazy::new(|| {
+    register(IntCounterVec::new(
+        Opts::new("usdc_api_key_rejections_total", "API requests refused by key checks, by reason"),
+        &["reason"],
+    ).unwrap())
+});
+
+fn register<T: prometheus::core::Collector + Clone + 'static>(collector: T) -> T {
+    REGISTRY.register(Box::new(collector.clone())).unwrap();
+    collector
+}
+
+/// Records a chain head read from the node, ignoring values older than the current one.
+pub fn observe_chain_head(block: u64) {
+    if block as i64 > CHAIN_HEAD.get() {
+        CHAIN_HEAD.set(block as i64);
+    }
+}
+
+/// Records the latest block written to the database together with its timestamp.
+pub fn observe_indexed_block(block: u64, timestamp: i64) {
+    if block as i64 >= INDEXED_BLOCK.get() {
+        INDEXED_BLOCK.set(block as i64);
+        INDEXED_BLOCK_TIMESTAMP.set(timestamp);
+    }
+}
+
+pub fn observe_rpc_call(method: &str, outcome: &str) {
+    RPC_CALLS.with_label_values(&[method, outcome]).inc();
+}
+
+pub fn observe_api_key_rejection(reason: &str) {
+    API_KEY_REJECTIONS.with_label_values(&[reason]).inc();
+}
+
+/// Renders every metric in the Prometheus text format, refreshing the lag gauges first.
+pub fn render() -> String {
+    Lazy::force(&CHAIN_HEAD);
+    Lazy::force(&INDEXED_BLOCK);
+    Lazy::force(&INDEXED_BLOCK_TIMESTAMP);
+    Lazy::force(&LAG_BLOCKS);
+    Lazy::force(&LAG_SECONDS);
+    Lazy::force(&LOGS_INGESTED);
+    Lazy::force(&INSERT_DUPLICATES);
+    Lazy::force(&RPC_CALLS);
+    Lazy::force(&BATCH_SIZE);
+    Lazy::force(&RPC_RETRIES);
+    Lazy::force(&SKIPPED_BLOCKS);
+    Lazy::force(&WS_RECONNECTS);
+    Lazy::force(&HISTORICAL_SYNC_FAILURES);
+    Lazy::force(&REMOVED_LOGS);
+    Lazy::force(&API_KEY_REJECTIONS);
+
+    if INDEXED_BLOCK.get() > 0 {
+        LAG_BLOCKS.set((CHAIN_HEAD.get() - INDEXED_BLOCK.get()).max(0));
+        LAG_SECONDS.set((Utc::now().timestamp() - INDEXED_BLOCK_TIMESTAMP.get()).max(0));
+    }
+
+    let mut buffer = Vec::new();
+    TextEncoder::new()
+        .encode(&REGISTRY.gather(), &mut buffer)
+        .unwrap();
+    String::from_utf8(buffer).unwrap()
+}